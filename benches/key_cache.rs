@@ -0,0 +1,51 @@
+//! Key extraction with and without `--key-cache` on a stream that repeats its payloads, as a
+//! looping replay does. Run with `cargo bench --bench key_cache`.
+
+use std::{hint::black_box, time::Instant};
+
+use serde_json::{Value, json};
+use tracker::prelude::*;
+
+const MESSAGES: usize = 200_000;
+const DISTINCT: usize = 500;
+
+fn messages() -> Vec<Value> {
+    (0..MESSAGES)
+        .map(|i| {
+            let n = i % DISTINCT;
+            json!({
+                "event": format!("game.round.{n}.settled"),
+                "data": {"players": (0..20).map(|p| json!({"id": p, "score": n * p})).collect::<Vec<_>>()}
+            })
+        })
+        .collect()
+}
+
+fn run(name: &str, extractor: &dyn AlignmentKeyExtractor, messages: &[Value]) {
+    let started = Instant::now();
+    let found = messages.iter().filter_map(|message| extractor.extract_key(black_box(message))).count();
+    let elapsed = started.elapsed();
+    println!(
+        "{name:<24} {:>8.1} ms  {:>6.0} ns/message  ({found} keys)",
+        elapsed.as_secs_f64() * 1e3,
+        elapsed.as_nanos() as f64 / messages.len() as f64
+    );
+}
+
+fn main() {
+    let messages = messages();
+    let pattern = r"^game\.round\.(\d+)\.";
+
+    run("field", &JsonPathExtractor::new("event"), &messages);
+    run("regex", &RegexExtractor::new("event", pattern).unwrap(), &messages);
+    for capacity in [DISTINCT / 2, DISTINCT] {
+        let cached = CachingExtractor::new(RegexExtractor::new("event", pattern).unwrap(), capacity);
+        run(&format!("regex, cache {capacity}"), &cached, &messages);
+    }
+    run("hash", &HashExtractor::new(vec!["/data/players/*/score".to_string()]), &messages);
+    run(
+        &format!("hash, cache {DISTINCT}"),
+        &CachingExtractor::new(HashExtractor::new(vec!["/data/players/*/score".to_string()]), DISTINCT),
+        &messages
+    );
+}
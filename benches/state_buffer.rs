@@ -0,0 +1,48 @@
+//! Pushing into a full state buffer, where every push evicts the oldest state: `StateBuffer`
+//! (a `VecDeque`) against the `Vec` with `remove(0)` it replaced. Run with
+//! `cargo bench --bench state_buffer`.
+
+use std::{hint::black_box, time::Instant};
+
+use serde_json::json;
+use tracker::prelude::*;
+
+const PUSHES: usize = 200_000;
+
+fn fresh_states() -> Vec<State> {
+    (0..PUSHES).map(|i| State::new(json!({"type": "tick", "seq": i}), Some("tick".to_string()))).collect()
+}
+
+fn report(name: &str, capacity: usize, started: Instant) {
+    let elapsed = started.elapsed();
+    println!(
+        "{name:<12} capacity {capacity:>6}  {:>8.1} ms  {:>6.0} ns/push",
+        elapsed.as_secs_f64() * 1e3,
+        elapsed.as_nanos() as f64 / PUSHES as f64
+    );
+}
+
+fn main() {
+    for capacity in [100, 1_000, 10_000] {
+        let states = fresh_states();
+        let started = Instant::now();
+        let mut buffer = StateBuffer::new(capacity);
+        for state in states {
+            buffer.push(black_box(state));
+        }
+        black_box(buffer.latest());
+        report("VecDeque", capacity, started);
+
+        let states = fresh_states();
+        let started = Instant::now();
+        let mut buffer = Vec::with_capacity(capacity + 1);
+        for state in states {
+            buffer.push(black_box(state));
+            if buffer.len() > capacity {
+                buffer.remove(0);
+            }
+        }
+        black_box(buffer.last());
+        report("Vec", capacity, started);
+    }
+}
@@ -0,0 +1,325 @@
+use std::{collections::HashMap, fmt};
+
+use anstream::println;
+use json_patch::{AddOperation, Patch, PatchOperation, RemoveOperation, jsonptr::PointerBuf};
+use owo_colors::OwoColorize;
+use serde_json::Value as JsonValue;
+
+use super::{Theme, print_json_line};
+use crate::{
+    domain::{DiffEvent, DiffResult, navigate, nest_patch, parse_pointer, prune},
+    port::Differ
+};
+
+/// Differ for snapshots holding an array of entities with a stable id (e.g., `/entities` keyed by
+/// `entity_id`): entities are paired by id instead of position, and each changed entity gets its
+/// own diff section. The rest of the document is diffed as usual by the wrapped differ.
+pub struct EntityDiffer<D: Differ> {
+    /// JSON Pointer to the entity array, split into segments
+    path:       Vec<String>,
+    /// Field identifying an entity within the array
+    id_field:   String,
+    /// Print one JSON line per comparison (for `--engine json`) instead of per-entity sections
+    json_lines: bool,
+    theme:      Theme,
+    inner:      D
+}
+
+/// What identifies an entity. Ids keep their JSON type, so `1` and `"1"` are different entities,
+/// and entities without the id field are keyed by position apart from any id.
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum EntityId {
+    Text(String),
+    /// Any other id value, as compact JSON
+    Value(String),
+    Position(usize)
+}
+
+impl fmt::Display for EntityId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntityId::Text(id) | EntityId::Value(id) => write!(f, "{id}"),
+            EntityId::Position(i) => write!(f, "[{i}]")
+        }
+    }
+}
+
+/// How an entity of either side was paired
+enum Pairing<'a> {
+    /// On both sides; `index` is its position on the left
+    Both {
+        id:    &'a EntityId,
+        index: usize,
+        left:  &'a JsonValue,
+        right: &'a JsonValue
+    },
+    LeftOnly {
+        id:    &'a EntityId,
+        index: usize
+    },
+    RightOnly {
+        id:    &'a EntityId,
+        right: &'a JsonValue
+    }
+}
+
+impl<D: Differ> EntityDiffer<D> {
+    pub fn new(pointer: &str, id_field: &str, inner: D) -> Self {
+        Self {
+            path: parse_pointer(pointer),
+            id_field: id_field.to_string(),
+            json_lines: false,
+            theme: Theme::default(),
+            inner
+        }
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Print each comparison as a single JSON line holding the id-paired patch, so stdout stays
+    /// valid JSONL; the per-entity headings are left out
+    pub fn with_json_lines(mut self, json_lines: bool) -> Self {
+        self.json_lines = json_lines;
+        self
+    }
+
+    /// Entities by id, in array order; an entity without the id field is keyed by its position
+    fn entities<'a>(&self, items: &'a [JsonValue]) -> Vec<(EntityId, &'a JsonValue)> {
+        items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let id = match item.get(&self.id_field) {
+                    Some(JsonValue::String(id)) => EntityId::Text(id.clone()),
+                    Some(id) => EntityId::Value(id.to_string()),
+                    None => EntityId::Position(i)
+                };
+                (id, item)
+            })
+            .collect()
+    }
+
+    /// Both entity arrays, when both documents have one
+    fn arrays<'a>(&self, left: &'a JsonValue, right: &'a JsonValue) -> Option<(&'a [JsonValue], &'a [JsonValue])> {
+        match (navigate(left, &self.path), navigate(right, &self.path)) {
+            (Some(JsonValue::Array(left)), Some(JsonValue::Array(right))) => Some((left, right)),
+            _ => None
+        }
+    }
+
+    /// Both documents without the entity array
+    fn rest(&self, left: &JsonValue, right: &JsonValue) -> (JsonValue, JsonValue) {
+        let (mut left, mut right) = (left.clone(), right.clone());
+        prune(&mut left, &self.path);
+        prune(&mut right, &self.path);
+        (left, right)
+    }
+}
+
+/// Entities paired by id: left entities in order, then those only on the right. A repeated id
+/// pairs its entities in order, the first on the left with the first on the right and so on.
+fn pair<'a>(left: &'a [(EntityId, &'a JsonValue)], right: &'a [(EntityId, &'a JsonValue)]) -> Vec<Pairing<'a>> {
+    let mut right_index: HashMap<&EntityId, Vec<&JsonValue>> = HashMap::with_capacity(right.len());
+    for (id, entity) in right {
+        right_index.entry(id).or_default().push(entity);
+    }
+    let mut left_count: HashMap<&EntityId, usize> = HashMap::with_capacity(left.len());
+    let mut pairs: Vec<Pairing> = left
+        .iter()
+        .enumerate()
+        .map(|(index, (id, left))| {
+            let nth = occurrence(&mut left_count, id);
+            match right_index.get(id).and_then(|entities| entities.get(nth)) {
+                Some(right) => Pairing::Both { id, index, left, right },
+                None => Pairing::LeftOnly { id, index }
+            }
+        })
+        .collect();
+    let mut right_count = HashMap::with_capacity(right.len());
+    pairs.extend(
+        right
+            .iter()
+            .filter(|(id, _)| occurrence(&mut right_count, id) >= left_count.get(id).copied().unwrap_or(0))
+            .map(|(id, right)| Pairing::RightOnly { id, right })
+    );
+    pairs
+}
+
+/// Count one more sighting of `id`, returning how many came before it
+fn occurrence<'a>(counts: &mut HashMap<&'a EntityId, usize>, id: &'a EntityId) -> usize {
+    let count = counts.entry(id).or_insert(0);
+    *count += 1;
+    *count - 1
+}
+
+impl<D: Differ> Differ for EntityDiffer<D> {
+    fn print_result(
+        &self,
+        left_label: &str,
+        right_label: &str,
+        left: &JsonValue,
+        right: &JsonValue,
+        result: &DiffResult
+    ) {
+        if self.json_lines {
+            print_json_line(&DiffEvent::new(left_label, right_label, left, right, result.clone()));
+            return;
+        }
+        let Some((left_items, right_items)) = self.arrays(left, right) else {
+            self.inner.print_result(left_label, right_label, left, right, result);
+            return;
+        };
+
+        // Everything outside the entity array
+        let (left_rest, right_rest) = self.rest(left, right);
+        let rest = self.inner.compute_diff(&left_rest, &right_rest);
+        if !rest.identical {
+            self.inner.print_result(left_label, right_label, &left_rest, &right_rest, &rest);
+        }
+
+        let (left_entities, right_entities) = (self.entities(left_items), self.entities(right_items));
+        let mut unchanged = 0;
+        for pairing in pair(&left_entities, &right_entities) {
+            match pairing {
+                Pairing::Both { id, left, right, .. } => {
+                    let result = self.inner.compute_diff(left, right);
+                    if result.identical {
+                        unchanged += 1;
+                        continue;
+                    }
+                    println!("{} {}", "◆ entity".bold(), id.bold());
+                    self.inner.print_result(left_label, right_label, left, right, &result);
+                }
+                Pairing::LeftOnly { id, .. } => {
+                    println!("{} {id} {}", self.theme.left(&"− entity"), format!("only in {left_label}").dimmed())
+                }
+                Pairing::RightOnly { id, .. } => {
+                    println!("{} {id} {}", self.theme.right(&"+ entity"), format!("only in {right_label}").dimmed())
+                }
+            }
+        }
+        if unchanged > 0 {
+            println!("{}", format!("{unchanged} entit{} unchanged", if unchanged == 1 { "y" } else { "ies" }).dimmed());
+        }
+    }
+
+    /// The changes `print_diff` shows, as one patch: the rest of the document, then each changed
+    /// entity at its left position, entities only on the left removed (last first, so positions
+    /// hold) and entities only on the right appended
+    fn compute_diff(&self, left: &JsonValue, right: &JsonValue) -> DiffResult {
+        let Some((left_items, right_items)) = self.arrays(left, right) else {
+            return self.inner.compute_diff(left, right);
+        };
+
+        let (left_rest, right_rest) = self.rest(left, right);
+        let mut ops = self.inner.compute_diff(&left_rest, &right_rest).patch.0;
+        let (mut removed, mut added) = (Vec::new(), Vec::new());
+        let (left_entities, right_entities) = (self.entities(left_items), self.entities(right_items));
+        for pairing in pair(&left_entities, &right_entities) {
+            match pairing {
+                Pairing::Both { index, left, right, .. } => {
+                    let patch = self.inner.compute_diff(left, right).patch;
+                    ops.extend(nest_patch(patch, &self.item_path(&index.to_string())).0);
+                }
+                Pairing::LeftOnly { index, .. } => removed.push(index),
+                Pairing::RightOnly { right, .. } => added.push(PatchOperation::Add(AddOperation {
+                    path:  PointerBuf::from_tokens(self.item_path("-")),
+                    value: right.clone()
+                }))
+            }
+        }
+        ops.extend(removed.into_iter().rev().map(|index| {
+            PatchOperation::Remove(RemoveOperation {
+                path: PointerBuf::from_tokens(self.item_path(&index.to_string()))
+            })
+        }));
+        ops.extend(added);
+        DiffResult::from_patch(Patch(ops))
+    }
+}
+
+impl<D: Differ> EntityDiffer<D> {
+    /// Path segments of an entity array item (or "-" for past the end)
+    fn item_path(&self, item: &str) -> Vec<String> {
+        let mut path = self.path.clone();
+        path.push(item.to_string());
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::adapter::JsonPatchDiffer;
+
+    fn differ() -> EntityDiffer<JsonPatchDiffer> {
+        EntityDiffer::new("/entities", "id", JsonPatchDiffer::default())
+    }
+
+    /// Paths of the id-paired patch from `left` to `right`
+    fn paths(left: &JsonValue, right: &JsonValue) -> Vec<String> {
+        let patch = differ().compute_diff(left, right).patch;
+        patch.0.iter().map(|op| serde_json::to_value(op).unwrap()["path"].as_str().unwrap().to_string()).collect()
+    }
+
+    #[test]
+    fn reordered_entities_are_identical() {
+        let left = json!({"entities": [{"id": 1, "hp": 5}, {"id": 2, "hp": 7}]});
+        let right = json!({"entities": [{"id": 2, "hp": 7}, {"id": 1, "hp": 5}]});
+        assert!(differ().compute_diff(&left, &right).identical);
+        assert_eq!(differ().count_changes(&left, &right), 0);
+    }
+
+    #[test]
+    fn counts_changes_per_paired_entity() {
+        let left = json!({"tick": 1, "entities": [{"id": 1, "hp": 5}, {"id": 2, "hp": 7}, {"id": 3}]});
+        let right = json!({"tick": 2, "entities": [{"id": 4}, {"id": 2, "hp": 6}, {"id": 1, "hp": 5}]});
+        assert_eq!(paths(&left, &right), ["/tick", "/entities/1/hp", "/entities/2", "/entities/-"]);
+    }
+
+    #[test]
+    fn the_patch_applies_to_the_left_document() {
+        let mut left = json!({"entities": [{"id": 1, "hp": 5}, {"id": 2, "hp": 7}, {"id": 3}]});
+        let right = json!({"entities": [{"id": 2, "hp": 6}, {"id": 4}]});
+        let patch = differ().compute_diff(&left, &right).patch;
+        json_patch::patch(&mut left, &patch).unwrap();
+        assert_eq!(left, json!({"entities": [{"id": 2, "hp": 6}, {"id": 4}]}));
+    }
+
+    #[test]
+    fn ids_of_different_types_are_different_entities() {
+        let left = json!({"entities": [{"id": 1, "hp": 5}]});
+        let right = json!({"entities": [{"id": "1", "hp": 5}]});
+        assert_eq!(paths(&left, &right), ["/entities/0", "/entities/-"]);
+    }
+
+    #[test]
+    fn entities_without_an_id_never_pair_with_one_that_has_it() {
+        let left = json!({"entities": [{"hp": 5}]});
+        let right = json!({"entities": [{"id": "#0", "hp": 5}]});
+        assert_eq!(paths(&left, &right), ["/entities/0", "/entities/-"]);
+
+        let left = json!({"entities": [{"hp": 5}, {"id": 1}]});
+        let right = json!({"entities": [{"hp": 6}, {"id": 1}]});
+        assert_eq!(paths(&left, &right), ["/entities/0/hp"]);
+    }
+
+    #[test]
+    fn repeated_ids_pair_in_order() {
+        let left = json!({"entities": [{"id": 1, "hp": 5}, {"id": 1, "hp": 9}]});
+        let right = json!({"entities": [{"id": 1, "hp": 5}]});
+        assert_eq!(paths(&left, &right), ["/entities/1"]);
+        assert_eq!(paths(&right, &left), ["/entities/-"]);
+    }
+
+    #[test]
+    fn falls_back_to_the_inner_differ_without_arrays() {
+        let result = differ().compute_diff(&json!({"a": 1}), &json!({"a": 2}));
+        assert_eq!(result.ops_count, 1);
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    sync::Mutex,
+    thread
+};
+
+use anstream::println;
+use json_patch::{Patch, PatchOperation, ReplaceOperation, jsonptr::PointerBuf};
+use owo_colors::OwoColorize;
+use serde_json::{Value as JsonValue, json};
+use tokio::runtime::{Handle, RuntimeFlavor};
+use tracing::{info, warn};
+
+use super::Theme;
+use crate::{domain::DiffResult, port::Differ};
+
+/// Differ that delegates to an external program.
+///
+/// The program receives `{"left": ..., "right": ...}` as JSON on stdin and is expected to write its
+/// diff to stdout. Empty output is treated as "no differences", any output as a difference: that
+/// verdict is what counts, fails and deduplicates pairs, not the JSON Patch between the sides.
+pub struct ExternalDiffer {
+    program:  String,
+    args:     Vec<String>,
+    theme:    Theme,
+    /// Input and output of the last run, so printing a pair that was just diffed doesn't run the
+    /// program a second time
+    last_run: Mutex<Option<(String, String)>>
+}
+
+impl ExternalDiffer {
+    /// Create a differ from a command line (e.g., "python3 my_diff.py --strict")
+    pub fn new(command: &str) -> Self {
+        let mut parts = command.split_whitespace().map(|s| s.to_string());
+        let program = parts.next().unwrap_or_default();
+        Self { program, args: parts.collect(), theme: Theme::default(), last_run: Mutex::default() }
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// The program's output for a pair, reusing the last run when it had the same input
+    fn output(&self, left: &JsonValue, right: &JsonValue) -> std::io::Result<String> {
+        let input = json!({ "left": left, "right": right }).to_string();
+        let mut last_run = self.last_run.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((last_input, output)) = last_run.as_ref()
+            && *last_input == input
+        {
+            return Ok(output.clone());
+        }
+        let output = self.run(input.clone().into_bytes())?;
+        *last_run = Some((input, output.clone()));
+        Ok(output)
+    }
+
+    /// Run the program on `input`. `Differ` is synchronous, so on a multi-threaded runtime the
+    /// worker hands its other tasks (the sources, tickers) to the rest of the pool while waiting.
+    fn run(&self, input: Vec<u8>) -> std::io::Result<String> {
+        match Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(|| self.run_blocking(input))
+            }
+            _ => self.run_blocking(input)
+        }
+    }
+
+    fn run_blocking(&self, input: Vec<u8>) -> std::io::Result<String> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()?;
+
+        // Written from another thread, so a program that answers before reading all of its input
+        // can't block on a full stdout pipe while we block on its stdin
+        let writer = child.stdin.take().map(|mut stdin| thread::spawn(move || stdin.write_all(&input)));
+        let output = child.wait_with_output()?;
+        if let Some(writer) = writer {
+            // A program that exits without reading everything closes the pipe; its output still counts
+            match writer.join() {
+                Ok(Err(err)) if err.kind() != std::io::ErrorKind::BrokenPipe => return Err(err),
+                _ => {}
+            }
+        }
+        if !output.status.success() {
+            return Err(std::io::Error::other(format!("exited with {}", output.status)));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+impl Differ for ExternalDiffer {
+    /// Identical when the program prints nothing. Otherwise the JSON Patch between the sides, or a
+    /// replacement of the whole document when the program reports differences JSON Patch doesn't
+    /// see. If the program fails, the plain JSON Patch comparison stands in for its verdict.
+    fn compute_diff(&self, left: &JsonValue, right: &JsonValue) -> DiffResult {
+        match self.output(left, right) {
+            Ok(output) if output.trim().is_empty() => DiffResult::default(),
+            Ok(_) => {
+                let result = DiffResult::between(left, right);
+                if !result.identical {
+                    return result;
+                }
+                DiffResult::from_patch(Patch(vec![PatchOperation::Replace(ReplaceOperation {
+                    path:  PointerBuf::new(),
+                    value: right.clone()
+                })]))
+            }
+            Err(err) => {
+                warn!("external differ `{}` failed: {err}", self.program);
+                DiffResult::between(left, right)
+            }
+        }
+    }
+
+    fn print_result(
+        &self,
+        left_label: &str,
+        right_label: &str,
+        left: &JsonValue,
+        right: &JsonValue,
+        result: &DiffResult
+    ) {
+        if result.identical {
+            info!("states are identical");
+            return;
+        }
+        match self.output(left, right) {
+            Ok(output) if output.trim().is_empty() => info!("states are identical"),
+            Ok(output) => {
+                println!(
+                    "\n{} {} -> {} {}",
+                    "diff".bold(),
+                    self.theme.left(&left_label).bold(),
+                    self.theme.right(&right_label).bold(),
+                    format!("[external: {}]", self.program).dimmed()
+                );
+                println!("{}", output.trim_end());
+            }
+            Err(err) => warn!("external differ `{}` failed: {err}", self.program)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn inputs_larger_than_a_pipe_buffer_pass_through() {
+        let input = "x".repeat(1 << 20);
+        assert_eq!(ExternalDiffer::new("cat").run(input.clone().into_bytes()).unwrap(), input);
+    }
+
+    #[test]
+    fn programs_may_stop_reading_early_but_not_fail() {
+        assert_eq!(ExternalDiffer::new("head -c 2").run(b"{\"left\": 1}".to_vec()).unwrap(), "{\"");
+        assert!(ExternalDiffer::new("false").run(Vec::new()).is_err());
+        assert!(ExternalDiffer::new("pica-no-such-program").run(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn the_programs_output_decides_whether_states_differ() {
+        let (left, right) = (json!({"a": 1}), json!({"a": 2}));
+        assert!(ExternalDiffer::new("true").compute_diff(&left, &right).identical);
+        assert_eq!(ExternalDiffer::new("true").count_changes(&left, &right), 0);
+        assert_eq!(ExternalDiffer::new("cat").count_changes(&left, &right), 1);
+
+        let result = ExternalDiffer::new("cat").compute_diff(&left, &left);
+        assert!(!result.identical);
+        assert_eq!(
+            serde_json::to_value(&result.patch).unwrap(),
+            json!([{"op": "replace", "path": "", "value": {"a": 1}}])
+        );
+    }
+
+    #[test]
+    fn failing_programs_fall_back_to_json_patch() {
+        assert!(ExternalDiffer::new("false").compute_diff(&json!(1), &json!(1)).identical);
+        assert_eq!(ExternalDiffer::new("false").count_changes(&json!(1), &json!(2)), 1);
+    }
+}
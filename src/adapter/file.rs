@@ -0,0 +1,246 @@
+use std::{
+    io,
+    path::{Path, PathBuf}
+};
+
+#[cfg(feature = "compression")]
+use flate2::read::MultiGzDecoder;
+use serde_json::Value;
+use tokio::{
+    fs::File,
+    io::{AsyncBufReadExt, BufReader},
+    sync::mpsc,
+    time::{Duration, sleep}
+};
+use tracing::{info, warn};
+
+use crate::{
+    domain::SourceError,
+    port::{SourceErrors, StateSource}
+};
+
+/// Replays a newline-delimited JSON capture file, one state per line. Files ending in ".gz" (e.g.,
+/// from `--record-gzip`) are decompressed on the fly with the `compression` feature and fail to
+/// open without it.
+#[derive(Clone, Debug)]
+pub struct FileSource {
+    pub name:    String,
+    pub path:    PathBuf,
+    interval_ms: u64,
+    looping:     bool,
+    loop_delay:  Duration,
+    /// Whether the file is gzipped; `None` decides by extension
+    compressed:  Option<bool>
+}
+
+impl FileSource {
+    pub fn new<N: Into<String>, P: Into<PathBuf>>(name: N, path: P) -> Self {
+        Self {
+            name:        name.into(),
+            path:        path.into(),
+            interval_ms: 0,
+            looping:     false,
+            loop_delay:  Duration::ZERO,
+            compressed:  None
+        }
+    }
+
+    /// Read the file as gzip (or not) regardless of its extension
+    pub fn with_compression(mut self, enabled: bool) -> Self {
+        self.compressed = Some(enabled);
+        self
+    }
+
+    /// Wait this long between replayed messages (0 = as fast as the tracker consumes them)
+    pub fn with_interval(mut self, interval_ms: u64) -> Self {
+        self.interval_ms = interval_ms;
+        self
+    }
+
+    /// On EOF, rewind to the start and replay again instead of closing
+    pub fn with_loop(mut self, enabled: bool) -> Self {
+        self.looping = enabled;
+        self
+    }
+
+    /// Pause between the end of one loop and the start of the next
+    pub fn with_loop_delay(mut self, delay: Duration) -> Self {
+        self.loop_delay = delay;
+        self
+    }
+}
+
+impl StateSource for FileSource {
+    fn spawn(&self) -> mpsc::Receiver<Value> {
+        // Nobody listens for errors; they still go to the log
+        self.spawn_with_errors(mpsc::unbounded_channel().0)
+    }
+
+    fn spawn_with_errors(&self, errors: SourceErrors) -> mpsc::Receiver<Value> {
+        let (tx, rx) = mpsc::channel::<Value>(64);
+        let source = self.clone();
+
+        tokio::spawn(async move {
+            let name = &source.name;
+            let gzip = source.compressed.unwrap_or_else(|| has_gz_extension(&source.path));
+            let mut lines = match CaptureLines::open(&source.path, gzip).await {
+                Ok(lines) => lines,
+                Err(err) => {
+                    let message = format!("failed to open {}: {err}", source.path.display());
+                    warn!("{name} {message}");
+                    let _ = errors.send(SourceError::new(name.as_str(), message));
+                    return;
+                }
+            };
+            info!("{name} replaying {}{}", source.path.display(), if gzip { " (gzip)" } else { "" });
+
+            let interval = Duration::from_millis(source.interval_ms);
+            let mut loops: usize = 0;
+
+            loop {
+                match lines.next_line().await {
+                    Ok(None) => {
+                        if !source.looping {
+                            info!("{name} reached end of file");
+                            break;
+                        }
+
+                        loops += 1;
+                        info!("{name} reached end of file, restarting replay (loop {loops})");
+                        lines = match CaptureLines::open(&source.path, gzip).await {
+                            Ok(lines) => lines,
+                            Err(err) => {
+                                warn!("{name} failed to reopen {}: {err}", source.path.display());
+                                break;
+                            }
+                        };
+                        sleep(source.loop_delay).await;
+                    }
+                    Ok(Some(line)) => {
+                        let trimmed = line.trim();
+                        if trimmed.is_empty() {
+                            continue;
+                        }
+
+                        match serde_json::from_str::<Value>(trimmed) {
+                            Ok(json) => {
+                                if tx.send(json).await.is_err() {
+                                    warn!("{name} output channel closed");
+                                    break;
+                                }
+                                if !interval.is_zero() {
+                                    sleep(interval).await;
+                                }
+                            }
+                            Err(err) => warn!("{name} failed to parse line as JSON: {err}")
+                        }
+                    }
+                    Err(err) => {
+                        warn!("{name} read error: {err}");
+                        break;
+                    }
+                }
+            }
+        });
+
+        rx
+    }
+}
+
+/// Whether a capture path ends in ".gz"
+fn has_gz_extension(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
+
+/// Lines of a capture file, from the start
+enum CaptureLines {
+    Plain(BufReader<File>),
+    /// Decompressed on a blocking thread, which stops once the receiver is dropped
+    #[cfg(feature = "compression")]
+    Gzip(mpsc::Receiver<io::Result<String>>)
+}
+
+impl CaptureLines {
+    async fn open(path: &Path, gzip: bool) -> io::Result<Self> {
+        if !gzip {
+            return Ok(Self::Plain(BufReader::new(File::open(path).await?)));
+        }
+        Self::gunzip(path)
+    }
+
+    #[cfg(not(feature = "compression"))]
+    fn gunzip(_path: &Path) -> io::Result<Self> {
+        Err(crate::domain::gzip_unsupported())
+    }
+
+    #[cfg(feature = "compression")]
+    fn gunzip(path: &Path) -> io::Result<Self> {
+        use std::io::BufRead;
+
+        let file = std::fs::File::open(path)?;
+        let (tx, rx) = mpsc::channel(64);
+        tokio::task::spawn_blocking(move || {
+            for line in io::BufReader::new(MultiGzDecoder::new(file)).lines() {
+                let failed = line.is_err();
+                if tx.blocking_send(line).is_err() || failed {
+                    return;
+                }
+            }
+        });
+        Ok(Self::Gzip(rx))
+    }
+
+    /// The next line, or `None` at the end of the file
+    async fn next_line(&mut self) -> io::Result<Option<String>> {
+        match self {
+            Self::Plain(reader) => {
+                let mut line = String::new();
+                match reader.read_line(&mut line).await? {
+                    0 => Ok(None),
+                    _ => Ok(Some(line))
+                }
+            }
+            #[cfg(feature = "compression")]
+            Self::Gzip(lines) => lines.recv().await.transpose()
+        }
+    }
+}
+
+#[cfg(all(test, feature = "compression"))]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{Compression, write::GzEncoder};
+    use serde_json::json;
+
+    use super::*;
+
+    async fn replay(source: FileSource) -> Vec<Value> {
+        let mut rx = source.spawn();
+        let mut states = Vec::new();
+        while let Some(state) = rx.recv().await {
+            states.push(state);
+        }
+        states
+    }
+
+    #[tokio::test]
+    async fn gzipped_captures_replay_like_plain_ones() {
+        let dir = std::env::temp_dir().join(format!("pica-gzip-capture-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let capture = "{\"v\": 1}\n\n{\"v\": 2}\n";
+        let (plain, gzipped, unnamed) = (dir.join("c.ndjson"), dir.join("c.ndjson.gz"), dir.join("c.bin"));
+        std::fs::write(&plain, capture).unwrap();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(capture.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        std::fs::write(&gzipped, &compressed).unwrap();
+        std::fs::write(&unnamed, &compressed).unwrap();
+
+        let expected = vec![json!({"v": 1}), json!({"v": 2})];
+        assert_eq!(replay(FileSource::new("plain", plain.clone())).await, expected);
+        assert_eq!(replay(FileSource::new("gzip", gzipped.clone())).await, expected);
+        assert_eq!(replay(FileSource::new("forced", unnamed.clone()).with_compression(true)).await, expected);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,211 @@
+use std::sync::Arc;
+
+use http_body_util::{BodyExt, Empty};
+use hyper::{
+    Request, Uri,
+    body::Bytes,
+    header::{HOST, HeaderValue}
+};
+use hyper_util::rt::TokioIo;
+use serde_json::Value;
+use tokio::{
+    net::TcpStream,
+    sync::mpsc,
+    time::{Duration, MissedTickBehavior, interval, sleep, timeout}
+};
+use tracing::{debug, error, info, warn};
+
+use super::ReconnectPolicy;
+use crate::{
+    domain::SourceError,
+    metric::Metrics,
+    port::{SourceErrors, StateSource}
+};
+
+/// How long one poll may take, connecting included
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Polls a REST endpoint with GET at a fixed interval and forwards each JSON body, for systems
+/// without a push feed. Failed polls (connect errors, non-2xx statuses, timeouts) back off like
+/// `WebSocketSource` reconnects, optionally giving up after a number in a row. Plain `http://`
+/// only; there is no TLS.
+#[derive(Clone, Debug)]
+pub struct HttpPollSource {
+    pub name:  String,
+    pub url:   String,
+    /// Time between polls
+    interval:  Duration,
+    /// Extra request headers, in order
+    headers:   Vec<(String, String)>,
+    /// JSON Pointer to the state inside a wrapped response (e.g., "/data")
+    pointer:   Option<String>,
+    /// Session counters to report retries to
+    metrics:   Option<Arc<Metrics>>,
+    /// When to give up retrying and how to space the attempts
+    reconnect: ReconnectPolicy
+}
+
+impl HttpPollSource {
+    pub fn new<N: Into<String>, U: Into<String>>(name: N, url: U, interval_ms: u64) -> Self {
+        Self {
+            name:      name.into(),
+            url:       url.into(),
+            interval:  Duration::from_millis(interval_ms.max(1)),
+            headers:   Vec::new(),
+            pointer:   None,
+            metrics:   None,
+            reconnect: ReconnectPolicy::default()
+        }
+    }
+
+    /// Send this header with every poll; repeating a name sends it several times
+    pub fn with_header<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Forward only the part of each response at this JSON Pointer (e.g., "/data" for
+    /// `{"data": {...}, "meta": {...}}`); responses without it are skipped
+    pub fn with_pointer<P: Into<String>>(mut self, pointer: P) -> Self {
+        self.pointer = Some(pointer.into());
+        self
+    }
+
+    /// Give up after `policy.max_attempts` consecutive failed polls, closing the stream so the
+    /// tracker can finish, and optionally jitter the backoff between attempts
+    pub fn with_reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect = policy;
+        self
+    }
+
+    /// Count retries in these session metrics
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+}
+
+impl StateSource for HttpPollSource {
+    fn spawn(&self) -> mpsc::Receiver<Value> {
+        // Nobody listens for errors; they still go to the log
+        self.spawn_with_errors(mpsc::unbounded_channel().0)
+    }
+
+    fn spawn_with_errors(&self, errors: SourceErrors) -> mpsc::Receiver<Value> {
+        let (tx, rx) = mpsc::channel::<Value>(64);
+        tokio::spawn(self.clone().run(tx, errors));
+        rx
+    }
+}
+
+impl HttpPollSource {
+    /// Poll until the receiver is dropped, backing off after failed polls
+    async fn run(self, tx: mpsc::Sender<Value>, errors: SourceErrors) {
+        let name = &self.name;
+        let uri = match self.url.parse::<Uri>() {
+            Ok(uri) if uri.scheme_str() == Some("http") && uri.host().is_some() => uri,
+            Ok(uri) if uri.scheme_str() == Some("https") => {
+                error!("{name} can't poll {}: https isn't supported, only plain http", self.url);
+                let _ = errors.send(SourceError::new(name, "https isn't supported"));
+                return;
+            }
+            _ => {
+                error!("{name} can't poll {}: not an http:// URL", self.url);
+                let _ = errors.send(SourceError::new(name, "invalid URL"));
+                return;
+            }
+        };
+        info!("{name} polling {} every {:?}", self.url, self.interval);
+
+        let mut ticker = interval(self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // Polls in a row that failed
+        let mut failures: u32 = 0;
+        loop {
+            ticker.tick().await;
+            if tx.is_closed() {
+                return;
+            }
+            let message = match timeout(REQUEST_TIMEOUT, self.poll(&uri)).await {
+                Ok(Ok(body)) => {
+                    failures = 0;
+                    if let Some(state) = self.extract(&body)
+                        && tx.send(state).await.is_err()
+                    {
+                        return;
+                    }
+                    continue;
+                }
+                Ok(Err(message)) => message,
+                Err(_) => format!("request timed out after {REQUEST_TIMEOUT:?}")
+            };
+
+            warn!("{name} {message}");
+            let _ = errors.send(SourceError::new(name, message));
+            failures += 1;
+            if self.reconnect.exhausted(failures) {
+                error!("{name} giving up after {failures} failed poll(s)");
+                return;
+            }
+            let delay = self.reconnect.delay(failures);
+            info!("{name} retrying in {:?}", delay);
+            if let Some(metrics) = &self.metrics {
+                metrics.record_reconnect(name);
+            }
+            sleep(delay).await;
+            ticker.reset();
+        }
+    }
+
+    /// One GET request; the body on a 2xx status
+    async fn poll(&self, uri: &Uri) -> Result<Bytes, String> {
+        let host = uri.host().unwrap_or_default();
+        let port = uri.port_u16().unwrap_or(80);
+        let stream = TcpStream::connect((host, port)).await.map_err(|err| format!("connect error: {err}"))?;
+        let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+            .await
+            .map_err(|err| format!("handshake error: {err}"))?;
+        tokio::spawn(async move {
+            if let Err(err) = connection.await {
+                debug!("http connection ended: {err}");
+            }
+        });
+
+        let authority = uri.authority().map_or(host, |authority| authority.as_str());
+        let path = uri.path_and_query().map_or("/", |path| path.as_str());
+        let mut request = Request::get(path).header(HOST, authority);
+        for (header, value) in &self.headers {
+            let value = HeaderValue::from_str(value).map_err(|err| format!("invalid header {header}: {err}"))?;
+            request = request.header(header.as_str(), value);
+        }
+        let request = request.body(Empty::<Bytes>::new()).map_err(|err| format!("invalid request: {err}"))?;
+
+        let response = sender.send_request(request).await.map_err(|err| format!("request error: {err}"))?;
+        let status = response.status();
+        let body = response.into_body().collect().await.map_err(|err| format!("read error: {err}"))?.to_bytes();
+        if !status.is_success() {
+            return Err(format!("returned {status}"));
+        }
+        Ok(body)
+    }
+
+    /// Parse a response body and pick out the state; unparsable bodies are logged and skipped
+    fn extract(&self, body: &[u8]) -> Option<Value> {
+        let name = &self.name;
+        let json = match serde_json::from_slice::<Value>(body) {
+            Ok(json) => json,
+            Err(err) => {
+                warn!("{name} failed to parse response as JSON: {err}");
+                return None;
+            }
+        };
+        let Some(pointer) = &self.pointer else {
+            return Some(json);
+        };
+        let state = json.pointer(pointer).cloned();
+        if state.is_none() {
+            warn!("{name} response has nothing at {pointer}; skipped");
+        }
+        state
+    }
+}
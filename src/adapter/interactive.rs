@@ -0,0 +1,178 @@
+use std::{
+    io::BufRead,
+    sync::{Arc, RwLock}
+};
+
+use serde_json::Value as JsonValue;
+
+use super::KeyRules;
+use crate::{
+    domain::DiffResult,
+    port::{AlignmentKeyExtractor, Differ, field_extractor}
+};
+
+/// Settings that can be changed from the interactive prompt while the tracker runs
+#[derive(Debug, Clone, Default)]
+pub struct LiveSettings {
+    /// Alignment path overriding the one the session started with
+    pub align_by: Option<String>,
+    /// Ignore/projection rules applied before every diff
+    pub rules:    KeyRules
+}
+
+/// Live settings shared between the command reader and the tracker's extractor and differ
+pub type SharedSettings = Arc<RwLock<LiveSettings>>;
+
+const HELP: &str = "\
+commands:
+  align-by <path>       align by this field path (no argument: back to the startup alignment)
+  ignore <pointer>      remove a JSON Pointer from both sides before diffing
+  unignore <pointer>    stop ignoring a pointer
+  project <pointer>...  only compare these pointers (no argument: compare everything)
+  show                  print the current settings
+  help                  print this help";
+
+impl LiveSettings {
+    /// Apply one prompt command; returns the confirmation to print or an error message
+    pub fn apply_command(&mut self, line: &str) -> Result<String, String> {
+        let mut words = line.split_whitespace();
+        let Some(command) = words.next() else {
+            return Ok(String::new());
+        };
+        let args: Vec<String> = words.map(str::to_string).collect();
+
+        match command {
+            "align-by" => {
+                self.align_by = args.first().cloned();
+                Ok(match &self.align_by {
+                    Some(path) => format!("aligning by `{path}`"),
+                    None => "aligning as configured at startup".to_string()
+                })
+            }
+            "ignore" | "unignore" => {
+                let [pointer] = args.as_slice() else {
+                    return Err(format!("usage: {command} <pointer>"));
+                };
+                if !pointer.starts_with('/') {
+                    return Err(format!("`{pointer}` is not a JSON Pointer (e.g. \"/timestamp\")"));
+                }
+                self.rules.ignore.retain(|p| p != pointer);
+                if command == "ignore" {
+                    self.rules.ignore.push(pointer.clone());
+                    Ok(format!("ignoring {pointer}"))
+                } else {
+                    Ok(format!("no longer ignoring {pointer}"))
+                }
+            }
+            "project" => {
+                if let Some(bad) = args.iter().find(|p| !p.starts_with('/')) {
+                    return Err(format!("`{bad}` is not a JSON Pointer (e.g. \"/data\")"));
+                }
+                self.rules.only = args;
+                Ok(if self.rules.only.is_empty() {
+                    "comparing whole states".to_string()
+                } else {
+                    format!("only comparing {}", self.rules.only.join(", "))
+                })
+            }
+            "show" => Ok(format!(
+                "align-by: {}\nignore: [{}]\nproject: [{}]",
+                self.align_by.as_deref().unwrap_or("(startup)"),
+                self.rules.ignore.join(", "),
+                self.rules.only.join(", ")
+            )),
+            "help" => Ok(HELP.to_string()),
+            other => Err(format!("unknown command `{other}` (try `help`)"))
+        }
+    }
+}
+
+/// Read prompt commands from stdin on a dedicated thread (a blocking read must not hold up the
+/// runtime's shutdown) and apply them to `settings`
+pub fn spawn_command_reader(settings: SharedSettings) {
+    println!("⌨️  interactive mode: type `help` for commands");
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            let result = settings.write().unwrap_or_else(|e| e.into_inner()).apply_command(&line);
+            match result {
+                Ok(message) if message.is_empty() => {}
+                Ok(message) => println!("⌨️  {message}"),
+                Err(message) => println!("⌨️  {message}")
+            }
+        }
+    });
+}
+
+/// Extractor that follows the live `align-by` setting, falling back to the startup extractor
+pub struct LiveExtractor<E: AlignmentKeyExtractor> {
+    settings: SharedSettings,
+    fallback: E
+}
+
+impl<E: AlignmentKeyExtractor> LiveExtractor<E> {
+    pub fn new(settings: SharedSettings, fallback: E) -> Self {
+        Self { settings, fallback }
+    }
+}
+
+impl<E: AlignmentKeyExtractor> AlignmentKeyExtractor for LiveExtractor<E> {
+    fn extract_key(&self, state: &JsonValue) -> Option<String> {
+        let align_by = self.settings.read().unwrap_or_else(|e| e.into_inner()).align_by.clone();
+        match align_by {
+            Some(path) => field_extractor(&path).extract_key(state),
+            None => self.fallback.extract_key(state)
+        }
+    }
+
+    fn extract_side_key(&self, is_left: bool, state: &JsonValue) -> Option<String> {
+        let align_by = self.settings.read().unwrap_or_else(|e| e.into_inner()).align_by.clone();
+        match align_by {
+            Some(path) => field_extractor(&path).extract_key(state),
+            None => self.fallback.extract_side_key(is_left, state)
+        }
+    }
+
+    fn describe(&self) -> String {
+        let align_by = self.settings.read().unwrap_or_else(|e| e.into_inner()).align_by.clone();
+        match align_by {
+            Some(path) => field_extractor(&path).describe(),
+            None => self.fallback.describe()
+        }
+    }
+}
+
+/// Differ that applies the live ignore/projection rules, then delegates to the wrapped differ
+pub struct LiveDiffer<D: Differ> {
+    settings: SharedSettings,
+    inner:    D
+}
+
+impl<D: Differ> LiveDiffer<D> {
+    pub fn new(settings: SharedSettings, inner: D) -> Self {
+        Self { settings, inner }
+    }
+
+    fn rules(&self) -> KeyRules {
+        self.settings.read().unwrap_or_else(|e| e.into_inner()).rules.clone()
+    }
+}
+
+impl<D: Differ> Differ for LiveDiffer<D> {
+    fn print_result(
+        &self,
+        left_label: &str,
+        right_label: &str,
+        left: &JsonValue,
+        right: &JsonValue,
+        result: &DiffResult
+    ) {
+        let (left, right) = self.rules().apply(left, right);
+        self.inner.print_result(left_label, right_label, &left, &right, result);
+    }
+
+    fn compute_diff(&self, left: &JsonValue, right: &JsonValue) -> DiffResult {
+        let (left, right) = self.rules().apply(left, right);
+        self.inner.compute_diff(&left, &right)
+    }
+}
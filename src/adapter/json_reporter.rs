@@ -0,0 +1,189 @@
+use std::{borrow::Cow, collections::HashMap, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use super::{
+    JsonPatchDiffer,
+    reporter::{PairScore, ReportStats, count_matched, count_mismatched, lag_stats, pair_scores, write_report}
+};
+use crate::{
+    domain::{KeyComparison, MatchStrategy, PathRollup, RunMetadata, State, round_numbers},
+    metric::LatencyStats,
+    port::{Differ, Reporter}
+};
+
+/// Machine-readable report for CI: session metadata, stats and every tracked state
+pub struct JsonReporter {
+    session_id:   String,
+    started_at:   DateTime<Utc>,
+    left_states:  Vec<State>,
+    right_states: Vec<State>,
+    rollup:       Option<PathRollup>,
+    metadata:     Option<RunMetadata>,
+    /// Round payload numbers to this many significant digits in the output
+    precision:    Option<u32>,
+    /// Which alignment keys count as the same (normalization, numeric tolerance)
+    keys:         KeyComparison,
+    /// How pairs are formed for the similarity scores
+    strategy:     MatchStrategy,
+    /// Diffs pairs for the similarity scores
+    differ:       Arc<dyn Differ>,
+    /// Differs used instead of `differ` for pairs aligned on specific keys
+    key_differs:  HashMap<String, Arc<dyn Differ>>
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    session_id:    &'a str,
+    started_at:    String,
+    generated_at:  String,
+    /// How the session was run
+    run:           Option<&'a RunMetadata>,
+    stats:         ReportStats,
+    /// Right-vs-left lag of matched pairs, in milliseconds
+    latency:       Option<LatencyStats>,
+    /// Paths changed across the round's comparisons
+    changed_paths: Option<&'a PathRollup>,
+    /// Similarity of each pair that matched on key, in round order
+    pairs:         Vec<PairScore>,
+    left:          Vec<JsonReportState<'a>>,
+    right:         Vec<JsonReportState<'a>>
+}
+
+#[derive(Serialize)]
+struct JsonReportState<'a> {
+    key:       Option<&'a str>,
+    timestamp: String,
+    data:      Cow<'a, JsonValue>
+}
+
+impl JsonReporter {
+    pub fn new() -> Self {
+        Self {
+            session_id:   uuid::Uuid::new_v4().to_string(),
+            started_at:   Utc::now(),
+            left_states:  Vec::new(),
+            right_states: Vec::new(),
+            rollup:       None,
+            metadata:     None,
+            precision:    None,
+            keys:         KeyComparison::default(),
+            strategy:     MatchStrategy::default(),
+            differ:       Arc::new(JsonPatchDiffer::default()),
+            key_differs:  HashMap::new()
+        }
+    }
+
+    /// Score pairs with these differs (the default one, and per-key ones) instead of a plain JSON
+    /// Patch diff
+    pub fn with_differs(mut self, differ: Arc<dyn Differ>, key_differs: HashMap<String, Arc<dyn Differ>>) -> Self {
+        self.differ = differ;
+        self.key_differs = key_differs;
+        self
+    }
+
+    /// Pair states for the similarity scores the way the tracker pairs a round
+    pub fn with_match_strategy(mut self, strategy: MatchStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Count pairs matched the way the tracker aligned them; reported keys stay as received
+    pub fn with_key_comparison(mut self, keys: KeyComparison) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    /// Write payload numbers rounded to `digits` significant digits (display only)
+    pub fn with_display_precision(mut self, digits: u32) -> Self {
+        self.precision = Some(digits);
+        self
+    }
+
+    fn stats(&self) -> ReportStats {
+        let left_keys: Vec<Option<&str>> = self.left_states.iter().map(|s| s.alignment_key.as_deref()).collect();
+        let right_keys: Vec<Option<&str>> = self.right_states.iter().map(|s| s.alignment_key.as_deref()).collect();
+
+        ReportStats {
+            left:       self.left_states.len(),
+            right:      self.right_states.len(),
+            matched:    count_matched(&self.keys, &left_keys, &right_keys),
+            mismatched: count_mismatched(&self.keys, &left_keys, &right_keys)
+        }
+    }
+
+    fn arrivals(states: &[State]) -> Vec<(Option<&str>, DateTime<Utc>)> {
+        states.iter().map(|s| (s.alignment_key.as_deref(), s.timestamp)).collect()
+    }
+
+    fn payloads(states: &[State]) -> Vec<(Option<&str>, Option<&JsonValue>)> {
+        states.iter().map(|s| (s.alignment_key.as_deref(), Some(&s.data))).collect()
+    }
+
+    fn report_states<'a>(&self, states: &'a [State]) -> Vec<JsonReportState<'a>> {
+        states
+            .iter()
+            .map(|s| JsonReportState {
+                key:       s.alignment_key.as_deref(),
+                timestamp: s.timestamp.to_rfc3339(),
+                data:      match self.precision {
+                    Some(digits) => Cow::Owned(round_numbers(&s.data, digits)),
+                    None => Cow::Borrowed(&s.data)
+                }
+            })
+            .collect()
+    }
+}
+
+impl Reporter for JsonReporter {
+    fn add_left(&mut self, state: State) {
+        self.left_states.push(state);
+    }
+
+    fn add_right(&mut self, state: State) {
+        self.right_states.push(state);
+    }
+
+    fn set_rollup(&mut self, rollup: PathRollup) {
+        self.rollup = Some(rollup);
+    }
+
+    fn set_metadata(&mut self, metadata: RunMetadata) {
+        self.metadata = Some(metadata);
+    }
+
+    fn generate(&self, output_path: &str) -> std::io::Result<()> {
+        let report = JsonReport {
+            session_id:    &self.session_id,
+            started_at:    self.started_at.to_rfc3339(),
+            generated_at:  Utc::now().to_rfc3339(),
+            run:           self.metadata.as_ref(),
+            stats:         self.stats(),
+            latency:       lag_stats(
+                &self.keys,
+                &Self::arrivals(&self.left_states),
+                &Self::arrivals(&self.right_states)
+            ),
+            changed_paths: self.rollup.as_ref(),
+            pairs:         pair_scores(
+                self.strategy,
+                &self.keys,
+                &Self::payloads(&self.left_states),
+                &Self::payloads(&self.right_states),
+                |key, left, right| self.key_differs.get(key).unwrap_or(&self.differ).compute_diff(left, right)
+            ),
+            left:          self.report_states(&self.left_states),
+            right:         self.report_states(&self.right_states)
+        };
+
+        write_report(output_path, &serde_json::to_vec_pretty(&report)?)
+    }
+}
+
+impl Default for JsonReporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,56 @@
+use serde_json::Value;
+use tokio::{
+    sync::mpsc,
+    time::{Duration, sleep}
+};
+
+use crate::port::StateSource;
+
+/// In-memory source that emits a fixed list of states in order, then closes. Useful for driving
+/// the trackers deterministically (the scripted counterpart to `RandomStream`).
+#[derive(Clone, Debug)]
+pub struct VecSource {
+    pub name: String,
+    /// Each message with the delay to wait before emitting it
+    messages: Vec<(Duration, Value)>
+}
+
+impl VecSource {
+    /// Emit `messages` back to back
+    pub fn new<N: Into<String>>(name: N, messages: Vec<Value>) -> Self {
+        Self { name: name.into(), messages: messages.into_iter().map(|m| (Duration::ZERO, m)).collect() }
+    }
+
+    /// Emit each message after its own delay (measured from the previous message)
+    pub fn timed<N: Into<String>>(name: N, messages: Vec<(Duration, Value)>) -> Self {
+        Self { name: name.into(), messages }
+    }
+
+    /// Wait `delay` before every message
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        for (message_delay, _) in &mut self.messages {
+            *message_delay = delay;
+        }
+        self
+    }
+}
+
+impl StateSource for VecSource {
+    fn spawn(&self) -> mpsc::Receiver<Value> {
+        let (tx, rx) = mpsc::channel::<Value>(64);
+        let messages = self.messages.clone();
+
+        tokio::spawn(async move {
+            for (delay, message) in messages {
+                if !delay.is_zero() {
+                    sleep(delay).await;
+                }
+                if tx.send(message).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        rx
+    }
+}
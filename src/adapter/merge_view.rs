@@ -0,0 +1,135 @@
+use anstream::println;
+use owo_colors::OwoColorize;
+use serde_json::Value as JsonValue;
+
+use super::Theme;
+use crate::{domain::DiffResult, port::Differ};
+
+/// Differ that renders a merge-style view: the common base, what only the left side has and what
+/// only the right side has, each in its own section. A changed value shows up once on each side.
+pub struct MergeViewDiffer {
+    /// List the common base instead of only counting it
+    show_unchanged: bool,
+    theme:          Theme
+}
+
+/// Leaves of both documents, sorted by provenance
+#[derive(Default)]
+struct MergeView {
+    common:     Vec<(String, JsonValue)>,
+    left_only:  Vec<(String, JsonValue)>,
+    right_only: Vec<(String, JsonValue)>
+}
+
+impl MergeViewDiffer {
+    pub fn new() -> Self {
+        Self { show_unchanged: true, theme: Theme::default() }
+    }
+
+    /// List unchanged leaves (dimmed); when off, the common section only shows a count
+    pub fn with_show_unchanged(mut self, enabled: bool) -> Self {
+        self.show_unchanged = enabled;
+        self
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    fn print_section(title: String, entries: &[(String, JsonValue)], line: impl Fn(&str, &JsonValue) -> String) {
+        println!("{title} ({})", entries.len());
+        for (path, value) in entries {
+            println!("  {}", line(path, value));
+        }
+    }
+}
+
+impl MergeView {
+    /// Walk both values side by side; objects and arrays are descended into, anything else is a
+    /// leaf that is either common or split into a left-only and a right-only entry
+    fn collect(&mut self, path: &str, left: Option<&JsonValue>, right: Option<&JsonValue>) {
+        match (left, right) {
+            (Some(JsonValue::Object(l)), Some(JsonValue::Object(r))) => {
+                let mut keys: Vec<&String> = l.keys().chain(r.keys()).collect();
+                keys.sort();
+                keys.dedup();
+                for key in keys {
+                    let child = if path.is_empty() { key.to_string() } else { format!("{path}.{key}") };
+                    self.collect(&child, l.get(key), r.get(key));
+                }
+            }
+            (Some(JsonValue::Array(l)), Some(JsonValue::Array(r))) => {
+                for i in 0..l.len().max(r.len()) {
+                    self.collect(&format!("{path}[{i}]"), l.get(i), r.get(i));
+                }
+            }
+            (Some(l), Some(r)) if l == r => self.common.push((Self::label(path), l.clone())),
+            (l, r) => {
+                if let Some(l) = l {
+                    self.left_only.push((Self::label(path), l.clone()));
+                }
+                if let Some(r) = r {
+                    self.right_only.push((Self::label(path), r.clone()));
+                }
+            }
+        }
+    }
+
+    fn label(path: &str) -> String {
+        if path.is_empty() { "(root)".to_string() } else { path.to_string() }
+    }
+}
+
+impl Default for MergeViewDiffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Differ for MergeViewDiffer {
+    fn print_result(
+        &self,
+        left_label: &str,
+        right_label: &str,
+        left: &JsonValue,
+        right: &JsonValue,
+        result: &DiffResult
+    ) {
+        if result.identical {
+            tracing::info!("states are identical");
+            return;
+        }
+
+        let mut view = MergeView::default();
+        view.collect("", Some(left), Some(right));
+
+        println!(
+            "\n{} {} {} {} {}",
+            "merge".bold(),
+            self.theme.left(&left_label).bold(),
+            "+".dimmed(),
+            self.theme.right(&right_label).bold(),
+            "[merge view]".dimmed()
+        );
+
+        if self.show_unchanged {
+            Self::print_section("═ common".dimmed().to_string(), &view.common, |path, value| {
+                format!("{path}: {value}").dimmed().to_string()
+            });
+        } else {
+            println!("{}", format!("═ common ({}, hidden)", view.common.len()).dimmed());
+        }
+        Self::print_section(
+            self.theme.left(&format!("− {left_label} only")).to_string(),
+            &view.left_only,
+            |path, value| format!("{}: {}", self.theme.left(&path), value.red())
+        );
+        Self::print_section(
+            self.theme.right(&format!("+ {right_label} only")).to_string(),
+            &view.right_only,
+            |path, value| format!("{}: {}", self.theme.right(&path), value.green())
+        );
+        println!();
+    }
+}
@@ -1,11 +1,45 @@
+mod entity;
+mod external;
+mod file;
+#[cfg(feature = "http")]
+mod http;
+mod interactive;
+mod json_reporter;
+mod memory;
+mod merge_view;
+#[cfg(feature = "nats")]
+mod nats;
 mod patcher;
+#[cfg(feature = "compression")]
+mod payload;
+mod policy;
+mod recorder;
 mod reporter;
+mod stdin;
 mod stream;
+mod theme;
 mod visualizer;
 mod websocket;
 
+pub use entity::*;
+pub use external::*;
+pub use file::*;
+#[cfg(feature = "http")]
+pub use http::*;
+pub use interactive::*;
+pub use json_reporter::*;
+pub use memory::*;
+pub use merge_view::*;
+#[cfg(feature = "nats")]
+pub use nats::*;
 pub use patcher::*;
+#[cfg(feature = "compression")]
+pub use payload::*;
+pub use policy::*;
+pub use recorder::*;
 pub use reporter::*;
+pub use stdin::*;
 pub use stream::*;
+pub use theme::*;
 pub use visualizer::*;
 pub use websocket::*;
@@ -1,23 +1,336 @@
-use json_patch::diff as json_patch_diff;
+use std::{borrow::Cow, collections::HashMap};
+
+use anstream::println;
 use owo_colors::OwoColorize;
 use serde_json::Value as JsonValue;
 
-use crate::port::Differ;
+use super::Theme;
+use crate::{
+    domain::{
+        ChangeType, DiffEvent, DiffLine, DiffResult, Tolerance, canonicalize, explain_changes, flat_changes,
+        key_array_items, parse_pointer, positional_patch, prune_matching, reconcile_numbers, round_numbers,
+        unified_hunks
+    },
+    port::Differ
+};
 
 #[derive(Debug, Clone, Copy)]
 pub enum DiffEngine {
     JsonPatch,
-    SerdeDiff
+    SerdeDiff,
+    /// One JSON object per comparison on a single line (JSONL), without colors, for CI to parse
+    Json,
+    /// Classic unified diff of both sides pretty-printed with sorted keys, `-`/`+` lines with
+    /// context, e.g. to paste into a review
+    Unified
 }
 
+/// Unchanged lines shown around each change in unified diffs
+const UNIFIED_CONTEXT: usize = 3;
+
 pub struct JsonPatchDiffer {
-    pretty: bool,
-    engine: DiffEngine
+    pretty:            bool,
+    engine:            DiffEngine,
+    /// Ignore leading/trailing whitespace when comparing strings
+    trim_strings:      bool,
+    /// Ignore case when comparing strings
+    case_insensitive:  bool,
+    /// Treat a numeric string and a number with the same value as equal
+    coerce_numeric:    bool,
+    /// Treat an empty object/array on one side and a missing key on the other as equal
+    empty_eq_absent:   bool,
+    /// Treat numbers this close as equal
+    float_tolerance:   Option<Tolerance>,
+    /// Print at most this many changes per diff, then summarize the rest
+    max_ops:           Option<usize>,
+    /// Follow the technical diff with one plain-language sentence per change
+    explain:           bool,
+    /// Print `serde_json_diff`'s nested structure instead of one line per changed path
+    serde_raw:         bool,
+    /// In pretty diffs, end each changed object with a count of its unchanged fields
+    unchanged_summary: bool,
+    /// Round printed numbers to this many significant digits (comparison is unaffected)
+    display_precision: Option<u32>,
+    /// Paths (parsed JSON Pointers, `*` matching any item) removed from both sides before diffing
+    ignore_paths:      Vec<Vec<String>>,
+    /// Arrays (parsed JSON Pointers) whose items are matched by an identity field, with the field
+    array_keys:        Vec<(Vec<String>, String)>,
+    theme:             Theme
+}
+
+/// `{"left_label":…,"right_label":…,"similarity":…,"ops_count":N,"patch":[…]}` on one line;
+/// identical states get a line too (with an empty patch), so every comparison is accounted for
+pub(crate) fn print_json_line(event: &DiffEvent) {
+    match serde_json::to_string(event) {
+        Ok(line) => println!("{line}"),
+        Err(err) => tracing::warn!("failed to serialize diff: {err}")
+    }
+}
+
+/// Counts changes during a diff and tells whether each one still fits in the print budget
+struct OpBudget {
+    limit: Option<usize>,
+    seen:  usize
+}
+
+impl OpBudget {
+    fn new(limit: Option<usize>) -> Self {
+        Self { limit, seen: 0 }
+    }
+
+    /// Whether another change would still be printed
+    fn has_room(&self) -> bool {
+        self.limit.is_none_or(|limit| self.seen < limit)
+    }
+
+    /// Count one change; returns whether it should be printed
+    fn take(&mut self) -> bool {
+        let print = self.has_room();
+        self.seen += 1;
+        print
+    }
+
+    fn hidden(&self) -> usize {
+        self.limit.map_or(0, |limit| self.seen.saturating_sub(limit))
+    }
+
+    fn print_summary(&self) {
+        let hidden = self.hidden();
+        if hidden > 0 {
+            println!("{}", format!("…and {hidden} more changes (use --report for full detail)").dimmed());
+        }
+    }
 }
 
 impl JsonPatchDiffer {
     pub fn new(pretty: bool, engine: DiffEngine) -> Self {
-        Self { pretty, engine }
+        Self {
+            pretty,
+            engine,
+            trim_strings: false,
+            case_insensitive: false,
+            coerce_numeric: false,
+            empty_eq_absent: false,
+            float_tolerance: None,
+            max_ops: None,
+            explain: false,
+            serde_raw: false,
+            unchanged_summary: false,
+            display_precision: None,
+            ignore_paths: Vec::new(),
+            array_keys: Vec::new(),
+            theme: Theme::default()
+        }
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Print at most `max` changes per diff; the remainder is still counted and summarized
+    pub fn with_max_ops(mut self, max: usize) -> Self {
+        self.max_ops = Some(max);
+        self
+    }
+
+    /// Treat strings that only differ in surrounding whitespace and/or case as equal. The output
+    /// still shows the raw values of strings that do differ.
+    pub fn with_string_normalization(mut self, trim: bool, case_insensitive: bool) -> Self {
+        self.trim_strings = trim;
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// In pretty diffs, print a dimmed "(+N unchanged fields)" line under each changed object, so
+    /// the changes come with a sense of how much stayed the same
+    pub fn with_unchanged_summary(mut self, enabled: bool) -> Self {
+        self.unchanged_summary = enabled;
+        self
+    }
+
+    /// Annotate each diff with plain-language sentences ("field `hp` decreased from 100 to 80")
+    pub fn with_explain(mut self, enabled: bool) -> Self {
+        self.explain = enabled;
+        self
+    }
+
+    /// With the serde_json_diff engine, print the crate's nested diff structure as is instead of
+    /// one `path change_type old → new` line per change
+    pub fn with_serde_raw(mut self, enabled: bool) -> Self {
+        self.serde_raw = enabled;
+        self
+    }
+
+    /// Compare a numeric string on one side with a number on the other by value (e.g., `"42"` and
+    /// `42`). Off by default since some contracts rely on the distinction.
+    pub fn with_coerce_numeric_strings(mut self, coerce: bool) -> Self {
+        self.coerce_numeric = coerce;
+        self
+    }
+
+    /// Treat `{}` or `[]` on one side and a missing key on the other as equal, for services that
+    /// omit empty fields where others send them. Unlike a null, an empty container still differs
+    /// from a non-empty one.
+    pub fn with_empty_equals_absent(mut self, enabled: bool) -> Self {
+        self.empty_eq_absent = enabled;
+        self
+    }
+
+    /// Treat numbers that differ by at most `tolerance` as equal (e.g., `19.99` and `19.990001`
+    /// with 0.001), integers and floats alike; the output shows left's value for them
+    pub fn with_float_tolerance(mut self, tolerance: f64) -> Self {
+        self.float_tolerance.get_or_insert_default().absolute = tolerance;
+        self
+    }
+
+    /// Treat numbers that differ by at most `ratio` of the larger one as equal (e.g., `1000` and
+    /// `1009` with 0.01); combined with an absolute tolerance, whichever allows more applies
+    pub fn with_relative_tolerance(mut self, ratio: f64) -> Self {
+        self.float_tolerance.get_or_insert_default().relative = ratio;
+        self
+    }
+
+    /// Print numbers rounded to `digits` significant digits, so float noise like
+    /// `0.30000000000000004` reads `0.3`; values are still compared exactly
+    pub fn with_display_precision(mut self, digits: u32) -> Self {
+        self.display_precision = Some(digits);
+        self
+    }
+
+    /// Remove these JSON Pointers from both sides before diffing, so fields that change on every
+    /// message (e.g., "/timestamp") never show up; a `*` segment matches every array item or
+    /// object member, as in "/items/*/id"
+    pub fn with_ignore_paths(mut self, pointers: Vec<String>) -> Self {
+        self.ignore_paths = pointers.iter().map(|pointer| parse_pointer(pointer)).collect();
+        self
+    }
+
+    /// Match the items of the array at each JSON Pointer (`*` matching any item or member, as in
+    /// "/teams/*/players") by the given identity field instead of by position, e.g.
+    /// `{"/orders": "id"}`. Reordered items then compare equal, and diffs name items as
+    /// "[id=7]", reporting whole items as added or removed and changed ones field by field.
+    pub fn with_array_key_paths(mut self, paths: HashMap<String, String>) -> Self {
+        self.array_keys = paths.into_iter().map(|(pointer, field)| (parse_pointer(&pointer), field)).collect();
+        self
+    }
+
+    /// A side with keyed arrays turned into objects and the ignored paths removed
+    fn masked<'a>(&self, value: &'a JsonValue) -> Cow<'a, JsonValue> {
+        if self.ignore_paths.is_empty() && self.array_keys.is_empty() {
+            return Cow::Borrowed(value);
+        }
+        let mut masked = value.clone();
+        // Keyed first, so an ignored id field can still identify its item
+        for (path, field) in &self.array_keys {
+            key_array_items(&mut masked, path, field);
+        }
+        for path in &self.ignore_paths {
+            prune_matching(&mut masked, path);
+        }
+        Cow::Owned(masked)
+    }
+
+    /// Result of `diff_masked` with keyed array segments turned back into positions in the original
+    /// sides, for output that is parsed as JSON Patch; a no-op without array keys
+    fn positional(&self, result: DiffResult, left: &JsonValue, right: &JsonValue) -> DiffResult {
+        if self.array_keys.is_empty() || result.identical {
+            return result;
+        }
+        let (mut left, mut right) = (left.clone(), right.clone());
+        for path in &self.ignore_paths {
+            prune_matching(&mut left, path);
+            prune_matching(&mut right, path);
+        }
+        DiffResult::from_patch(positional_patch(result.patch, &left, &right))
+    }
+
+    /// A value as it should be printed: rounded with a display precision, untouched otherwise
+    fn shown(&self, value: JsonValue) -> JsonValue {
+        match self.display_precision {
+            Some(digits) => round_numbers(&value, digits),
+            None => value
+        }
+    }
+
+    fn normalize<'a>(&self, s: &'a str) -> std::borrow::Cow<'a, str> {
+        let s = if self.trim_strings { s.trim() } else { s };
+        if self.case_insensitive { s.to_lowercase().into() } else { s.into() }
+    }
+
+    /// Copy of `right` with everything that matches `left` under the normalization options
+    /// replaced by left's value
+    fn reconciled(&self, left: &JsonValue, right: &JsonValue) -> JsonValue {
+        let mut normalized = right.clone();
+        self.reconcile(left, &mut normalized);
+        if let Some(tolerance) = self.float_tolerance {
+            reconcile_numbers(left, &mut normalized, tolerance);
+        }
+        normalized
+    }
+
+    fn reconciles(&self) -> bool {
+        self.trim_strings
+            || self.case_insensitive
+            || self.coerce_numeric
+            || self.empty_eq_absent
+            || self.float_tolerance.is_some()
+    }
+
+    /// Copy left's value into right wherever both sides hold strings that normalize equally or,
+    /// with numeric coercion, a numeric string and a number of the same value. With
+    /// empty-equals-absent, right's object keys are made to match left's where only an empty
+    /// container stands between them. Numbers within the tolerance are reconciled afterwards.
+    fn reconcile(&self, left: &JsonValue, right: &mut JsonValue) {
+        match (left, right) {
+            (JsonValue::String(l), JsonValue::String(r)) if self.normalize(l) == self.normalize(r) => {
+                *r = l.clone();
+            }
+            (_, r) if self.coerce_numeric && numeric_string_eq(left, r) => {
+                *r = left.clone();
+            }
+            (JsonValue::Object(l), JsonValue::Object(r)) => {
+                if self.empty_eq_absent {
+                    r.retain(|key, r_val| l.contains_key(key) || !is_empty_container(r_val));
+                    for (key, l_val) in l {
+                        if is_empty_container(l_val) && !r.contains_key(key) {
+                            r.insert(key.clone(), l_val.clone());
+                        }
+                    }
+                }
+                for (key, r_val) in r.iter_mut() {
+                    if let Some(l_val) = l.get(key) {
+                        self.reconcile(l_val, r_val);
+                    }
+                }
+            }
+            (JsonValue::Array(l), JsonValue::Array(r)) => {
+                for (l_val, r_val) in l.iter().zip(r.iter_mut()) {
+                    self.reconcile(l_val, r_val);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn is_empty_container(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Object(map) => map.is_empty(),
+        JsonValue::Array(items) => items.is_empty(),
+        _ => false
+    }
+}
+
+/// Whether one side is a number and the other a string parsing to the same value
+fn numeric_string_eq(left: &JsonValue, right: &JsonValue) -> bool {
+    let (s, n) = match (left, right) {
+        (JsonValue::String(s), JsonValue::Number(n)) | (JsonValue::Number(n), JsonValue::String(s)) => (s, n),
+        _ => return false
+    };
+    match (s.trim().parse::<f64>(), n.as_f64()) {
+        (Ok(parsed), Some(number)) => parsed == number,
+        _ => false
     }
 }
 
@@ -28,64 +341,161 @@ impl Default for JsonPatchDiffer {
 }
 
 impl Differ for JsonPatchDiffer {
-    fn print_diff(&self, left_label: &str, right_label: &str, left: &JsonValue, right: &JsonValue) {
-        if left == right {
+    fn compute_diff(&self, left: &JsonValue, right: &JsonValue) -> DiffResult {
+        self.positional(self.diff_masked(&self.masked(left), &self.masked(right)), left, right)
+    }
+
+    fn print_result(
+        &self,
+        left_label: &str,
+        right_label: &str,
+        left: &JsonValue,
+        right: &JsonValue,
+        result: &DiffResult
+    ) {
+        if let DiffEngine::Json = self.engine {
+            print_json_line(&DiffEvent::new(left_label, right_label, left, right, result.clone()));
+            return;
+        }
+
+        if result.identical {
             tracing::info!("states are identical");
             return;
         }
 
+        let (left, right) = (self.masked(left), self.masked(right));
+        let (left, right) = (left.as_ref(), right.as_ref());
+
+        // Renderers other than the patch work on the values themselves
+        let reconciled;
+        let right = if self.reconciles() {
+            reconciled = self.reconciled(left, right);
+            &reconciled
+        } else {
+            right
+        };
+
         if self.pretty {
             self.print_pretty_diff(left_label, right_label, left, right);
         } else {
             match self.engine {
-                DiffEngine::JsonPatch => self.print_json_patch_diff(left_label, right_label, left, right),
-                DiffEngine::SerdeDiff => self.print_serde_diff(left_label, right_label, left, right)
+                DiffEngine::JsonPatch => self.print_json_patch_diff(left_label, right_label, result),
+                DiffEngine::SerdeDiff => self.print_serde_diff(left_label, right_label, left, right),
+                DiffEngine::Unified => self.print_unified_diff(left_label, right_label, left, right),
+                DiffEngine::Json => unreachable!("JSON lines are printed before the identical check")
             }
         }
+
+        if self.explain {
+            self.print_explanation(left, right);
+        }
     }
 }
 
 impl JsonPatchDiffer {
-    fn print_json_patch_diff(&self, left_label: &str, right_label: &str, left: &JsonValue, right: &JsonValue) {
-        let patch = json_patch_diff(left, right);
-        let patch_json = match serde_json::to_value(&patch) {
-            Ok(v) => v,
+    /// Diff two sides whose ignored paths are already removed
+    fn diff_masked(&self, left: &JsonValue, right: &JsonValue) -> DiffResult {
+        if self.reconciles() {
+            DiffResult::between(left, &self.reconciled(left, right))
+        } else {
+            DiffResult::between(left, right)
+        }
+    }
+
+    fn print_json_patch_diff(&self, left_label: &str, right_label: &str, result: &DiffResult) {
+        let patch_json = match serde_json::to_value(&result.patch) {
+            Ok(v) => self.shown(v),
             Err(_) => JsonValue::Null
         };
-        let ops_count = patch_json.as_array().map(|a| a.len()).unwrap_or(1);
 
         println!(
             "\n{} {} -> {} ({} ops) [json-patch]",
             "diff".bold(),
-            left_label.blue().bold(),
-            right_label.magenta().bold(),
-            ops_count
+            self.theme.left(&left_label).bold(),
+            self.theme.right(&right_label).bold(),
+            result.ops_count
         );
 
-        // Pretty print the JSON directly
-        let json_string = serde_json::to_string_pretty(&patch_json).unwrap_or_else(|_| "[]".to_string());
+        // Pretty print the JSON directly, within the op budget
+        let mut budget = OpBudget::new(self.max_ops);
+        let shown = match patch_json {
+            JsonValue::Array(ops) => JsonValue::Array(ops.into_iter().filter(|_| budget.take()).collect()),
+            other => other
+        };
+        let json_string = serde_json::to_string_pretty(&shown).unwrap_or_else(|_| "[]".to_string());
         println!("{}", json_string);
+        budget.print_summary();
     }
 
     fn print_serde_diff(&self, left_label: &str, right_label: &str, left: &JsonValue, right: &JsonValue) {
         println!(
             "\n{} {} {} {} {}",
             "diff".bold(),
-            left_label.blue().bold(),
+            self.theme.left(&left_label).bold(),
             "→".dimmed(),
-            right_label.magenta().bold(),
+            self.theme.right(&right_label).bold(),
             "[serde_json_diff]".dimmed()
         );
 
-        match serde_json_diff::values(left.clone(), right.clone()) {
-            Some(diff) => {
-                // Serialize the structured diff directly
-                let diff_json = serde_json::to_value(&diff).unwrap_or(JsonValue::Null);
-                let json_string = serde_json::to_string_pretty(&diff_json).unwrap_or_else(|_| "{}".to_string());
-                println!("{}", json_string);
+        let (left, right) = (canonicalize(left), canonicalize(right));
+        if self.serde_raw {
+            match serde_json_diff::values(left, right) {
+                Some(diff) => {
+                    // Serialize the structured diff directly
+                    let diff_json = self.shown(serde_json::to_value(&diff).unwrap_or(JsonValue::Null));
+                    let json_string = serde_json::to_string_pretty(&diff_json).unwrap_or_else(|_| "{}".to_string());
+                    println!("{}", json_string);
+                }
+                None => {
+                    println!("{}", "  (no differences)".dimmed());
+                }
             }
-            None => {
-                println!("{}", "  (no differences)".dimmed());
+            return;
+        }
+
+        let changes = flat_changes(&left, &right);
+        if changes.is_empty() {
+            println!("{}", "  (no differences)".dimmed());
+        }
+        // One line per path so a field can be grepped for: "/a/b changed 1 → 2"
+        let mut budget = OpBudget::new(self.max_ops);
+        for change in changes.into_iter().filter(|_| budget.take()) {
+            let value = |value: Option<JsonValue>| value.map(|v| self.shown(v).to_string()).unwrap_or_default();
+            let (old, new) = (value(change.old), value(change.new));
+            let detail = match change.change_type {
+                ChangeType::Added => self.theme.right(&new).to_string(),
+                ChangeType::Removed => self.theme.left(&old).to_string(),
+                ChangeType::Changed | ChangeType::TypeChanged => {
+                    format!("{} {} {}", self.theme.left(&old), "→".dimmed(), self.theme.right(&new))
+                }
+            };
+            let path = if change.path.is_empty() { "(root)" } else { change.path.as_str() };
+            println!("{} {} {detail}", path.bold(), change.change_type.yellow());
+        }
+        budget.print_summary();
+    }
+
+    fn print_unified_diff(&self, left_label: &str, right_label: &str, left: &JsonValue, right: &JsonValue) {
+        // Sorted keys keep the line order, and so the diff, the same from run to run
+        let render =
+            |value: &JsonValue| serde_json::to_string_pretty(&self.shown(canonicalize(value))).unwrap_or_default();
+        let (left_text, right_text) = (render(left), render(right));
+
+        println!("\n{} {}", "---".bold(), self.theme.left(&left_label).bold());
+        println!("{} {}", "+++".bold(), self.theme.right(&right_label).bold());
+        let hunks = unified_hunks(&left_text, &right_text, UNIFIED_CONTEXT);
+        if hunks.is_empty() {
+            // Only rounding for display made the sides look alike
+            println!("{}", "  (no differences at this display precision)".dimmed());
+        }
+        for hunk in hunks {
+            println!("{}", hunk.header().cyan());
+            for line in hunk.lines {
+                match line {
+                    DiffLine::Context(text) => println!(" {text}"),
+                    DiffLine::Removed(text) => println!("{}", self.theme.left(&format!("-{text}"))),
+                    DiffLine::Added(text) => println!("{}", self.theme.right(&format!("+{text}")))
+                }
             }
         }
     }
@@ -94,16 +504,18 @@ impl JsonPatchDiffer {
         println!(
             "\n{} {} {} {}",
             "━━━".dimmed(),
-            left_label.blue().bold(),
+            self.theme.left(&left_label).bold(),
             "vs".dimmed(),
-            right_label.magenta().bold()
+            self.theme.right(&right_label).bold()
         );
 
-        self.print_value_diff("", left, right, 0);
+        let mut budget = OpBudget::new(self.max_ops);
+        self.print_value_diff("", left, right, 0, &mut budget);
+        budget.print_summary();
         println!();
     }
 
-    fn print_value_diff(&self, path: &str, left: &JsonValue, right: &JsonValue, indent: usize) {
+    fn print_value_diff(&self, path: &str, left: &JsonValue, right: &JsonValue, indent: usize, budget: &mut OpBudget) {
         let indent_str = "  ".repeat(indent);
 
         match (left, right) {
@@ -111,52 +523,66 @@ impl JsonPatchDiffer {
                 let mut all_keys = std::collections::BTreeSet::new();
                 all_keys.extend(l_obj.keys());
                 all_keys.extend(r_obj.keys());
+                let mut unchanged = 0;
 
                 for key in all_keys {
                     let current_path = if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
 
                     match (l_obj.get(key), r_obj.get(key)) {
                         (Some(l_val), Some(r_val)) => {
-                            if l_val != r_val {
+                            if l_val == r_val {
+                                unchanged += 1;
+                            } else {
                                 if l_val.is_object() || r_val.is_object() || l_val.is_array() || r_val.is_array() {
-                                    println!("{}{}", indent_str, key.bold());
-                                    self.print_value_diff(&current_path, l_val, r_val, indent + 1);
-                                } else {
+                                    if budget.has_room() {
+                                        println!("{}{}", indent_str, key.bold());
+                                    }
+                                    self.print_value_diff(&current_path, l_val, r_val, indent + 1, budget);
+                                } else if budget.take() {
                                     println!(
                                         "{}{}: {} {} {}",
                                         indent_str,
                                         key.bold(),
-                                        Self::format_value(l_val).red().strikethrough(),
+                                        self.format_value(l_val).red().strikethrough(),
                                         "→".yellow(),
-                                        Self::format_value(r_val).green()
+                                        self.format_value(r_val).green()
                                     );
                                 }
                             }
                         }
                         (Some(l_val), None) => {
-                            println!(
-                                "{}{}: {} {}",
-                                indent_str,
-                                key.bold(),
-                                Self::format_value(l_val).red().strikethrough(),
-                                "(removed)".red().dimmed()
-                            );
+                            if budget.take() {
+                                println!(
+                                    "{}{}: {} {}",
+                                    indent_str,
+                                    key.bold(),
+                                    self.format_value(l_val).red().strikethrough(),
+                                    "(removed)".red().dimmed()
+                                );
+                            }
                         }
                         (None, Some(r_val)) => {
-                            println!(
-                                "{}{}: {} {}",
-                                indent_str,
-                                key.bold(),
-                                "(added)".green().dimmed(),
-                                Self::format_value(r_val).green()
-                            );
+                            if budget.take() {
+                                println!(
+                                    "{}{}: {} {}",
+                                    indent_str,
+                                    key.bold(),
+                                    "(added)".green().dimmed(),
+                                    self.format_value(r_val).green()
+                                );
+                            }
                         }
                         (None, None) => {}
                     }
                 }
+
+                if self.unchanged_summary && unchanged > 0 && budget.has_room() {
+                    let fields = if unchanged == 1 { "field" } else { "fields" };
+                    println!("{}{}", indent_str, format!("(+{unchanged} unchanged {fields})").dimmed());
+                }
             }
             (JsonValue::Array(l_arr), JsonValue::Array(r_arr)) => {
-                if l_arr != r_arr {
+                if l_arr != r_arr && budget.take() {
                     println!(
                         "{}[array changed: {} {} {}]",
                         indent_str,
@@ -167,26 +593,126 @@ impl JsonPatchDiffer {
                 }
             }
             _ => {
-                if left != right {
+                if left != right && budget.take() {
                     println!(
                         "{}{} {} {}",
                         indent_str,
-                        Self::format_value(left).red().strikethrough(),
+                        self.format_value(left).red().strikethrough(),
                         "→".yellow(),
-                        Self::format_value(right).green()
+                        self.format_value(right).green()
                     );
                 }
             }
         }
     }
 
-    fn format_value(val: &JsonValue) -> String {
+    fn print_explanation(&self, left: &JsonValue, right: &JsonValue) {
+        println!("{}", "In plain words:".italic());
+        let mut budget = OpBudget::new(self.max_ops);
+        for sentence in explain_changes(&canonicalize(left), &canonicalize(right)) {
+            if budget.take() {
+                println!("  • {sentence}");
+            }
+        }
+        budget.print_summary();
+    }
+
+    fn format_value(&self, val: &JsonValue) -> String {
         match val {
             JsonValue::String(s) => format!("\"{}\"", s),
-            JsonValue::Number(n) => n.to_string(),
+            JsonValue::Number(_) => self.shown(val.clone()).to_string(),
             JsonValue::Bool(b) => b.to_string(),
             JsonValue::Null => "null".to_string(),
-            _ => val.to_string()
+            _ => self.shown(val.clone()).to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn keyed_differ() -> JsonPatchDiffer {
+        JsonPatchDiffer::new(false, DiffEngine::Json)
+            .with_array_key_paths(HashMap::from([("/orders".to_string(), "id".to_string())]))
+            .with_ignore_paths(vec!["/orders/*/ts".to_string()])
+    }
+
+    #[test]
+    fn keyed_patches_use_positions_and_apply_to_the_left_side() {
+        let left =
+            json!({"orders": [{"id": 1, "q": 1, "ts": 1}, {"id": 2, "q": 2, "ts": 1}, {"id": 3, "q": 3, "ts": 1}]});
+        let right =
+            json!({"orders": [{"id": 2, "q": 5, "ts": 2}, {"id": 3, "q": 3, "ts": 2}, {"id": 4, "q": 4, "ts": 2}]});
+        let result = keyed_differ().compute_diff(&left, &right);
+
+        assert_eq!(
+            serde_json::to_value(&result.patch).unwrap(),
+            json!([
+                {"op": "replace", "path": "/orders/1/q", "value": 5},
+                {"op": "remove", "path": "/orders/0"},
+                {"op": "add", "path": "/orders/2", "value": {"id": 4, "q": 4}}
+            ])
+        );
+        let mut patched = left;
+        json_patch::patch(&mut patched, &result.patch).unwrap();
+        for item in patched["orders"].as_array_mut().unwrap() {
+            item.as_object_mut().unwrap().remove("ts");
         }
+        assert_eq!(patched, json!({"orders": [{"id": 2, "q": 5}, {"id": 3, "q": 3}, {"id": 4, "q": 4}]}));
+    }
+
+    #[test]
+    fn reordered_keyed_items_are_identical() {
+        let left = json!({"orders": [{"id": 1, "q": 1}, {"id": 2, "q": 2}]});
+        let right = json!({"orders": [{"id": 2, "q": 2}, {"id": 1, "q": 1}]});
+        assert!(keyed_differ().compute_diff(&left, &right).identical);
+    }
+
+    #[test]
+    fn unkeyed_patches_are_left_alone() {
+        let differ = JsonPatchDiffer::default();
+        let result = differ.compute_diff(&json!({"a": [1, 2]}), &json!({"a": [1, 3]}));
+        assert_eq!(
+            serde_json::to_value(&result.patch).unwrap(),
+            json!([{"op": "replace", "path": "/a/1", "value": 3}])
+        );
+    }
+
+    #[test]
+    fn ignored_paths_are_removed_from_both_sides() {
+        let differ = JsonPatchDiffer::default().with_ignore_paths(vec!["/timestamp".into(), "/items/*/seen".into()]);
+        let left = json!({"timestamp": 1, "items": [{"id": 1, "seen": 1}], "status": "open"});
+        let right = json!({"timestamp": 2, "items": [{"id": 1, "seen": 2}], "status": "open"});
+        assert!(differ.compute_diff(&left, &right).identical);
+
+        let right = json!({"items": [{"id": 2, "seen": 2}], "status": "open"});
+        assert_eq!(
+            serde_json::to_value(&differ.compute_diff(&left, &right).patch).unwrap(),
+            json!([{"op": "replace", "path": "/items/0/id", "value": 2}])
+        );
+    }
+
+    #[test]
+    fn ops_follow_key_order_whatever_the_field_order() {
+        let left: JsonValue = serde_json::from_str(r#"{"b": 1, "a": 1}"#).unwrap();
+        let right: JsonValue = serde_json::from_str(r#"{"a": 2, "b": 2}"#).unwrap();
+        assert_eq!(
+            serde_json::to_value(&JsonPatchDiffer::default().compute_diff(&left, &right).patch).unwrap(),
+            json!([{"op": "replace", "path": "/a", "value": 2}, {"op": "replace", "path": "/b", "value": 2}])
+        );
+    }
+
+    #[test]
+    fn absolute_and_relative_tolerances_combine() {
+        let left = json!({"price": 19.99, "volume": 1000, "count": 1});
+        let right = json!({"price": 19.990001, "volume": 1009, "count": 2});
+        let differ = JsonPatchDiffer::default().with_float_tolerance(0.001).with_relative_tolerance(0.01);
+        assert_eq!(
+            serde_json::to_value(&differ.compute_diff(&left, &right).patch).unwrap(),
+            json!([{"op": "replace", "path": "/count", "value": 2}])
+        );
     }
 }
@@ -0,0 +1,57 @@
+use std::io::Read;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use serde_json::Value;
+
+use crate::domain::{navigate, parse_dotted};
+
+/// Compression applied to an envelope's payload before it was base64-encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PayloadCompression {
+    #[default]
+    Gzip,
+    /// zlib-wrapped deflate, as in HTTP's `Content-Encoding: deflate`
+    Deflate
+}
+
+/// Unwraps application-level envelopes such as `{"enc":"gzip+b64","payload":"H4sI..."}`: the
+/// payload field is base64-decoded, decompressed and parsed, and the inner JSON replaces the
+/// envelope. Messages without the field aren't envelopes and pass through unchanged.
+#[derive(Debug, Clone)]
+pub struct PayloadDecoder {
+    /// Dot-separated path of the encoded payload
+    field:       Vec<String>,
+    compression: PayloadCompression
+}
+
+impl PayloadDecoder {
+    pub fn new(field: &str, compression: PayloadCompression) -> Self {
+        Self { field: parse_dotted(field), compression }
+    }
+
+    /// Base64, then gunzip (the `gzip+b64` envelope)
+    pub fn gzip_base64(field: &str) -> Self {
+        Self::new(field, PayloadCompression::Gzip)
+    }
+
+    /// The inner JSON of an envelope, the message itself when it has no payload field, or an error
+    /// describing why the payload couldn't be decoded
+    pub fn decode(&self, message: Value) -> Result<Value, String> {
+        let Some(payload) = navigate(&message, &self.field) else {
+            return Ok(message);
+        };
+        let Value::String(encoded) = payload else {
+            return Err(format!("payload field {} is not a string", self.field.join(".")));
+        };
+
+        let compressed = STANDARD.decode(encoded.trim()).map_err(|err| format!("invalid base64 payload: {err}"))?;
+        let mut text = String::new();
+        let inflated = match self.compression {
+            PayloadCompression::Gzip => GzDecoder::new(compressed.as_slice()).read_to_string(&mut text),
+            PayloadCompression::Deflate => ZlibDecoder::new(compressed.as_slice()).read_to_string(&mut text)
+        };
+        inflated.map_err(|err| format!("failed to decompress payload: {err}"))?;
+        serde_json::from_str(&text).map_err(|err| format!("decoded payload is not JSON: {err}"))
+    }
+}
@@ -0,0 +1,141 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::Deserialize;
+use serde_json::{Map, Value as JsonValue};
+
+use crate::{
+    domain::{DiffResult, Tolerance, TrackerError, insert, navigate, parse_pointer, reconcile_numbers, remove_pointer},
+    port::Differ
+};
+
+/// Per-key comparison rules loaded from a TOML policy file, e.g.
+///
+/// ```toml
+/// [keys.GameResult]
+/// ignore = ["/timestamp", "/data/metadata"]
+/// float_tolerance = 0.001
+/// relative_tolerance = 0.01
+///
+/// [keys."order.completed"]
+/// only = ["/data"]
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DiffPolicy {
+    #[serde(default)]
+    pub keys: HashMap<String, KeyRules>
+}
+
+impl DiffPolicy {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, TrackerError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        toml::from_str(&text).map_err(|e| TrackerError::Config(format!("{}: {e}", path.display())))
+    }
+}
+
+/// Comparison rules for a single alignment key. Paths are JSON Pointers (e.g. "/data/amount").
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyRules {
+    /// Paths removed from both sides before diffing
+    #[serde(default)]
+    pub ignore:             Vec<String>,
+    /// When non-empty, only these paths are compared
+    #[serde(default)]
+    pub only:               Vec<String>,
+    /// Numbers at most this far apart are treated as equal
+    pub float_tolerance:    Option<f64>,
+    /// Numbers at most this fraction of the larger one apart are treated as equal (e.g., 0.01)
+    pub relative_tolerance: Option<f64>
+}
+
+impl KeyRules {
+    /// Apply the rules to copies of both sides
+    pub fn apply(&self, left: &JsonValue, right: &JsonValue) -> (JsonValue, JsonValue) {
+        let mut left = self.project(left);
+        let mut right = self.project(right);
+
+        for pointer in &self.ignore {
+            remove_pointer(&mut left, pointer);
+            remove_pointer(&mut right, pointer);
+        }
+
+        if self.float_tolerance.is_some() || self.relative_tolerance.is_some() {
+            let tolerance = Tolerance {
+                absolute: self.float_tolerance.unwrap_or_default(),
+                relative: self.relative_tolerance.unwrap_or_default()
+            };
+            reconcile_numbers(&left, &mut right, tolerance);
+        }
+
+        (left, right)
+    }
+
+    fn project(&self, value: &JsonValue) -> JsonValue {
+        if self.only.is_empty() {
+            return value.clone();
+        }
+
+        let mut projected = JsonValue::Object(Map::new());
+        for pointer in &self.only {
+            let path = parse_pointer(pointer);
+            if let Some(found) = navigate(value, &path) {
+                insert(&mut projected, &path, found.clone());
+            }
+        }
+        projected
+    }
+}
+
+/// Differ that applies a key's rules to both sides, then delegates to the wrapped differ
+pub struct RuleDiffer<D: Differ> {
+    rules: KeyRules,
+    inner: D
+}
+
+impl<D: Differ> RuleDiffer<D> {
+    pub fn new(rules: KeyRules, inner: D) -> Self {
+        Self { rules, inner }
+    }
+}
+
+impl<D: Differ> Differ for RuleDiffer<D> {
+    fn print_result(
+        &self,
+        left_label: &str,
+        right_label: &str,
+        left: &JsonValue,
+        right: &JsonValue,
+        result: &DiffResult
+    ) {
+        let (left, right) = self.rules.apply(left, right);
+        self.inner.print_result(left_label, right_label, &left, &right, result);
+    }
+
+    fn compute_diff(&self, left: &JsonValue, right: &JsonValue) -> DiffResult {
+        let (left, right) = self.rules.apply(left, right);
+        self.inner.compute_diff(&left, &right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn rules_reconcile_numbers_within_either_tolerance() {
+        let policy: DiffPolicy =
+            toml::from_str("[keys.quote]\nignore = [\"/ts\"]\nfloat_tolerance = 0.001\nrelative_tolerance = 0.01\n")
+                .unwrap();
+        let rules = &policy.keys["quote"];
+        let (left, right) = rules.apply(
+            &json!({"price": 19.99, "volume": 1000, "count": 1, "ts": 1}),
+            &json!({"price": 19.990001, "volume": 1009, "count": 2, "ts": 2})
+        );
+        assert_eq!(left, json!({"price": 19.99, "volume": 1000, "count": 1}));
+        assert_eq!(right, json!({"price": 19.99, "volume": 1000, "count": 2}));
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    sync::{Arc, Mutex}
+};
+
+#[cfg(feature = "compression")]
+use flate2::{Compression, write::GzEncoder};
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::port::{SourceErrors, StateSource};
+
+/// Writes every received message as one NDJSON line, optionally gzip-compressed. Clones share the
+/// same file; call `finish` once the session ends so a gzip stream gets its trailer.
+#[derive(Clone)]
+pub struct Recorder {
+    path:   String,
+    writer: Arc<Mutex<Option<RecordWriter>>>
+}
+
+enum RecordWriter {
+    Plain(BufWriter<File>),
+    #[cfg(feature = "compression")]
+    Gzip(GzEncoder<BufWriter<File>>)
+}
+
+impl RecordWriter {
+    // Without the `compression` feature only the plain writer is left
+    #[cfg_attr(not(feature = "compression"), allow(clippy::infallible_destructuring_match))]
+    fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        let out: &mut dyn Write = match self {
+            RecordWriter::Plain(w) => w,
+            #[cfg(feature = "compression")]
+            RecordWriter::Gzip(w) => w
+        };
+        out.write_all(line)?;
+        out.write_all(b"\n")
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            RecordWriter::Plain(mut w) => w.flush(),
+            #[cfg(feature = "compression")]
+            RecordWriter::Gzip(w) => w.finish()?.flush()
+        }
+    }
+}
+
+impl Recorder {
+    /// Create the capture file; with `gzip`, ".gz" is appended to the path unless already there
+    /// (gzip needs the `compression` feature)
+    pub fn create(path: &str, gzip: bool) -> io::Result<Self> {
+        let path = if gzip && !path.ends_with(".gz") { format!("{path}.gz") } else { path.to_string() };
+        let writer = if gzip { Self::gzip(&path)? } else { RecordWriter::Plain(BufWriter::new(File::create(&path)?)) };
+        Ok(Self { path, writer: Arc::new(Mutex::new(Some(writer))) })
+    }
+
+    #[cfg(feature = "compression")]
+    fn gzip(path: &str) -> io::Result<RecordWriter> {
+        Ok(RecordWriter::Gzip(GzEncoder::new(BufWriter::new(File::create(path)?), Compression::default())))
+    }
+
+    #[cfg(not(feature = "compression"))]
+    fn gzip(_path: &str) -> io::Result<RecordWriter> {
+        Err(crate::domain::gzip_unsupported())
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Append one message; messages arriving after `finish` are dropped
+    pub fn record(&self, value: &Value) -> io::Result<()> {
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        match writer.as_mut() {
+            Some(writer) => writer.write_line(&serde_json::to_vec(value)?),
+            None => Ok(())
+        }
+    }
+
+    /// Flush buffered lines and finalize the gzip stream; later calls do nothing
+    pub fn finish(&self) -> io::Result<()> {
+        let writer = self.writer.lock().unwrap_or_else(|e| e.into_inner()).take();
+        writer.map_or(Ok(()), RecordWriter::finish)
+    }
+}
+
+/// Source decorator that tees every message of the inner source into a `Recorder`
+pub struct RecordingSource<S: StateSource> {
+    inner:    S,
+    recorder: Recorder
+}
+
+impl<S: StateSource> RecordingSource<S> {
+    pub fn new(inner: S, recorder: Recorder) -> Self {
+        Self { inner, recorder }
+    }
+}
+
+impl<S: StateSource> StateSource for RecordingSource<S> {
+    fn spawn(&self) -> mpsc::Receiver<Value> {
+        self.tee(self.inner.spawn())
+    }
+
+    fn spawn_with_errors(&self, errors: SourceErrors) -> mpsc::Receiver<Value> {
+        self.tee(self.inner.spawn_with_errors(errors))
+    }
+}
+
+impl<S: StateSource> RecordingSource<S> {
+    /// Forward everything from the inner source, recording each message on the way
+    fn tee(&self, mut inner_rx: mpsc::Receiver<Value>) -> mpsc::Receiver<Value> {
+        let (tx, rx) = mpsc::channel::<Value>(64);
+        let recorder = self.recorder.clone();
+
+        tokio::spawn(async move {
+            while let Some(value) = inner_rx.recv().await {
+                if let Err(err) = recorder.record(&value) {
+                    warn!("failed to record to {}: {err}", recorder.path());
+                }
+                if tx.send(value).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        rx
+    }
+}
@@ -1,11 +1,28 @@
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+#[cfg(feature = "compression")]
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use json_patch::PatchOperation;
+use serde_json::Value as JsonValue;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
-use std::io::Write;
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::sync::Arc;
 
-use crate::domain::State;
+use crate::domain::{
+    BufferedState, DiffResult, KeyComparison, MatchStrategy, MatchedSummary, PathRollup, RunMetadata, State,
+    TimelineAnchor, TrackerError, anchor_order, match_states, round_numbers, similarity,
+};
+use crate::adapter::JsonPatchDiffer;
+use crate::metric::{LatencyHistogram, LatencyStats, lag_millis};
+use crate::port::{Differ, Reporter};
 
-#[derive(Serialize, Clone)]
+/// Placeholder shown in place of payloads that were summarized by selective buffering
+const NOT_RETAINED: &str = "(matched — payload not retained)";
+
+#[derive(Serialize, Deserialize, Clone)]
 struct TimelineEvent {
     side: String,        // "left" or "right"
     key: String,
@@ -15,7 +32,34 @@ struct TimelineEvent {
     index: usize,
 }
 
+/// One cell of the matching heat-map: how often a left key was paired with a right key
+#[derive(Serialize)]
+struct HeatCell {
+    left: String,
+    right: String,
+    count: usize,
+    status: &'static str,
+}
+
+/// Payload changes of a matching-view row whose keys align but whose data differs
+#[derive(Serialize)]
+struct PairDiff {
+    index: usize,
+    changes: Vec<FieldChange>,
+}
+
+/// One changed field, with its value on each side it exists on
 #[derive(Serialize)]
+struct FieldChange {
+    op: &'static str,
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    old: Option<JsonValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new: Option<JsonValue>,
+}
+
+#[derive(Serialize, Deserialize)]
 struct ReportState {
     key: String,
     timestamp: String,
@@ -25,71 +69,210 @@ struct ReportState {
 pub struct HtmlReporter {
     session_id: String,
     started_at: DateTime<Utc>,
-    left_states: Vec<State>,
-    right_states: Vec<State>,
+    left_states: Vec<BufferedState>,
+    right_states: Vec<BufferedState>,
+    /// Keep only summaries for matched pairs, full data for mismatched/missing ones
+    selective: bool,
+    /// Paths changed across the round's comparisons
+    rollup: Option<PathRollup>,
+    /// How the timeline interleaves the two sides
+    anchor: TimelineAnchor,
+    /// How the session was run
+    metadata: Option<RunMetadata>,
+    /// Diffs aligned pairs for the matching view
+    differ: Arc<dyn Differ>,
+    /// Differs used instead of `differ` for pairs aligned on specific keys
+    key_differs: HashMap<String, Arc<dyn Differ>>,
+    /// Round payload numbers to this many significant digits in the report
+    precision: Option<u32>,
+    /// Which alignment keys count as the same (normalization, numeric tolerance)
+    keys: KeyComparison,
+    /// How pairs are formed for the similarity scores
+    strategy: MatchStrategy,
 }
 
 impl HtmlReporter {
-    pub fn new() -> Self {
+    pub fn new(differ: Arc<dyn Differ>) -> Self {
         Self {
             session_id: uuid::Uuid::new_v4().to_string(),
             started_at: Utc::now(),
             left_states: Vec::new(),
             right_states: Vec::new(),
+            selective: false,
+            rollup: None,
+            anchor: TimelineAnchor::default(),
+            metadata: None,
+            differ,
+            key_differs: HashMap::new(),
+            precision: None,
+            keys: KeyComparison::default(),
+            strategy: MatchStrategy::default(),
         }
     }
 
-    pub fn add_left(&mut self, state: State) {
-        self.left_states.push(state);
+    /// Pair states for the similarity scores the way the tracker pairs a round
+    pub fn with_match_strategy(mut self, strategy: MatchStrategy) -> Self {
+        self.strategy = strategy;
+        self
     }
 
-    pub fn add_right(&mut self, state: State) {
-        self.right_states.push(state);
+    /// Judge pairs matched the way the tracker aligned them, e.g. ignoring case; reported keys
+    /// stay as received
+    pub fn with_key_comparison(mut self, keys: KeyComparison) -> Self {
+        self.keys = keys;
+        self
     }
 
-    pub fn generate(&self, output_path: &str) -> std::io::Result<()> {
-        let mut file = File::create(output_path)?;
-        
-        let html = self.build_html();
-        file.write_all(html.as_bytes())?;
-        
-        Ok(())
+    /// Show payload numbers rounded to `digits` significant digits (pairs are still diffed on the
+    /// exact values)
+    pub fn with_display_precision(mut self, digits: u32) -> Self {
+        self.precision = Some(digits);
+        self
     }
 
-    fn build_timeline_events(&self) -> Vec<TimelineEvent> {
-        let mut events = Vec::new();
-
-        for (i, state) in self.left_states.iter().enumerate() {
-            events.push(TimelineEvent {
-                side: "left".to_string(),
-                key: state.alignment_key.clone().unwrap_or_else(|| "<no-key>".to_string()),
-                timestamp: state.timestamp.format("%H:%M:%S%.3f").to_string(),
-                timestamp_ms: state.timestamp.timestamp_millis(),
-                data: serde_json::to_string_pretty(&state.data).unwrap_or_default(),
-                index: i,
-            });
+    /// A payload as the report shows it
+    fn shown<'a>(&self, data: &'a JsonValue) -> Cow<'a, JsonValue> {
+        match self.precision {
+            Some(digits) => Cow::Owned(round_numbers(data, digits)),
+            None => Cow::Borrowed(data),
         }
+    }
 
-        for (i, state) in self.right_states.iter().enumerate() {
-            events.push(TimelineEvent {
-                side: "right".to_string(),
-                key: state.alignment_key.clone().unwrap_or_else(|| "<no-key>".to_string()),
-                timestamp: state.timestamp.format("%H:%M:%S%.3f").to_string(),
-                timestamp_ms: state.timestamp.timestamp_millis(),
-                data: serde_json::to_string_pretty(&state.data).unwrap_or_default(),
-                index: i,
-            });
+    /// Diff pairs aligned on these keys with their own differs (e.g., per-key policy rules)
+    pub fn with_key_differs(mut self, differs: HashMap<String, Arc<dyn Differ>>) -> Self {
+        self.key_differs = differs;
+        self
+    }
+
+    /// Order the timeline by timestamp or by one side's arrival order (for skewed clocks)
+    pub fn with_timeline_anchor(mut self, anchor: TimelineAnchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    pub fn with_selective_buffering(mut self, enabled: bool) -> Self {
+        self.selective = enabled;
+        self
+    }
+
+    /// Downgrade the most recently completed pair to summaries if keys and payloads match
+    fn compact_latest_pair(&mut self) {
+        if !self.selective {
+            return;
+        }
+
+        let paired = self.left_states.len().min(self.right_states.len());
+        if paired == 0 {
+            return;
+        }
+
+        let i = paired - 1;
+        let summaries = match (&self.left_states[i], &self.right_states[i]) {
+            (BufferedState::Full(left), BufferedState::Full(right))
+                if left.alignment_key.is_some()
+                    && self.keys.same(left.alignment_key.as_deref(), right.alignment_key.as_deref())
+                    && left.data == right.data =>
+            {
+                Some((MatchedSummary::from(left), MatchedSummary::from(right)))
+            }
+            _ => None,
+        };
+
+        if let Some((left, right)) = summaries {
+            self.left_states[i] = BufferedState::Summary(left);
+            self.right_states[i] = BufferedState::Summary(right);
         }
+    }
+
+    fn build_timeline_events(&self) -> Vec<TimelineEvent> {
+        let left = self.side_events("left", &self.left_states);
+        let right = self.side_events("right", &self.right_states);
+
+        let (anchor, other) = match self.anchor {
+            TimelineAnchor::Time => {
+                let mut events: Vec<TimelineEvent> = left.into_iter().chain(right).collect();
+                events.sort_by_key(|e| e.timestamp_ms);
+                return events;
+            }
+            TimelineAnchor::Left => (left, right),
+            TimelineAnchor::Right => (right, left),
+        };
+
+        let anchor_keys: Vec<&str> = anchor.iter().map(|e| e.key.as_str()).collect();
+        let other_keys: Vec<&str> = other.iter().map(|e| e.key.as_str()).collect();
+        let order = anchor_order(&anchor_keys, &other_keys);
+
+        let mut anchor: Vec<Option<TimelineEvent>> = anchor.into_iter().map(Some).collect();
+        let mut other: Vec<Option<TimelineEvent>> = other.into_iter().map(Some).collect();
+        order
+            .into_iter()
+            .filter_map(|(is_anchor, i)| if is_anchor { anchor[i].take() } else { other[i].take() })
+            .collect()
+    }
+
+    fn side_events(&self, side: &str, states: &[BufferedState]) -> Vec<TimelineEvent> {
+        states
+            .iter()
+            .enumerate()
+            .map(|(i, state)| TimelineEvent {
+                side: side.to_string(),
+                key: state.alignment_key().unwrap_or("<no-key>").to_string(),
+                timestamp: state.timestamp().format("%H:%M:%S%.3f").to_string(),
+                timestamp_ms: state.timestamp().timestamp_millis(),
+                data: state
+                    .data()
+                    .map(|data| serde_json::to_string_pretty(&self.shown(data)).unwrap_or_default())
+                    .unwrap_or_else(|| NOT_RETAINED.to_string()),
+                index: i,
+            })
+            .collect()
+    }
+
+    /// Write the alignment timeline as CSV, one row per state in timeline order with the columns
+    /// `side,index,key,timestamp_ms,matched`; `matched` says whether the state's positional pair on
+    /// the other side has a matching key, as counted by the Matched stat. Gzip-compressed when the
+    /// path ends in ".gz".
+    pub fn generate_csv(&self, output_path: &str) -> std::io::Result<()> {
+        write_report(output_path, self.build_csv().as_bytes())
+    }
 
-        // Sort by timestamp
-        events.sort_by_key(|e| e.timestamp_ms);
-        events
+    fn build_csv(&self) -> String {
+        let left = Self::keys(&self.left_states);
+        let right = Self::keys(&self.right_states);
+        let key = |side: &str, index: usize| {
+            let keys = if side == "left" { &left } else { &right };
+            keys.get(index).copied().flatten()
+        };
+
+        let mut csv = String::from("side,index,key,timestamp_ms,matched\n");
+        for event in self.build_timeline_events() {
+            let own = key(&event.side, event.index);
+            let other = key(if event.side == "left" { "right" } else { "left" }, event.index);
+            let matched = own.is_some() && self.keys.same(own, other);
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{}",
+                event.side,
+                event.index,
+                csv_field(own.unwrap_or_default()),
+                event.timestamp_ms,
+                matched
+            );
+        }
+        csv
     }
 
     fn build_html(&self) -> String {
         let timeline_json = serde_json::to_string(&self.build_timeline_events()).unwrap_or_else(|_| "[]".to_string());
         let left_states_json = self.states_to_json(&self.left_states);
         let right_states_json = self.states_to_json(&self.right_states);
+        let heatmap_json = serde_json::to_string(&self.build_heatmap()).unwrap_or_else(|_| "[]".to_string());
+        let rollup_json = serde_json::to_string(&self.rollup).unwrap_or_else(|_| "null".to_string());
+        let run_json = serde_json::to_string(&self.metadata).unwrap_or_else(|_| "null".to_string());
+        let scores_json = serde_json::to_string(&self.pair_scores()).unwrap_or_else(|_| "[]".to_string());
+        let diffs_json = serde_json::to_string(&self.pair_diffs()).unwrap_or_else(|_| "[]".to_string());
+        let key_matches_json = serde_json::to_string(&self.key_matches()).unwrap_or_else(|_| "[]".to_string());
+        let key_comparison_json = serde_json::to_string(&[self.keys]).unwrap_or_else(|_| "[]".to_string());
 
         format!(r#"<!DOCTYPE html>
 <html lang="en">
@@ -168,6 +351,11 @@ impl HtmlReporter {
             margin-bottom: 0.5rem;
         }}
         
+        .stat-value.small {{
+            font-size: 1.5rem;
+            line-height: 4.5rem;
+        }}
+        
         .stat-label {{
             color: #6c757d;
             font-size: 1rem;
@@ -365,6 +553,28 @@ impl HtmlReporter {
             overflow: auto;
         }}
         
+        .event-diff {{
+            display: none;
+            margin-top: 1rem;
+            padding: 1rem;
+            background: white;
+            border-radius: 8px;
+            font-family: 'Monaco', 'Courier New', monospace;
+            font-size: 0.85rem;
+            line-height: 1.5;
+            word-break: break-all;
+        }}
+        
+        .event-card.expanded .event-diff {{
+            display: block;
+        }}
+        
+        .event-diff .diff-title {{
+            font-weight: 700;
+            color: #2d3748;
+            margin-bottom: 0.5rem;
+        }}
+        
         .expand-hint {{
             text-align: center;
             font-size: 0.8rem;
@@ -451,6 +661,129 @@ impl HtmlReporter {
             color: white;
         }}
         
+        .match-indicator.data-mismatch {{
+            background: linear-gradient(135deg, #6f42c1 0%, #e83e8c 100%);
+            color: white;
+        }}
+        
+        .match-diff {{
+            grid-column: 1 / -1;
+            background: #f8f9fa;
+            border-left: 4px solid #6f42c1;
+            border-radius: 8px;
+            padding: 1rem 1.5rem;
+            font-family: 'Monaco', 'Courier New', monospace;
+            font-size: 0.85rem;
+            line-height: 1.6;
+            word-break: break-all;
+        }}
+        
+        .diff-line.added {{
+            color: #28a745;
+        }}
+        
+        .diff-line.removed {{
+            color: #dc3545;
+        }}
+        
+        .diff-line.changed {{
+            color: #fd7e14;
+        }}
+        
+        /* Heat-map View */
+        .heatmap-wrapper {{
+            overflow-x: auto;
+            max-width: 1400px;
+            margin: 0 auto;
+        }}
+        
+        .heatmap {{
+            border-collapse: separate;
+            border-spacing: 4px;
+            margin: 0 auto;
+        }}
+        
+        .heatmap th {{
+            font-size: 0.85rem;
+            font-weight: 700;
+            color: #4a5568;
+            padding: 0.5rem;
+            white-space: nowrap;
+        }}
+        
+        .heatmap th.left-key {{
+            color: #667eea;
+            text-align: right;
+        }}
+        
+        .heatmap th.right-key {{
+            color: #f5576c;
+            writing-mode: vertical-rl;
+            transform: rotate(180deg);
+        }}
+        
+        .heat-cell {{
+            width: 56px;
+            height: 56px;
+            border-radius: 8px;
+            text-align: center;
+            font-weight: 700;
+            color: #2d3748;
+            background: #f8f9fa;
+        }}
+        
+        .rollup {{
+            border-collapse: collapse;
+            margin: 0 auto;
+            min-width: 480px;
+        }}
+        
+        .rollup th, .rollup td {{
+            padding: 0.5rem 1rem;
+            border-bottom: 1px solid #e2e8f0;
+            text-align: left;
+        }}
+        
+        .rollup th.sortable {{
+            cursor: pointer;
+            user-select: none;
+        }}
+        
+        .rollup td.path {{
+            font-family: 'Monaco', 'Courier New', monospace;
+            font-size: 0.85rem;
+        }}
+        
+        .run-info td:first-child {{
+            font-weight: 600;
+            color: #2d3748;
+            white-space: nowrap;
+        }}
+        
+        .run-info code {{
+            font-family: 'Monaco', 'Courier New', monospace;
+            font-size: 0.85rem;
+            word-break: break-all;
+        }}
+        
+        .heat-legend {{
+            display: flex;
+            justify-content: center;
+            gap: 1.5rem;
+            margin-bottom: 1.5rem;
+            color: #6c757d;
+            font-size: 0.9rem;
+        }}
+        
+        .heat-swatch {{
+            display: inline-block;
+            width: 14px;
+            height: 14px;
+            border-radius: 4px;
+            margin-right: 0.4rem;
+            vertical-align: middle;
+        }}
+        
         .footer {{
             text-align: center;
             padding: 2rem;
@@ -497,11 +830,23 @@ impl HtmlReporter {
                 <div class="stat-value">{mismatched}</div>
                 <div class="stat-label">Mismatched</div>
             </div>
+            <div class="stat-card">
+                <div class="stat-value small">{latency}</div>
+                <div class="stat-label">Right Lag p50 / p95 / p99</div>
+            </div>
+            <div class="stat-card">
+                <div class="stat-value small">{lag_avg_max}</div>
+                <div class="stat-label">Right Lag avg / max</div>
+            </div>
         </div>
         
         <div class="tabs">
             <button class="tab active" onclick="showTab('timeline')">📊 Timeline</button>
             <button class="tab" onclick="showTab('matching')">🔗 Matching View</button>
+            <button class="tab" onclick="showTab('heatmap')">🔥 Heat-map</button>
+            <button class="tab" onclick="showTab('paths')">🧩 Changed Paths</button>
+            <button class="tab" onclick="showTab('scores')">📐 Similarity</button>
+            <button class="tab" onclick="showTab('run')">⚙️ Run</button>
         </div>
         
         <div id="timeline-tab" class="tab-content active">
@@ -512,6 +857,27 @@ impl HtmlReporter {
             <div class="matching-grid" id="matching"></div>
         </div>
         
+        <div id="heatmap-tab" class="tab-content">
+            <div class="heat-legend">
+                <span><span class="heat-swatch" style="background: #28a745"></span>Match</span>
+                <span><span class="heat-swatch" style="background: #dc3545"></span>Mismatch</span>
+                <span><span class="heat-swatch" style="background: #ffc107"></span>Missing</span>
+            </div>
+            <div class="heatmap-wrapper" id="heatmap"></div>
+        </div>
+        
+        <div id="paths-tab" class="tab-content">
+            <div id="paths"></div>
+        </div>
+        
+        <div id="scores-tab" class="tab-content">
+            <div id="scores"></div>
+        </div>
+        
+        <div id="run-tab" class="tab-content">
+            <div id="run"></div>
+        </div>
+        
         <div class="footer">
             Generated by State Tracker • <a href="https://github.com/sagoez/tracker">GitHub</a>
         </div>
@@ -521,6 +887,13 @@ impl HtmlReporter {
         const timelineEvents = {timeline_json};
         const leftStates = {left_states_json};
         const rightStates = {right_states_json};
+        const heatCells = {heatmap_json};
+        const rollup = {rollup_json};
+        const run = {run_json};
+        const pairScores = {scores_json};
+        const pairDiffs = {diffs_json};
+        const keyMatches = {key_matches_json};
+        const keyComparison = {key_comparison_json};
         
         function showTab(tabName) {{
             document.querySelectorAll('.tab').forEach(tab => tab.classList.remove('active'));
@@ -545,7 +918,8 @@ impl HtmlReporter {
                     <div class="chrono-time">${{event.timestamp}}</div>
                 `;
                 
-                // Event card
+                // Event card; one half of a pair whose payloads differ also shows the field-level diff
+                const diff = pairDiffs.find(d => d.index === event.index);
                 const card = document.createElement('div');
                 card.className = `event-card ${{event.side}}`;
                 card.innerHTML = `
@@ -553,9 +927,11 @@ impl HtmlReporter {
                     <div>
                         <span class="event-badge">${{event.side.toUpperCase()}}</span>
                         <span class="event-badge">#${{event.index + 1}}</span>
+                        ${{diff ? `<span class="event-badge">≠ ${{diff.changes.length}} field(s)</span>` : ''}}
                     </div>
+                    ${{diff ? `<div class="event-diff"><div class="diff-title">left → right</div>${{diffLines(diff)}}</div>` : ''}}
                     <div class="event-data">${{escapeHtml(event.data)}}</div>
-                    <div class="expand-hint">Click to expand JSON</div>
+                    <div class="expand-hint">Click to expand ${{diff ? 'diff and JSON' : 'JSON'}}</div>
                 `;
                 
                 card.addEventListener('click', () => {{
@@ -607,9 +983,13 @@ impl HtmlReporter {
                 
                 // Indicator
                 const indicator = document.createElement('div');
-                const status = getStatus(left?.key, right?.key);
+                const diff = pairDiffs.find(d => d.index === i);
+                const status = getStatus(left?.key, right?.key, keyMatches[i], diff);
                 indicator.className = `match-indicator ${{status}}`;
-                indicator.textContent = status === 'match' ? '✓' : status === 'mismatch' ? '✗' : '⚠';
+                indicator.textContent = {{ match: '✓', 'data-mismatch': '≠', mismatch: '✗', missing: '⚠' }}[status];
+                if (diff) {{
+                    indicator.title = `keys match, ${{diff.changes.length}} field(s) differ`;
+                }}
                 
                 // Right card
                 const rightCard = document.createElement('div');
@@ -627,13 +1007,145 @@ impl HtmlReporter {
                 row.appendChild(leftCard);
                 row.appendChild(indicator);
                 row.appendChild(rightCard);
+                if (diff) {{
+                    row.appendChild(renderDiff(diff));
+                }}
                 matching.appendChild(row);
             }}
         }}
         
-        function getStatus(leftKey, rightKey) {{
+        // Changed fields of a pair whose keys align, spanning the whole row under it
+        function renderDiff(diff) {{
+            const panel = document.createElement('div');
+            panel.className = 'match-diff';
+            panel.innerHTML = diffLines(diff);
+            return panel;
+        }}
+        
+        // One colored line per added (+), removed (-) or changed (~) field
+        function diffLines(diff) {{
+            const value = v => escapeHtml(JSON.stringify(v));
+            return diff.changes.map(c => {{
+                const path = escapeHtml(c.path || '/');
+                if (c.op === 'added') return `<div class="diff-line added">+ ${{path}}: ${{value(c.new)}}</div>`;
+                if (c.op === 'removed') return `<div class="diff-line removed">- ${{path}}: ${{value(c.old)}}</div>`;
+                return `<div class="diff-line changed">~ ${{path}}: ${{value(c.old)}} → ${{value(c.new)}}</div>`;
+            }}).join('');
+        }}
+        
+        function renderHeatmap() {{
+            const container = document.getElementById('heatmap');
+            if (heatCells.length === 0) {{
+                container.innerHTML = '<p style="text-align: center; color: #6c757d;">No paired states</p>';
+                return;
+            }}
+            
+            const leftKeys = [...new Set(heatCells.map(c => c.left))];
+            const rightKeys = [...new Set(heatCells.map(c => c.right))];
+            const maxCount = Math.max(...heatCells.map(c => c.count));
+            const colors = {{ match: '40, 167, 69', mismatch: '220, 53, 69', missing: '255, 193, 7' }};
+            
+            const table = document.createElement('table');
+            table.className = 'heatmap';
+            
+            const header = document.createElement('tr');
+            header.innerHTML = '<th>LEFT ↓ / RIGHT →</th>' +
+                rightKeys.map(k => `<th class="right-key">${{escapeHtml(k)}}</th>`).join('');
+            table.appendChild(header);
+            
+            leftKeys.forEach(leftKey => {{
+                const row = document.createElement('tr');
+                row.innerHTML = `<th class="left-key">${{escapeHtml(leftKey)}}</th>`;
+                
+                rightKeys.forEach(rightKey => {{
+                    const cell = document.createElement('td');
+                    cell.className = 'heat-cell';
+                    const match = heatCells.find(c => c.left === leftKey && c.right === rightKey);
+                    if (match) {{
+                        const alpha = 0.25 + 0.75 * (match.count / maxCount);
+                        cell.style.background = `rgba(${{colors[match.status]}}, ${{alpha}})`;
+                        cell.textContent = match.count;
+                        cell.title = `${{leftKey}} → ${{rightKey}}: ${{match.count}} (${{match.status}})`;
+                    }}
+                    row.appendChild(cell);
+                }});
+                
+                table.appendChild(row);
+            }});
+            
+            container.appendChild(table);
+        }}
+        
+        function renderPaths() {{
+            const container = document.getElementById('paths');
+            const paths = rollup ? Object.entries(rollup.paths).sort((a, b) => b[1] - a[1] || a[0].localeCompare(b[0])) : [];
+            if (paths.length === 0) {{
+                container.innerHTML = '<p style="text-align: center; color: #6c757d;">No changed paths</p>';
+                return;
+            }}
+            
+            const table = document.createElement('table');
+            table.className = 'rollup';
+            table.innerHTML = `<tr><th>Path</th><th>Changed in</th></tr>` +
+                paths.map(([path, count]) =>
+                    `<tr><td class="path">${{escapeHtml(path)}}</td><td>${{count}} / ${{rollup.comparisons}} states</td></tr>`
+                ).join('');
+            container.appendChild(table);
+        }}
+        
+        // Most divergent pairs first; clicking a column header re-sorts by it
+        let scoreSort = {{ column: 'similarity', ascending: true }};
+        function renderScores() {{
+            const container = document.getElementById('scores');
+            if (pairScores.length === 0) {{
+                container.innerHTML = '<p style="text-align: center; color: #6c757d;">No aligned pairs</p>';
+                return;
+            }}
+            
+            const {{ column, ascending }} = scoreSort;
+            const sorted = [...pairScores].sort((a, b) => {{
+                const order = typeof a[column] === 'string' ? a[column].localeCompare(b[column]) : a[column] - b[column];
+                return ascending ? order : -order;
+            }});
+            const header = (name, label) =>
+                `<th class="sortable" onclick="sortScores('${{name}}')">${{label}}${{column === name ? (ascending ? ' ▲' : ' ▼') : ''}}</th>`;
+            container.innerHTML = `<table class="rollup"><tr>${{header('index', '#')}}${{header('key', 'Key')}}${{header('similarity', 'Similarity')}}</tr>` +
+                sorted.map(s =>
+                    `<tr><td>${{s.index + 1}}</td><td class="path">${{escapeHtml(s.key)}}</td><td>${{(s.similarity * 100).toFixed(1)}}%</td></tr>`
+                ).join('') + '</table>';
+        }}
+        
+        function sortScores(column) {{
+            scoreSort = {{ column, ascending: scoreSort.column === column ? !scoreSort.ascending : true }};
+            renderScores();
+        }}
+        
+        function renderRun() {{
+            const container = document.getElementById('run');
+            if (!run) {{
+                container.innerHTML = '<p style="text-align: center; color: #6c757d;">No run information recorded</p>';
+                return;
+            }}
+            
+            const rows = [
+                ['Version', run.version],
+                ['Config file', run.config || '—'],
+                ['Left source', run.left || '—'],
+                ['Right source', run.right || '—'],
+                ['Arguments', run.args.join(' ')],
+            ];
+            const table = document.createElement('table');
+            table.className = 'rollup run-info';
+            table.innerHTML = rows.map(([label, value]) =>
+                `<tr><td>${{label}}</td><td><code>${{escapeHtml(value)}}</code></td></tr>`
+            ).join('');
+            container.appendChild(table);
+        }}
+        
+        function getStatus(leftKey, rightKey, keysMatch, diff) {{
             if (!leftKey || !rightKey) return 'missing';
-            return leftKey === rightKey ? 'match' : 'mismatch';
+            if (!keysMatch) return 'mismatch';
+            return diff ? 'data-mismatch' : 'match';
         }}
         
         function escapeHtml(text) {{
@@ -644,6 +1156,10 @@ impl HtmlReporter {
         
         renderTimeline();
         renderMatching();
+        renderHeatmap();
+        renderPaths();
+        renderScores();
+        renderRun();
     </script>
 </body>
 </html>"#,
@@ -653,49 +1169,469 @@ impl HtmlReporter {
             right_count = self.right_states.len(),
             matched = self.count_matched(),
             mismatched = self.count_mismatched(),
+            latency = self
+                .latency()
+                .map(|l| format!("{:.0} / {:.0} / {:.0} ms", l.p50, l.p95, l.p99))
+                .unwrap_or_else(|| "—".to_string()),
+            // Signed, so a right side that runs ahead shows up as negative
+            lag_avg_max = self
+                .latency()
+                .map(|l| format!("{:+.0} / {:+.0} ms", l.avg, l.peak()))
+                .unwrap_or_else(|| "—".to_string()),
             timeline_json = timeline_json,
             left_states_json = left_states_json,
             right_states_json = right_states_json,
+            heatmap_json = heatmap_json,
+            rollup_json = rollup_json,
+            run_json = run_json,
+            scores_json = scores_json,
+            diffs_json = diffs_json,
+            key_matches_json = key_matches_json,
+            key_comparison_json = key_comparison_json,
         )
     }
 
-    fn states_to_json(&self, states: &[State]) -> String {
+    fn states_to_json(&self, states: &[BufferedState]) -> String {
         let report_states: Vec<ReportState> = states
             .iter()
             .map(|s| ReportState {
-                key: s.alignment_key.clone().unwrap_or_else(|| "<no-key>".to_string()),
-                timestamp: s.timestamp.format("%H:%M:%S%.3f").to_string(),
-                data: serde_json::to_string(&s.data).unwrap_or_default(),
+                key: s.alignment_key().unwrap_or("<no-key>").to_string(),
+                timestamp: s.timestamp().format("%H:%M:%S%.3f").to_string(),
+                data: s
+                    .data()
+                    .map(|data| serde_json::to_string(&self.shown(data)).unwrap_or_default())
+                    .unwrap_or_else(|| NOT_RETAINED.to_string()),
             })
             .collect();
 
         serde_json::to_string(&report_states).unwrap_or_else(|_| "[]".to_string())
     }
 
-    fn count_matched(&self) -> usize {
-        let max_len = self.left_states.len().min(self.right_states.len());
-        (0..max_len)
-            .filter(|&i| {
-                self.left_states[i].alignment_key == self.right_states[i].alignment_key
-                    && self.left_states[i].alignment_key.is_some()
+    /// Aggregate positional left/right key pairs into heat-map cells
+    fn build_heatmap(&self) -> Vec<HeatCell> {
+        let max_len = self.left_states.len().max(self.right_states.len());
+        let mut counts: BTreeMap<(String, String), usize> = BTreeMap::new();
+
+        for i in 0..max_len {
+            let left = self.left_states.get(i).and_then(|s| s.alignment_key());
+            let right = self.right_states.get(i).and_then(|s| s.alignment_key());
+            let pair = (left.unwrap_or("—").to_string(), right.unwrap_or("—").to_string());
+            *counts.entry(pair).or_default() += 1;
+        }
+
+        counts
+            .into_iter()
+            .map(|((left, right), count)| {
+                let status = if left == "—" || right == "—" {
+                    "missing"
+                } else if self.keys.matches(&left, &right) {
+                    "match"
+                } else {
+                    "mismatch"
+                };
+                HeatCell { left, right, count, status }
             })
-            .count()
+            .collect()
+    }
+
+    fn count_matched(&self) -> usize {
+        count_matched(&self.keys, &Self::keys(&self.left_states), &Self::keys(&self.right_states))
     }
 
     fn count_mismatched(&self) -> usize {
-        let max_len = self.left_states.len().min(self.right_states.len());
-        (0..max_len)
-            .filter(|&i| {
-                let left = &self.left_states[i].alignment_key;
-                let right = &self.right_states[i].alignment_key;
-                left.is_some() && right.is_some() && left != right
+        count_mismatched(&self.keys, &Self::keys(&self.left_states), &Self::keys(&self.right_states))
+    }
+
+    /// Whether each positional pair's keys match, for the matching view
+    fn key_matches(&self) -> Vec<bool> {
+        let (left, right) = (Self::keys(&self.left_states), Self::keys(&self.right_states));
+        left.iter().zip(&right).map(|(l, r)| l.is_some() && self.keys.same(*l, *r)).collect()
+    }
+
+    fn latency(&self) -> Option<LatencyStats> {
+        lag_stats(&self.keys, &Self::arrivals(&self.left_states), &Self::arrivals(&self.right_states))
+    }
+
+    fn arrivals(states: &[BufferedState]) -> Vec<(Option<&str>, DateTime<Utc>)> {
+        states.iter().map(|s| (s.alignment_key(), s.timestamp())).collect()
+    }
+
+    fn pair_scores(&self) -> Vec<PairScore> {
+        pair_scores(
+            self.strategy,
+            &self.keys,
+            &Self::payloads(&self.left_states),
+            &Self::payloads(&self.right_states),
+            |key, left, right| self.key_differs.get(key).unwrap_or(&self.differ).compute_diff(left, right),
+        )
+    }
+
+    /// Diff each positional pair whose keys align; pairs the differ counts as identical are left out
+    fn pair_diffs(&self) -> Vec<PairDiff> {
+        self.left_states
+            .iter()
+            .zip(&self.right_states)
+            .enumerate()
+            .filter_map(|(index, (left, right))| {
+                let key = left.alignment_key().filter(|key| self.keys.same(Some(key), right.alignment_key()))?;
+                let (left, right) = (left.data()?, right.data()?);
+                let differ = self.key_differs.get(key).unwrap_or(&self.differ);
+                let result = differ.compute_diff(left, right);
+                if result.identical {
+                    return None;
+                }
+                let mut changes: Vec<FieldChange> =
+                    result.patch.0.iter().filter_map(|op| field_change(op, left)).collect();
+                if let Some(digits) = self.precision {
+                    for change in &mut changes {
+                        change.old = change.old.as_ref().map(|old| round_numbers(old, digits));
+                        change.new = change.new.as_ref().map(|new| round_numbers(new, digits));
+                    }
+                }
+                Some(PairDiff { index, changes })
             })
-            .count()
+            .collect()
+    }
+
+    fn payloads(states: &[BufferedState]) -> Vec<(Option<&str>, Option<&JsonValue>)> {
+        states.iter().map(|s| (s.alignment_key(), s.data())).collect()
+    }
+
+    fn keys(states: &[BufferedState]) -> Vec<Option<&str>> {
+        states.iter().map(|s| s.alignment_key()).collect()
+    }
+
+    /// Check a previously generated report: the embedded JSON must parse and the stat cards must agree
+    /// with the matched/mismatched counts recomputed from the embedded states
+    pub fn validate(html: &str) -> Result<ReportStats, TrackerError> {
+        let timeline: Vec<TimelineEvent> = parse_embedded(html, "timelineEvents")?;
+        let left: Vec<ReportState> = parse_embedded(html, "leftStates")?;
+        let right: Vec<ReportState> = parse_embedded(html, "rightStates")?;
+        // Reports from before key comparison was embedded compared keys exactly
+        let keys = parse_embedded::<KeyComparison>(html, "keyComparison")
+            .ok()
+            .and_then(|keys| keys.into_iter().next())
+            .unwrap_or_default();
+
+        let key = |s: &ReportState| if s.key == "<no-key>" { None } else { Some(s.key.clone()) };
+        let left_keys: Vec<Option<String>> = left.iter().map(key).collect();
+        let right_keys: Vec<Option<String>> = right.iter().map(key).collect();
+        let left_keys: Vec<Option<&str>> = left_keys.iter().map(|k| k.as_deref()).collect();
+        let right_keys: Vec<Option<&str>> = right_keys.iter().map(|k| k.as_deref()).collect();
+
+        let recomputed = ReportStats {
+            left: left.len(),
+            right: right.len(),
+            matched: count_matched(&keys, &left_keys, &right_keys),
+            mismatched: count_mismatched(&keys, &left_keys, &right_keys),
+        };
+
+        let declared = ReportStats {
+            left: parse_stat(html, "Left States")?,
+            right: parse_stat(html, "Right States")?,
+            matched: parse_stat(html, "Matched")?,
+            mismatched: parse_stat(html, "Mismatched")?,
+        };
+
+        if timeline.len() != recomputed.left + recomputed.right {
+            return Err(TrackerError::InvalidReport(format!(
+                "timeline has {} events but the report holds {} left + {} right states",
+                timeline.len(),
+                recomputed.left,
+                recomputed.right
+            )));
+        }
+
+        if declared != recomputed {
+            return Err(TrackerError::InvalidReport(format!(
+                "stat cards {:?} don't match recomputed values {:?}",
+                declared, recomputed
+            )));
+        }
+
+        Ok(recomputed)
     }
 }
 
+impl Reporter for HtmlReporter {
+    fn add_left(&mut self, state: State) {
+        self.left_states.push(BufferedState::Full(state));
+        self.compact_latest_pair();
+    }
+
+    fn add_right(&mut self, state: State) {
+        self.right_states.push(BufferedState::Full(state));
+        self.compact_latest_pair();
+    }
+
+    fn set_rollup(&mut self, rollup: PathRollup) {
+        self.rollup = Some(rollup);
+    }
+
+    fn set_metadata(&mut self, metadata: RunMetadata) {
+        self.metadata = Some(metadata);
+    }
+
+    fn generate(&self, output_path: &str) -> std::io::Result<()> {
+        let html = self.build_html();
+        write_report(output_path, html.as_bytes())
+    }
+}
+
+/// Writes an `HtmlReporter`'s alignment timeline as CSV (see `HtmlReporter::generate_csv`)
+/// instead of the HTML page
+pub struct CsvReporter(HtmlReporter);
+
+impl CsvReporter {
+    pub fn new(reporter: HtmlReporter) -> Self {
+        Self(reporter)
+    }
+}
+
+impl Reporter for CsvReporter {
+    fn add_left(&mut self, state: State) {
+        self.0.add_left(state);
+    }
+
+    fn add_right(&mut self, state: State) {
+        self.0.add_right(state);
+    }
+
+    fn generate(&self, output_path: &str) -> std::io::Result<()> {
+        self.0.generate_csv(output_path)
+    }
+}
+
+/// Quote a CSV field when it holds a comma, quote or line break, doubling inner quotes
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// Split a trailing ".gz" off a report path: `("report.html", true)` for "report.html.gz"
+pub fn split_gz(path: &str) -> (&str, bool) {
+    match path.strip_suffix(".gz") {
+        Some(base) => (base, true),
+        None => (path, false),
+    }
+}
+
+/// Write a report file, gzip-compressed when the path ends in ".gz" (which needs the `compression`
+/// feature)
+pub(crate) fn write_report(path: &str, contents: &[u8]) -> std::io::Result<()> {
+    if split_gz(path).1 {
+        return write_gzipped(path, contents);
+    }
+    File::create(path)?.write_all(contents)
+}
+
+#[cfg(feature = "compression")]
+fn write_gzipped(path: &str, contents: &[u8]) -> std::io::Result<()> {
+    let mut encoder = GzEncoder::new(File::create(path)?, Compression::best());
+    encoder.write_all(contents)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+#[cfg(not(feature = "compression"))]
+fn write_gzipped(_path: &str, _contents: &[u8]) -> std::io::Result<()> {
+    Err(crate::domain::gzip_unsupported())
+}
+
+/// Read a report file, transparently decompressing ".gz" reports
+pub fn read_report(path: &str) -> std::io::Result<String> {
+    let mut contents = String::new();
+    if split_gz(path).1 {
+        read_gzipped(path, &mut contents)?;
+    } else {
+        File::open(path)?.read_to_string(&mut contents)?;
+    }
+    Ok(contents)
+}
+
+#[cfg(feature = "compression")]
+fn read_gzipped(path: &str, contents: &mut String) -> std::io::Result<()> {
+    GzDecoder::new(File::open(path)?).read_to_string(contents)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "compression"))]
+fn read_gzipped(_path: &str, _contents: &mut String) -> std::io::Result<()> {
+    Err(crate::domain::gzip_unsupported())
+}
+
+/// Counts shown in a report's stat cards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ReportStats {
+    pub left: usize,
+    pub right: usize,
+    pub matched: usize,
+    pub mismatched: usize,
+}
+
+/// Similarity of one pair of states that aligned on key
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct PairScore {
+    /// Position of the left state in the round
+    pub index: usize,
+    pub key: String,
+    /// 1.0 for identical payloads down to 0.0 (see `similarity`)
+    pub similarity: f64,
+}
+
+/// Score the pairs `strategy` forms, as the tracker pairs a round, diffing each with `diff` (the
+/// differ for the pair's key) so ignores, tolerances and array keys apply; a missing payload
+/// (summarized by selective buffering) only ever belongs to an identical pair
+pub(crate) fn pair_scores(
+    strategy: MatchStrategy,
+    keys: &KeyComparison,
+    left: &[(Option<&str>, Option<&JsonValue>)],
+    right: &[(Option<&str>, Option<&JsonValue>)],
+    diff: impl Fn(&str, &JsonValue, &JsonValue) -> DiffResult,
+) -> Vec<PairScore> {
+    let outline = |side: &[(Option<&str>, Option<&JsonValue>)]| -> Vec<State> {
+        side.iter().map(|(key, _)| State::new(JsonValue::Null, key.map(str::to_string))).collect()
+    };
+    let compare = |i: usize, j: usize| match (left[i].1, right[j].1) {
+        (Some(l), Some(r)) => Some((diff(left[i].0.unwrap_or_default(), l, r), l, r)),
+        _ => None,
+    };
+    let cost = |i, j| compare(i, j).map_or(0, |(result, ..)| result.ops_count);
+
+    match_states(strategy, *keys, &outline(left), &outline(right), cost)
+        .into_iter()
+        .filter_map(|pair| {
+            let (i, j) = (pair.left?, pair.right?);
+            let key = left[i].0.filter(|key| keys.same(Some(key), right[j].0))?;
+            let similarity = compare(i, j).map_or(1.0, |(result, l, r)| similarity(&result, l, r));
+            Some(PairScore { index: i, key: key.to_string(), similarity })
+        })
+        .collect()
+}
+
+pub(crate) fn count_matched(keys: &KeyComparison, left: &[Option<&str>], right: &[Option<&str>]) -> usize {
+    left.iter().zip(right).filter(|(l, r)| l.is_some() && keys.same(**l, **r)).count()
+}
+
+pub(crate) fn count_mismatched(keys: &KeyComparison, left: &[Option<&str>], right: &[Option<&str>]) -> usize {
+    left.iter().zip(right).filter(|(l, r)| l.is_some() && r.is_some() && !keys.same(**l, **r)).count()
+}
+
+/// Right-vs-left lag over positional pairs that matched on key
+pub(crate) fn lag_stats(
+    keys: &KeyComparison,
+    left: &[(Option<&str>, DateTime<Utc>)],
+    right: &[(Option<&str>, DateTime<Utc>)],
+) -> Option<LatencyStats> {
+    let mut histogram = LatencyHistogram::new();
+    for ((left_key, left_at), (right_key, right_at)) in left.iter().zip(right) {
+        if left_key.is_some() && keys.same(*left_key, *right_key) {
+            histogram.record(lag_millis(*left_at, *right_at));
+        }
+    }
+    histogram.stats()
+}
+
+/// A patch operation as a field change, with the replaced or removed value looked up in `left`
+fn field_change(op: &PatchOperation, left: &JsonValue) -> Option<FieldChange> {
+    let path = op.path().to_string();
+    let old = || left.pointer(&path).cloned();
+    let (op, old, new) = match op {
+        PatchOperation::Add(add) => ("added", None, Some(add.value.clone())),
+        PatchOperation::Remove(_) => ("removed", old(), None),
+        PatchOperation::Replace(replace) => ("changed", old(), Some(replace.value.clone())),
+        _ => return None,
+    };
+    Some(FieldChange { op, path, old, new })
+}
+
+/// Extract and parse a `const <name> = [...];` array embedded in the report script
+fn parse_embedded<T: DeserializeOwned>(html: &str, name: &str) -> Result<Vec<T>, TrackerError> {
+    let marker = format!("const {} = ", name);
+    let start = html
+        .find(&marker)
+        .map(|i| i + marker.len())
+        .ok_or_else(|| TrackerError::InvalidReport(format!("missing embedded `{}`", name)))?;
+    let end = html[start..]
+        .find(";\n")
+        .map(|i| start + i)
+        .ok_or_else(|| TrackerError::InvalidReport(format!("unterminated embedded `{}`", name)))?;
+
+    serde_json::from_str(&html[start..end])
+        .map_err(|e| TrackerError::InvalidReport(format!("embedded `{}` is not valid JSON: {}", name, e)))
+}
+
+/// Read the numeric value of the stat card with the given label
+fn parse_stat(html: &str, label: &str) -> Result<usize, TrackerError> {
+    let label_marker = format!("<div class=\"stat-label\">{}</div>", label);
+    let value_marker = "<div class=\"stat-value\">";
+    let missing = || TrackerError::InvalidReport(format!("missing or malformed `{}` stat", label));
+
+    let label_at = html.find(&label_marker).ok_or_else(missing)?;
+    let value_at = html[..label_at].rfind(value_marker).ok_or_else(missing)? + value_marker.len();
+    let value_end = html[value_at..].find("</div>").ok_or_else(missing)? + value_at;
+
+    html[value_at..value_end].trim().parse().map_err(|_| missing())
+}
+
 impl Default for HtmlReporter {
     fn default() -> Self {
-        Self::new()
+        Self::new(Arc::new(JsonPatchDiffer::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::domain::Normalize;
+
+    fn reporter(keys: KeyComparison) -> HtmlReporter {
+        let mut reporter = HtmlReporter::default().with_key_comparison(keys);
+        for (left, right) in [("GameCleared", "gamecleared "), ("Lobby", "Lobby"), ("Start", "End")] {
+            reporter.add_left(State::new(json!({"phase": left}), Some(left.to_string())));
+            reporter.add_right(State::new(json!({"phase": right}), Some(right.to_string())));
+        }
+        reporter
+    }
+
+    #[test]
+    fn normalized_keys_count_as_matched_but_are_reported_as_received() {
+        let normalize = Normalize { lowercase: true, trim: true };
+        let reporter = reporter(KeyComparison::default().with_normalization(normalize));
+
+        assert_eq!((reporter.count_matched(), reporter.count_mismatched()), (2, 1));
+        let csv = reporter.build_csv();
+        assert!(csv.contains("left,0,GameCleared,"));
+        assert!(csv.contains("right,0,gamecleared ,"));
+        assert_eq!(csv.lines().filter(|line| line.ends_with(",true")).count(), 4);
+
+        let stats = HtmlReporter::validate(&reporter.build_html()).unwrap();
+        assert_eq!((stats.matched, stats.mismatched), (2, 1));
+    }
+
+    #[test]
+    fn scores_pair_states_by_key_with_the_differ() {
+        let differ = JsonPatchDiffer::default().with_ignore_paths(vec!["/ts".to_string()]);
+        let mut reporter = HtmlReporter::new(Arc::new(differ));
+        reporter.add_left(State::new(json!({"hp": 10, "ts": 1}), Some("Attack".into())));
+        reporter.add_left(State::new(json!({"hp": 1, "mp": 1}), Some("Heal".into())));
+        reporter.add_right(State::new(json!({"hp": 2, "mp": 1}), Some("Heal".into())));
+        reporter.add_right(State::new(json!({"hp": 10, "ts": 2}), Some("Attack".into())));
+
+        let scores: Vec<(usize, String, f64)> =
+            reporter.pair_scores().into_iter().map(|score| (score.index, score.key, score.similarity)).collect();
+        assert_eq!(scores, vec![(0, "Attack".to_string(), 1.0), (1, "Heal".to_string(), 0.5)]);
+    }
+
+    #[test]
+    fn exact_keys_by_default() {
+        let reporter = reporter(KeyComparison::default());
+        assert_eq!((reporter.count_matched(), reporter.count_mismatched()), (1, 2));
+        assert_eq!(HtmlReporter::validate(&reporter.build_html()).unwrap().matched, 1);
     }
 }
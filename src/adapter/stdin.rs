@@ -0,0 +1,80 @@
+use std::{
+    io::{self, BufRead},
+    sync::atomic::{AtomicBool, Ordering},
+    thread
+};
+
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::{
+    domain::SourceError,
+    port::{SourceErrors, StateSource}
+};
+
+/// Whether a source has taken stdin already; it can only be read once per process
+static STDIN_TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// Reads newline-delimited JSON from standard input, one state per line, so another program's
+/// output can be piped in (e.g., `producer | pica diff - ws://...`). The stream closes at EOF.
+/// Only the first `StdinSource` spawned gets the input; later ones close right away.
+#[derive(Clone, Debug)]
+pub struct StdinSource {
+    pub name: String
+}
+
+impl StdinSource {
+    pub fn new<N: Into<String>>(name: N) -> Self {
+        Self { name: name.into() }
+    }
+}
+
+impl StateSource for StdinSource {
+    fn spawn(&self) -> mpsc::Receiver<Value> {
+        // Nobody listens for errors; they still go to the log
+        self.spawn_with_errors(mpsc::unbounded_channel().0)
+    }
+
+    fn spawn_with_errors(&self, errors: SourceErrors) -> mpsc::Receiver<Value> {
+        let (tx, rx) = mpsc::channel::<Value>(64);
+        let name = self.name.clone();
+        if STDIN_TAKEN.swap(true, Ordering::SeqCst) {
+            warn!("{name} can't read stdin: another source already does");
+            let _ = errors.send(SourceError::new(name, "stdin is already read by another source"));
+            return rx;
+        }
+
+        info!("{name} reading JSON lines from stdin");
+        // A plain thread rather than `spawn_blocking`, so a read waiting on an idle pipe doesn't keep
+        // the runtime from shutting down
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(err) => {
+                        warn!("{name} read error: {err}");
+                        let _ = errors.send(SourceError::new(name.as_str(), format!("read error: {err}")));
+                        return;
+                    }
+                };
+                let trimmed = line.trim();
+                if trimmed.is_empty() {
+                    continue;
+                }
+
+                match serde_json::from_str::<Value>(trimmed) {
+                    Ok(json) => {
+                        if tx.blocking_send(json).is_err() {
+                            return;
+                        }
+                    }
+                    Err(err) => warn!("{name} failed to parse line as JSON: {err}")
+                }
+            }
+            info!("{name} reached end of stdin");
+        });
+
+        rx
+    }
+}
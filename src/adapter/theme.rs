@@ -0,0 +1,59 @@
+use owo_colors::{AnsiColors, FgDynColorDisplay, OwoColorize};
+
+/// Colors used to tell the left and right side apart in terminal output
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub left:  AnsiColors,
+    pub right: AnsiColors
+}
+
+impl Theme {
+    pub fn new(left: AnsiColors, right: AnsiColors) -> Self {
+        Self { left, right }
+    }
+
+    /// Paint `text` in the left side's color
+    pub fn left<'a, T>(&self, text: &'a T) -> FgDynColorDisplay<'a, AnsiColors, T> {
+        text.color(self.left)
+    }
+
+    /// Paint `text` in the right side's color
+    pub fn right<'a, T>(&self, text: &'a T) -> FgDynColorDisplay<'a, AnsiColors, T> {
+        text.color(self.right)
+    }
+
+    /// Paint `text` in the color of the given side
+    pub fn side<'a, T>(&self, is_left: bool, text: &'a T) -> FgDynColorDisplay<'a, AnsiColors, T> {
+        if is_left { self.left(text) } else { self.right(text) }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::new(AnsiColors::Blue, AnsiColors::Magenta)
+    }
+}
+
+/// Parse a named terminal color ("yellow", "bright-cyan", ...)
+pub fn parse_color(name: &str) -> Result<AnsiColors, String> {
+    let color = match name.to_ascii_lowercase().replace('_', "-").as_str() {
+        "black" => AnsiColors::Black,
+        "red" => AnsiColors::Red,
+        "green" => AnsiColors::Green,
+        "yellow" => AnsiColors::Yellow,
+        "blue" => AnsiColors::Blue,
+        "magenta" => AnsiColors::Magenta,
+        "cyan" => AnsiColors::Cyan,
+        "white" => AnsiColors::White,
+        "bright-black" | "gray" | "grey" => AnsiColors::BrightBlack,
+        "bright-red" => AnsiColors::BrightRed,
+        "bright-green" => AnsiColors::BrightGreen,
+        "bright-yellow" => AnsiColors::BrightYellow,
+        "bright-blue" => AnsiColors::BrightBlue,
+        "bright-magenta" => AnsiColors::BrightMagenta,
+        "bright-cyan" => AnsiColors::BrightCyan,
+        "bright-white" => AnsiColors::BrightWhite,
+        other => return Err(format!("unknown color `{other}` (try red, yellow, cyan, bright-blue, ...)"))
+    };
+    Ok(color)
+}
@@ -1,26 +1,71 @@
+use anstream::{print, println};
 use owo_colors::OwoColorize;
 use std::collections::VecDeque;
 
+use super::Theme;
 use crate::domain::State;
 
+/// Rows of history kept per side by default
+pub const DEFAULT_VISUAL_HISTORY: usize = 15;
+/// Frame width used when none is set and the terminal's can't be detected
+pub const DEFAULT_VISUAL_WIDTH: usize = 100;
+/// Narrowest a timeline column gets, however small the frame
+const MIN_COLUMN: usize = 12;
+
+/// Width of the terminal on stdout, from the `COLUMNS` variable or the tty itself; `None` when
+/// stdout isn't a terminal
+pub fn terminal_width() -> Option<usize> {
+    if let Some(columns) = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return Some(columns);
+    }
+    tty_width()
+}
+
+#[cfg(unix)]
+fn tty_width() -> Option<usize> {
+    let mut size = libc::winsize { ws_row: 0, ws_col: 0, ws_xpixel: 0, ws_ypixel: 0 };
+    // SAFETY: TIOCGWINSZ only writes a `winsize` through the pointer, which outlives the call
+    let result = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+    (result == 0 && size.ws_col > 0).then_some(size.ws_col as usize)
+}
+
+#[cfg(not(unix))]
+fn tty_width() -> Option<usize> {
+    None
+}
+
 /// Visual timeline renderer for state tracking
 pub struct TimelineVisualizer {
     left_history:  VecDeque<String>,
     right_history: VecDeque<String>,
     max_history:   usize,
     width:         usize,
+    theme:         Theme,
 }
 
 impl TimelineVisualizer {
+    /// Keep `max_history` rows per side (at least one) and draw the frame `width` columns wide; the
+    /// key columns (and where long keys are cut) scale with the width
     pub fn new(max_history: usize, width: usize) -> Self {
         Self {
             left_history:  VecDeque::new(),
             right_history: VecDeque::new(),
-            max_history,
+            max_history:   max_history.max(1),
             width,
+            theme:         Theme::default(),
         }
     }
 
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Width of each side's column in the live timeline (40 at the default width)
+    fn column_width(&self) -> usize {
+        (self.width.saturating_sub(20) / 2).max(MIN_COLUMN)
+    }
+
     pub fn add_left(&mut self, key: &str) {
         self.left_history.push_back(key.to_string());
         if self.left_history.len() > self.max_history {
@@ -42,23 +87,47 @@ impl TimelineVisualizer {
         self.print_footer();
     }
 
+    /// Whether the row just completed by a state from this side pairs two different keys
+    pub fn latest_row_diverged(&self, is_left: bool) -> bool {
+        let (own, other) = if is_left {
+            (&self.left_history, &self.right_history)
+        } else {
+            (&self.right_history, &self.left_history)
+        };
+        let row = own.len().saturating_sub(1);
+        matches!((own.get(row), other.get(row)), (Some(a), Some(b)) if a != b)
+    }
+
+    /// Render the timeline held at a divergence, with a prompt to continue
+    pub fn render_paused(&self) {
+        self.clear_screen();
+        self.print_header();
+        self.print_timeline();
+        println!("\n{}", "─".repeat(self.width).dimmed());
+        println!(
+            "{}",
+            "⏸  PAUSED at the first divergence — press Enter to continue".bright_yellow().bold()
+        );
+    }
+
     pub fn render_round_comparison(&self, left_states: &[State], right_states: &[State]) {
         self.clear_screen();
         println!("\n{}", "═".repeat(self.width).bright_cyan());
         println!(
             "{}",
-            format!("🎯 ROUND COMPARISON").bright_yellow().bold()
+            "🎯 ROUND COMPARISON".bright_yellow().bold()
         );
         println!("{}\n", "═".repeat(self.width).bright_cyan());
 
         let max_len = left_states.len().max(right_states.len());
 
         // Header
+        let column = self.column_width() - 10;
         println!(
-            "{:^4} │ {:<30} │ {:<30} │ {}",
+            "{:^4} │ {:<column$} │ {:<column$} │ {}",
             "#".bright_white().bold(),
-            "LEFT".blue().bold(),
-            "RIGHT".magenta().bold(),
+            self.theme.left(&"LEFT").bold(),
+            self.theme.right(&"RIGHT").bold(),
             "STATUS".bright_white().bold()
         );
         println!("{}", "─".repeat(self.width).dimmed());
@@ -77,14 +146,14 @@ impl TimelineVisualizer {
             };
 
             let left_display = left_key
-                .map(|k| format!("{}", k.blue()))
+                .map(|k| format!("{}", self.theme.left(&k)))
                 .unwrap_or_else(|| "—".dimmed().to_string());
             let right_display = right_key
-                .map(|k| format!("{}", k.magenta()))
+                .map(|k| format!("{}", self.theme.right(&k)))
                 .unwrap_or_else(|| "—".dimmed().to_string());
 
             println!(
-                "{:>4} │ {:<30} │ {:<30} │ {}",
+                "{:>4} │ {:<column$} │ {:<column$} │ {}",
                 format!("{}", i + 1).bright_white(),
                 left_display,
                 right_display,
@@ -124,11 +193,12 @@ impl TimelineVisualizer {
         let max_len = left_len.max(right_len);
 
         // Column headers
+        let column = self.column_width();
         println!(
-            "{:^4} │ {:<40} │ {:<40}",
+            "{:^4} │ {:<column$} │ {:<column$}",
             "#".bright_white().bold(),
-            "LEFT STREAM".blue().bold(),
-            "RIGHT STREAM".magenta().bold()
+            self.theme.left(&"LEFT STREAM").bold(),
+            self.theme.right(&"RIGHT STREAM").bold()
         );
         println!("{}", "─".repeat(self.width).dimmed());
 
@@ -138,13 +208,13 @@ impl TimelineVisualizer {
                 .left_history
                 .get(i)
                 .map(|s| self.format_state_box(s, true))
-                .unwrap_or_else(|| " ".repeat(40));
+                .unwrap_or_else(|| " ".repeat(column));
 
             let right = self
                 .right_history
                 .get(i)
                 .map(|s| self.format_state_box(s, false))
-                .unwrap_or_else(|| " ".repeat(40));
+                .unwrap_or_else(|| " ".repeat(column));
 
             // Check if they're aligned
             let marker = if let (Some(l), Some(r)) = (self.left_history.get(i), self.right_history.get(i)) {
@@ -173,25 +243,23 @@ impl TimelineVisualizer {
                 println!(
                     "{} left={} ≠ right={}",
                     "⏳ WAITING:".yellow().bold(),
-                    l.blue().bold(),
-                    r.magenta().bold()
+                    self.theme.left(l).bold(),
+                    self.theme.right(r).bold()
                 );
             }
         }
     }
 
     fn format_state_box(&self, state: &str, is_left: bool) -> String {
-        let truncated = if state.len() > 35 {
-            format!("{}...", &state[..32])
+        let column = self.column_width();
+        let limit = column - 5;
+        let truncated = if state.chars().count() > limit {
+            format!("{}...", state.chars().take(limit - 3).collect::<String>())
         } else {
             state.to_string()
         };
 
-        if is_left {
-            format!("{:<40}", truncated.blue())
-        } else {
-            format!("{:<40}", truncated.magenta())
-        }
+        format!("{:<column$}", self.theme.side(is_left, &truncated))
     }
 
     fn print_footer(&self) {
@@ -208,3 +276,18 @@ impl TimelineVisualizer {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_at_least_one_row_per_side() {
+        let mut visualizer = TimelineVisualizer::new(0, 80);
+        visualizer.add_left("a");
+        visualizer.add_left("b");
+        visualizer.add_right("c");
+        assert_eq!(visualizer.left_history, ["b"]);
+        assert!(visualizer.latest_row_diverged(false));
+    }
+}
@@ -1,83 +1,551 @@
-use futures::StreamExt;
+use std::{
+    any::Any,
+    sync::{Arc, Mutex}
+};
+
+use futures::{SinkExt, StreamExt};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use rand::Rng;
 use serde_json::Value;
 use tokio::{
+    net::TcpStream,
     sync::mpsc,
-    time::{Duration, sleep}
+    time::{Duration, Instant, Interval, interval_at, sleep}
+};
+use tokio_tungstenite::{
+    MaybeTlsStream, WebSocketStream, connect_async,
+    tungstenite::{
+        self, Message,
+        client::IntoClientRequest,
+        handshake::client::Request,
+        http::{self, HeaderName, HeaderValue}
+    }
 };
-use tokio_tungstenite::{connect_async, tungstenite::Message};
-use tracing::{info, warn};
+use tracing::{debug, error, info, warn};
+
+#[cfg(feature = "compression")]
+use super::PayloadDecoder;
+use crate::{
+    domain::{SourceError, redact_url},
+    metric::Metrics,
+    port::{AlignmentKeyExtractor, JsonPathExtractor, SourceErrors, StateSource}
+};
+
+/// Everything but unreserved characters is escaped in query parameters
+const QUERY: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
 
-use crate::port::StateSource;
+type Connection = WebSocketStream<MaybeTlsStream<TcpStream>>;
 
 #[derive(Clone, Debug)]
 pub struct WebSocketSource {
-    pub name: String,
-    pub url:  String
+    pub name:       String,
+    pub url:        String,
+    /// Subscription/auth frames sent as text, in order, after every (re)connect
+    init_messages:  Vec<String>,
+    /// HTTP headers added to every handshake (e.g., "Authorization")
+    headers:        Vec<(String, String)>,
+    /// Dot-separated path of the message id used to resume after a reconnect
+    id_field:       Option<String>,
+    /// Id to resume from on the first connect
+    resume_from:    Option<String>,
+    /// Field of the resume message (or query parameter, without one) carrying the resume id
+    resume_key:     String,
+    /// JSON object sent after the init messages on a reconnect, with the resume id added
+    resume_message: Option<Value>,
+    /// Session counters to report reconnects to
+    metrics:        Option<Arc<Metrics>>,
+    /// Times a panicked source task is restarted before the source gives up
+    max_restarts:   usize,
+    /// Unwraps encoded envelopes before messages are forwarded
+    #[cfg(feature = "compression")]
+    decoder:        Option<PayloadDecoder>,
+    /// A connection that lasts this long (or delivers a message) resets the reconnect backoff
+    stable_after:   Duration,
+    /// When to give up reconnecting and how to space the attempts
+    reconnect:      ReconnectPolicy,
+    /// Send a ping at this interval so idle connections aren't dropped
+    keepalive:      Option<Duration>
+}
+
+/// Reconnect behavior of a WebSocket source: exponential backoff (1s, 2s, 4s, ... capped at 30s)
+/// between attempts, optionally jittered, and optionally a limit on consecutive failures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReconnectPolicy {
+    /// Consecutive failed connections after which the source closes; `None` retries forever
+    pub max_attempts: Option<usize>,
+    /// Spread each delay randomly over its upper half so many clients don't reconnect in lockstep
+    pub jitter:       bool
+}
+
+impl ReconnectPolicy {
+    pub fn new(max_attempts: Option<usize>, jitter: bool) -> Self {
+        Self { max_attempts, jitter }
+    }
+
+    /// Un-jittered delay after `failures` consecutive failures (1 for the first one)
+    pub fn backoff(failures: u32) -> Duration {
+        Duration::from_secs(1u64.checked_shl(failures.saturating_sub(1)).unwrap_or(u64::MAX).min(30))
+    }
+
+    /// Delay before the next attempt, jittered if enabled
+    pub fn delay(&self, failures: u32) -> Duration {
+        let delay = Self::backoff(failures);
+        if !self.jitter {
+            return delay;
+        }
+        let millis = delay.as_millis() as u64;
+        Duration::from_millis(rand::rng().random_range(millis / 2..=millis))
+    }
+
+    /// Whether `failures` consecutive failures use up the allowed attempts
+    pub fn exhausted(&self, failures: u32) -> bool {
+        self.max_attempts.is_some_and(|max| failures as usize >= max)
+    }
 }
 
 impl WebSocketSource {
     pub fn new<N: Into<String>, U: Into<String>>(name: N, url: U) -> Self {
-        Self { name: name.into(), url: url.into() }
+        Self {
+            name:                                    name.into(),
+            url:                                     url.into(),
+            init_messages:                           Vec::new(),
+            headers:                                 Vec::new(),
+            id_field:                                None,
+            resume_from:                             None,
+            resume_key:                              "resume_from".to_string(),
+            resume_message:                          None,
+            metrics:                                 None,
+            max_restarts:                            3,
+            #[cfg(feature = "compression")]
+            decoder:                                 None,
+            stable_after:                            Duration::from_secs(10),
+            reconnect:                               ReconnectPolicy::default(),
+            keepalive:                               None
+        }
+    }
+
+    /// Send this message right after connecting (and after every reconnect)
+    pub fn with_init_message(mut self, message: Value) -> Self {
+        self.init_messages.push(message.to_string());
+        self
+    }
+
+    /// Send each of these as a text frame, in order, right after connecting and before reading
+    /// (again after every reconnect), e.g., an auth frame followed by subscriptions
+    pub fn with_init_messages(mut self, messages: Vec<String>) -> Self {
+        self.init_messages.extend(messages);
+        self
+    }
+
+    /// Send this HTTP header with the handshake of every (re)connect; repeating a name sends it
+    /// several times
+    pub fn with_header<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Give up after `policy.max_attempts` consecutive failed connections, closing the stream so
+    /// the tracker can finish, and optionally jitter the backoff between attempts
+    pub fn with_reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect = policy;
+        self
+    }
+
+    /// Ping the server every `interval` while connected, for feeds whose servers (or proxies) drop
+    /// connections that stay idle
+    pub fn with_keepalive(mut self, interval: Duration) -> Self {
+        self.keepalive = Some(interval);
+        self
+    }
+
+    /// Reset the reconnect backoff only after a connection has stayed up this long (default 10s) or
+    /// delivered a message; a server that accepts and immediately drops connections keeps
+    /// escalating the backoff instead of being hammered
+    pub fn with_stability_threshold(mut self, threshold: Duration) -> Self {
+        self.stable_after = threshold;
+        self
+    }
+
+    /// Decode an encoded payload field (e.g., base64+gzip JSON) and forward the inner JSON instead
+    /// of the envelope; undecodable messages are skipped with a warning
+    #[cfg(feature = "compression")]
+    pub fn with_payload_decoder(mut self, decoder: PayloadDecoder) -> Self {
+        self.decoder = Some(decoder);
+        self
+    }
+
+    /// Track the last-seen id at this path (e.g., "id" or "meta.seq") so reconnects resume from it
+    pub fn with_id_field<F: Into<String>>(mut self, path: F) -> Self {
+        self.id_field = Some(path.into());
+        self
+    }
+
+    /// Resume from this id on the first connect
+    pub fn with_resume_from<I: Into<String>>(mut self, id: I) -> Self {
+        self.resume_from = Some(id.into());
+        self
+    }
+
+    /// Count reconnects in these session metrics
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Restart the source task at most this many times after it panics (default 3)
+    pub fn with_max_restarts(mut self, max: usize) -> Self {
+        self.max_restarts = max;
+        self
+    }
+
+    /// Name of the resume-message field (or query parameter) that carries the resume id
+    pub fn with_resume_key<K: Into<String>>(mut self, key: K) -> Self {
+        self.resume_key = key.into();
+        self
+    }
+
+    /// Resume by sending this JSON object, with the resume id under the resume key, after the init
+    /// messages instead of adding the id to the URL's query
+    pub fn with_resume_message(mut self, message: Value) -> Self {
+        self.resume_message = Some(message);
+        self
+    }
+
+    /// Handshake request for `url` carrying the configured headers
+    fn handshake_request(&self, url: &str) -> Result<Request, tungstenite::Error> {
+        let mut request = url.into_client_request()?;
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(http::Error::from)?;
+            let value = HeaderValue::from_str(value).map_err(http::Error::from)?;
+            request.headers_mut().append(name, value);
+        }
+        Ok(request)
+    }
+
+    /// URL and init messages for a connection attempt, carrying `resume_id` if there is one: in
+    /// the resume message sent after the init messages, or percent-encoded in the URL's query
+    fn connect_request(&self, resume_id: Option<&str>) -> (String, Vec<String>) {
+        let mut messages = self.init_messages.clone();
+        let Some(id) = resume_id else {
+            return (self.url.clone(), messages);
+        };
+        if let Some(Value::Object(fields)) = &self.resume_message {
+            let mut fields = fields.clone();
+            fields.insert(self.resume_key.clone(), Value::String(id.to_string()));
+            messages.push(Value::Object(fields).to_string());
+            return (self.url.clone(), messages);
+        }
+
+        let separator = if self.url.contains('?') { '&' } else { '?' };
+        let (key, id) = (utf8_percent_encode(&self.resume_key, QUERY), utf8_percent_encode(id, QUERY));
+        (format!("{}{separator}{key}={id}", self.url), messages)
     }
 }
 
 impl StateSource for WebSocketSource {
     fn spawn(&self) -> mpsc::Receiver<Value> {
+        // Nobody listens for errors; they still go to the log
+        self.spawn_with_errors(mpsc::unbounded_channel().0)
+    }
+
+    fn spawn_with_errors(&self, errors: SourceErrors) -> mpsc::Receiver<Value> {
         let (tx, rx) = mpsc::channel::<Value>(64);
-        let name = self.name.clone();
-        let url = self.url.clone();
+        let source = self.clone();
         tokio::spawn(async move {
-            let mut backoff_secs: u64 = 1;
+            // Shared with restarted tasks so they resume where the failed one stopped
+            let last_id = Arc::new(Mutex::new(source.resume_from.clone()));
+            let mut restarts = 0;
             loop {
-                match connect_async(&url).await {
-                    Ok((ws_stream, _resp)) => {
-                        info!("{name} connected to {url}");
-                        backoff_secs = 1;
-                        let (_write, mut read) = ws_stream.split();
-                        while let Some(next) = read.next().await {
-                            match next {
-                                Ok(Message::Text(txt)) => match serde_json::from_str::<Value>(&txt) {
-                                    Ok(json) => {
-                                        let _ = tx.send(json).await;
-                                    }
-                                    Err(err) => warn!("{name} failed to parse text as JSON: {err}")
-                                },
-                                Ok(Message::Binary(bin)) => match String::from_utf8(bin.to_vec()) {
-                                    Ok(txt) => match serde_json::from_str::<Value>(&txt) {
-                                        Ok(json) => {
-                                            let _ = tx.send(json).await;
-                                        }
-                                        Err(err) => {
-                                            warn!("{name} failed to parse binary as JSON: {err}")
-                                        }
-                                    },
-                                    Err(err) => warn!("{name} received non-utf8 binary: {err}")
-                                },
-                                Ok(Message::Ping(_)) => {}
-                                Ok(Message::Pong(_)) => {}
-                                Ok(Message::Close(frame)) => {
-                                    warn!("{name} closed by peer: {:?}", frame);
+                let task = tokio::spawn(source.clone().run(tx.clone(), errors.clone(), Arc::clone(&last_id)));
+                let Err(err) = task.await else {
+                    break; // the receiver is gone
+                };
+                let reason = if err.is_panic() { panic_message(err.into_panic()) } else { err.to_string() };
+                let message = format!("source task died: {reason}");
+                error!("{} {message}", source.name);
+                let _ = errors.send(SourceError::new(&source.name, message));
+                if restarts >= source.max_restarts {
+                    error!("{} gave up after {restarts} restart(s)", source.name);
+                    break;
+                }
+                restarts += 1;
+                warn!("{} restarting source task ({restarts}/{})", source.name, source.max_restarts);
+            }
+        });
+        rx
+    }
+}
+
+impl WebSocketSource {
+    /// Connect to `url` and send the init messages; a connection that doesn't take them is a
+    /// failed attempt like one that can't be opened. The error is the line to report.
+    async fn connect(
+        &self,
+        url: &str,
+        init_messages: Vec<String>,
+        resume_id: Option<String>
+    ) -> Result<Connection, String> {
+        let connected = match self.handshake_request(url) {
+            Ok(request) => connect_async(request).await,
+            Err(err) => Err(err)
+        };
+        // The URL may carry credentials or tokens
+        let shown = redact_url(url);
+        let (mut ws_stream, _resp) = connected.map_err(|err| format!("connect error to {shown}: {err}"))?;
+        info!("{} connected to {shown}", self.name);
+        if !init_messages.is_empty()
+            && let Some(id) = resume_id
+        {
+            info!("{} resuming from {id}", self.name);
+        }
+        for init in init_messages {
+            ws_stream
+                .send(Message::Text(init.into()))
+                .await
+                .map_err(|err| format!("failed to send init message: {err}"))?;
+        }
+        Ok(ws_stream)
+    }
+
+    /// Connect, forward messages and reconnect with backoff until the receiver is dropped
+    async fn run(self, tx: mpsc::Sender<Value>, errors: SourceErrors, last_id: Arc<Mutex<Option<String>>>) {
+        let name = &self.name;
+        let report = |message: String| {
+            warn!("{name} {message}");
+            let _ = errors.send(SourceError::new(name, message));
+        };
+        let id_extractor = self.id_field.as_deref().map(JsonPathExtractor::new);
+        // Connections in a row that failed or dropped before becoming stable
+        let mut failures: u32 = 0;
+        loop {
+            let (url, init_messages) = self.connect_request(resume_id(&last_id).as_deref());
+            match self.connect(&url, init_messages, resume_id(&last_id)).await {
+                Ok(ws_stream) => {
+                    let connected_at = Instant::now();
+                    let mut delivered = false;
+                    let (mut write, mut read) = ws_stream.split();
+                    let mut keepalive = self
+                        .keepalive
+                        .filter(|period| !period.is_zero())
+                        .map(|period| interval_at(Instant::now() + period, period));
+                    loop {
+                        let next = tokio::select! {
+                            next = read.next() => next,
+                            _ = next_ping(&mut keepalive) => {
+                                debug!("{name} sending keepalive ping");
+                                if let Err(err) = write.send(Message::Ping(Vec::new().into())).await {
+                                    report(format!("failed to send keepalive ping: {err}"));
                                     break; // reconnect
                                 }
+                                continue;
+                            }
+                        };
+                        let Some(next) = next else {
+                            break; // reconnect
+                        };
+                        let json = match next {
+                            Ok(Message::Text(txt)) => match serde_json::from_str::<Value>(&txt) {
+                                Ok(json) => json,
                                 Err(err) => {
-                                    warn!("{name} read error: {err}");
+                                    warn!("{name} failed to parse text as JSON: {err}");
+                                    continue;
+                                }
+                            },
+                            Ok(Message::Binary(bin)) => match String::from_utf8(bin.to_vec()) {
+                                Ok(txt) => match serde_json::from_str::<Value>(&txt) {
+                                    Ok(json) => json,
+                                    Err(err) => {
+                                        warn!("{name} failed to parse binary as JSON: {err}");
+                                        continue;
+                                    }
+                                },
+                                Err(err) => {
+                                    warn!("{name} received non-utf8 binary: {err}");
+                                    continue;
+                                }
+                            },
+                            // Held apart from the read half, the write half doesn't answer pings
+                            // on its own
+                            Ok(Message::Ping(payload)) => {
+                                debug!("{name} answering ping");
+                                if let Err(err) = write.send(Message::Pong(payload)).await {
+                                    report(format!("failed to answer ping: {err}"));
                                     break; // reconnect
                                 }
-                                _ => {}
+                                continue;
                             }
+                            Ok(Message::Pong(_)) => {
+                                debug!("{name} received pong");
+                                continue;
+                            }
+                            Ok(Message::Close(frame)) => {
+                                report(format!("closed by peer: {frame:?}"));
+                                break; // reconnect
+                            }
+                            Err(err) => {
+                                report(format!("read error: {err}"));
+                                break; // reconnect
+                            }
+                            Ok(_) => continue
+                        };
+                        #[cfg(feature = "compression")]
+                        let json = match &self.decoder {
+                            Some(decoder) => match decoder.decode(json) {
+                                Ok(json) => json,
+                                Err(err) => {
+                                    warn!("{name} skipped message: {err}");
+                                    continue;
+                                }
+                            },
+                            None => json
+                        };
+
+                        if let Some(id) = id_extractor.as_ref().and_then(|e| e.extract_key(&json)) {
+                            *last_id.lock().unwrap_or_else(|e| e.into_inner()) = Some(id);
+                        }
+                        if tx.send(json).await.is_err() {
+                            return;
                         }
+                        delivered = true;
                     }
-                    Err(err) => {
-                        warn!("{name} connect error to {url}: {err}");
+
+                    if delivered || connected_at.elapsed() >= self.stable_after {
+                        failures = 0;
+                    } else {
+                        warn!("{name} connection dropped after {:?} without a message", connected_at.elapsed());
                     }
                 }
+                Err(err) => report(err)
+            }
 
-                let delay = Duration::from_secs(backoff_secs.min(30));
-                info!("{name} reconnecting in {:?}", delay);
-                sleep(delay).await;
-                backoff_secs = (backoff_secs * 2).max(2);
+            failures += 1;
+            if self.reconnect.exhausted(failures) {
+                error!("{name} giving up after {failures} failed connection attempt(s)");
+                return;
             }
-        });
-        rx
+            let delay = self.reconnect.delay(failures);
+            info!("{name} reconnecting in {:?}", delay);
+            if let Some(metrics) = &self.metrics {
+                metrics.record_reconnect(name);
+            }
+            sleep(delay).await;
+        }
+    }
+}
+
+/// Wait for the next keepalive tick; never completes without keepalive
+async fn next_ping(keepalive: &mut Option<Interval>) {
+    match keepalive {
+        Some(keepalive) => {
+            keepalive.tick().await;
+        }
+        None => std::future::pending().await
+    }
+}
+
+fn resume_id(last_id: &Mutex<Option<String>>) -> Option<String> {
+    last_id.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Text of a panic payload (`panic!` with a literal or a formatted message)
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => payload.downcast_ref::<&str>().map_or_else(|| "panicked".to_string(), |m| m.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn first_connects_leave_the_request_alone() {
+        let source = WebSocketSource::new("left", "ws://host/feed").with_init_message(json!({"op": "subscribe"}));
+        let (url, messages) = source.connect_request(None);
+        assert_eq!(url, "ws://host/feed");
+        assert_eq!(messages, vec![r#"{"op":"subscribe"}"#.to_string()]);
+    }
+
+    #[test]
+    fn resume_ids_are_percent_encoded_in_the_query() {
+        let source = WebSocketSource::new("left", "ws://host/feed?v=2")
+            .with_init_message(json!({"op": "subscribe"}))
+            .with_resume_key("after");
+        let (url, messages) = source.connect_request(Some("a b&c=d/é"));
+        assert_eq!(url, "ws://host/feed?v=2&after=a%20b%26c%3Dd%2F%C3%A9");
+        assert_eq!(messages, vec![r#"{"op":"subscribe"}"#.to_string()]);
+    }
+
+    #[test]
+    fn resume_messages_carry_the_id_after_the_init_messages() {
+        let source = WebSocketSource::new("left", "ws://host/feed")
+            .with_init_message(json!({"op": "subscribe"}))
+            .with_resume_message(json!({"op": "resume"}));
+        let (url, messages) = source.connect_request(Some("42"));
+        assert_eq!(url, "ws://host/feed");
+        assert_eq!(
+            messages,
+            vec![r#"{"op":"subscribe"}"#.to_string(), r#"{"op":"resume","resume_from":"42"}"#.to_string()]
+        );
+    }
+
+    #[test]
+    fn handshakes_carry_the_configured_headers() {
+        let source = WebSocketSource::new("left", "ws://host/feed")
+            .with_header("Authorization", "Bearer t0ken")
+            .with_header("X-Tenant", "a")
+            .with_header("X-Tenant", "b");
+        let request = source.handshake_request("ws://host/feed?after=1").unwrap();
+        assert_eq!(request.uri(), "ws://host/feed?after=1");
+        assert_eq!(request.headers()["authorization"], "Bearer t0ken");
+        let tenants: Vec<_> = request.headers().get_all("x-tenant").iter().collect();
+        assert_eq!(tenants, ["a", "b"]);
+        assert!(request.headers().contains_key("sec-websocket-key"));
+
+        assert!(
+            WebSocketSource::new("left", "ws://host")
+                .with_header("bad name", "v")
+                .handshake_request("ws://host")
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn connect_errors_name_the_redacted_url() {
+        let source = WebSocketSource::new("left", "ws://user:secret@127.0.0.1:1/feed?token=abc");
+        let err = source.connect(&source.url, Vec::new(), None).await.unwrap_err();
+        assert!(err.starts_with("connect error to ws://***@127.0.0.1:1/feed?***: "), "{err}");
+        assert!(!err.contains("secret") && !err.contains("abc"), "{err}");
+    }
+
+    #[test]
+    fn backoff_doubles_from_one_second_up_to_thirty() {
+        let delays: Vec<u64> = (1..=7).map(|failures| ReconnectPolicy::backoff(failures).as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 30, 30]);
+        assert_eq!(ReconnectPolicy::backoff(u32::MAX), Duration::from_secs(30));
+        assert_eq!(ReconnectPolicy::default().delay(3), Duration::from_secs(4));
+    }
+
+    #[test]
+    fn jitter_stays_within_the_upper_half_of_the_delay() {
+        let policy = ReconnectPolicy::new(None, true);
+        for failures in 1..=8 {
+            let backoff = ReconnectPolicy::backoff(failures);
+            for _ in 0..50 {
+                let delay = policy.delay(failures);
+                assert!(backoff / 2 <= delay && delay <= backoff, "{delay:?} outside {backoff:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn attempts_run_out_after_the_configured_number_of_failures() {
+        let policy = ReconnectPolicy::new(Some(3), false);
+        assert!(!policy.exhausted(2));
+        assert!(policy.exhausted(3));
+        assert!(!ReconnectPolicy::default().exhausted(u32::MAX));
     }
 }
@@ -0,0 +1,183 @@
+//! TOML config files for the CLI. Keys are long flag names with `_` for `-` (e.g.,
+//! `round_end = "GameCleared"`); the loader turns them into command-line arguments, so a config
+//! file accepts exactly what the flags accept and flags given on the command line win.
+
+use std::{ffi::OsString, fs};
+
+use clap::{Arg, ArgAction, Command, CommandFactory, ValueEnum};
+use toml::{Table, Value};
+use tracker::prelude::TrackerError;
+
+use crate::Cli;
+
+/// Subcommands whose flags can be set from a config file
+const CONFIGURABLE: [&str; 3] = ["diff", "track", "example"];
+
+const HEADER: &str = "\
+# pica config: pass with `--config <file>`. Keys are the long flag names with `_` for `-`;
+# flags on the command line override values here. Options that don't apply to the
+# subcommand being run are ignored.
+";
+
+/// Starting points for `gen-config`
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// Game server rounds: align by phase, one report per cleared game
+    Game,
+    /// Order pipelines: align by event type, lenient about string formatting of amounts
+    Ecommerce
+}
+
+impl Preset {
+    fn values(self) -> Vec<(&'static str, Value)> {
+        match self {
+            Preset::Game => vec![
+                ("align_by", "phase".into()),
+                ("round_end", "GameCleared".into()),
+                ("match_strategy", "sequential-by-key".into()),
+                ("report", Value::Array(vec!["report.html".into()])),
+                ("fail_on_type_change", true.into()),
+            ],
+            Preset::Ecommerce => vec![
+                ("align_by", "event_type".into()),
+                ("round_end", "order.completed".into()),
+                ("report", Value::Array(vec!["report.html".into()])),
+                ("coerce_numeric_strings", true.into()),
+                ("trim_strings", true.into()),
+                ("max_ops", 20.into()),
+            ]
+        }
+    }
+}
+
+/// Expand `--config <file>` into the flags it sets. Keys that don't apply to the chosen subcommand
+/// are skipped; keys no subcommand knows are an error.
+pub fn expand_args(args: Vec<OsString>) -> Result<Vec<OsString>, TrackerError> {
+    let Some(path) = config_path(&args) else {
+        return Ok(args);
+    };
+    let text = fs::read_to_string(&path).map_err(|e| TrackerError::Config(format!("{path}: {e}")))?;
+    let table: Table = toml::from_str(&text).map_err(|e| TrackerError::Config(format!("{path}: {e}")))?;
+
+    let cli = Cli::command();
+    let subcommand = args.iter().skip(1).find_map(|arg| cli.find_subcommand(arg));
+    let Some(subcommand) = subcommand.filter(|cmd| CONFIGURABLE.contains(&cmd.get_name())) else {
+        return Ok(args);
+    };
+
+    let mut expanded = args.clone();
+    for (key, value) in table {
+        let known = configurable_args(&cli).any(|arg| arg_key(arg) == key);
+        if !known {
+            return Err(TrackerError::Config(format!("{path}: unknown option `{key}`")));
+        }
+        let applies = cli.get_arguments().chain(subcommand.get_arguments()).any(|arg| arg_key(arg) == key);
+        let flag = format!("--{}", key.replace('_', "-"));
+        let given =
+            args.iter().any(|arg| arg.to_str().is_some_and(|a| a == flag || a.starts_with(&format!("{flag}="))));
+        if applies && !given {
+            push_flag(&mut expanded, &flag, &value)
+                .map_err(|e| TrackerError::Config(format!("{path}: `{key}` {e}")))?;
+        }
+    }
+
+    Ok(expanded)
+}
+
+/// Render a commented config listing every option, with the preset's values filled in
+pub fn render(preset: Option<Preset>) -> String {
+    let cli = Cli::command();
+    let preset = preset.map(Preset::values).unwrap_or_default();
+
+    let mut out = String::from(HEADER);
+    let mut seen = Vec::new();
+    for arg in configurable_args(&cli) {
+        let key = arg_key(arg);
+        if seen.contains(&key) {
+            continue;
+        }
+        seen.push(key.clone());
+
+        out.push('\n');
+        if let Some(help) = arg.get_help() {
+            for line in help.to_string().lines() {
+                out.push_str(&format!("# {line}\n"));
+            }
+        }
+        let choices: Vec<String> = arg.get_possible_values().iter().map(|v| v.get_name().to_owned()).collect();
+        if !choices.is_empty() && !is_flag(arg) {
+            out.push_str(&format!("# one of: {}\n", choices.join(", ")));
+        }
+
+        match preset.iter().find(|(name, _)| *name == key) {
+            Some((_, value)) => out.push_str(&format!("{key} = {value}\n")),
+            None => out.push_str(&format!("# {key} = {}\n", placeholder(arg)))
+        }
+    }
+    out
+}
+
+/// Flags that can appear in a config file: globals plus the options of every configurable
+/// subcommand
+fn configurable_args(cli: &Command) -> impl Iterator<Item = &Arg> {
+    let subcommands = cli.get_subcommands().filter(|cmd| CONFIGURABLE.contains(&cmd.get_name()));
+    cli.get_arguments()
+        .chain(subcommands.flat_map(|cmd| cmd.get_arguments()))
+        .filter(|arg| arg.get_long().is_some_and(|long| long != "config"))
+        .filter(|arg| !matches!(arg.get_action(), ArgAction::Help | ArgAction::Version))
+}
+
+fn arg_key(arg: &Arg) -> String {
+    arg.get_long().unwrap_or_default().replace('-', "_")
+}
+
+fn is_flag(arg: &Arg) -> bool {
+    matches!(arg.get_action(), ArgAction::SetTrue)
+}
+
+/// The value shown for an option the preset leaves unset: its default, or its value name
+fn placeholder(arg: &Arg) -> Value {
+    if is_flag(arg) {
+        return false.into();
+    }
+    if matches!(arg.get_action(), ArgAction::Append) {
+        return Value::Array(Vec::new());
+    }
+    match arg.get_default_values().first().and_then(|v| v.to_str()) {
+        Some(default) => default.parse::<i64>().map(Value::from).unwrap_or_else(|_| default.into()),
+        None => {
+            let name = arg.get_value_names().and_then(|names| names.first()).map(|n| n.to_string());
+            format!("<{}>", name.unwrap_or_else(|| arg_key(arg).to_uppercase())).into()
+        }
+    }
+}
+
+/// Locate the `--config` value in raw arguments
+fn config_path(args: &[OsString]) -> Option<String> {
+    let mut args = args.iter().filter_map(|arg| arg.to_str());
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            return args.next().map(str::to_owned);
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(path.to_owned());
+        }
+    }
+    None
+}
+
+fn push_flag(args: &mut Vec<OsString>, flag: &str, value: &Value) -> Result<(), String> {
+    match value {
+        Value::Boolean(true) => args.push(flag.into()),
+        Value::Boolean(false) => {}
+        Value::Array(items) => {
+            for item in items {
+                push_flag(args, flag, item)?;
+            }
+        }
+        Value::String(s) => args.extend([flag.into(), s.into()]),
+        Value::Integer(_) | Value::Float(_) => args.extend([flag.into(), value.to_string().into()]),
+        _ => return Err("must be a string, number, boolean or array".into())
+    }
+    Ok(())
+}
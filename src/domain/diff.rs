@@ -0,0 +1,172 @@
+use std::cmp::Reverse;
+
+use json_patch::{Patch, PatchOperation, jsonptr::PointerBuf};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use super::{canonicalize, navigate, positional_path, similarity};
+
+/// Structured outcome of comparing two states, independent of how it's printed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiffResult {
+    /// Whether the two sides count as equal (after any normalization the differ applied)
+    pub identical: bool,
+    /// Number of patch operations turning left into right
+    pub ops_count: usize,
+    /// JSON Patch (RFC 6902) from left to right, in key order
+    pub patch:     Patch
+}
+
+impl DiffResult {
+    /// Plain JSON Patch comparison of two values
+    pub fn between(left: &JsonValue, right: &JsonValue) -> Self {
+        Self::from_patch(json_patch::diff(&canonicalize(left), &canonicalize(right)))
+    }
+
+    /// The result of an already computed patch
+    pub fn from_patch(patch: Patch) -> Self {
+        Self { identical: patch.0.is_empty(), ops_count: patch.0.len(), patch }
+    }
+}
+
+/// One comparison as machine consumers see it (e.g. `--engine json` lines): which sides were
+/// compared, how alike they are and what changed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiffEvent {
+    pub left_label:  String,
+    pub right_label: String,
+    /// 1.0 for identical states down to 0.0 (see `similarity`)
+    pub similarity:  f64,
+    #[serde(flatten)]
+    pub result:      DiffResult
+}
+
+impl DiffEvent {
+    /// The event for `result`, the differ's diff of `left` and `right`
+    pub fn new(left_label: &str, right_label: &str, left: &JsonValue, right: &JsonValue, result: DiffResult) -> Self {
+        Self {
+            left_label: left_label.to_string(),
+            right_label: right_label.to_string(),
+            similarity: similarity(&result, left, right),
+            result
+        }
+    }
+}
+
+/// No changes at all
+impl Default for DiffResult {
+    fn default() -> Self {
+        Self::from_patch(Patch::default())
+    }
+}
+
+/// Move every path of `patch` under `prefix` (path segments), e.g. to place a patch between two
+/// array items at the item's position in the whole document
+pub fn nest_patch(mut patch: Patch, prefix: &[String]) -> Patch {
+    let prefix = PointerBuf::from_tokens(prefix);
+    let nest = |path: &mut PointerBuf| *path = prefix.concat(path);
+    for op in &mut patch.0 {
+        match op {
+            PatchOperation::Add(op) => nest(&mut op.path),
+            PatchOperation::Remove(op) => nest(&mut op.path),
+            PatchOperation::Replace(op) => nest(&mut op.path),
+            PatchOperation::Move(op) => {
+                nest(&mut op.from);
+                nest(&mut op.path);
+            }
+            PatchOperation::Copy(op) => {
+                nest(&mut op.from);
+                nest(&mut op.path);
+            }
+            PatchOperation::Test(op) => nest(&mut op.path)
+        }
+    }
+    patch
+}
+
+/// Rewrite a patch computed between documents with keyed arrays (see `key_array_items`) to plain
+/// JSON Pointers into the original documents: changed and removed values are located in `left`,
+/// added array items take their position in `right`, and values are taken from `right`. Other
+/// changes come first, then removed items from the back and added items from the front, so the
+/// patch applies to `left` as long as the items both sides keep are in the same order.
+pub fn positional_patch(patch: Patch, left: &JsonValue, right: &JsonValue) -> Patch {
+    let segments = |path: &PointerBuf| path.tokens().map(|token| token.decoded().into_owned()).collect::<Vec<_>>();
+    let value_at = |path: &[String], fallback: JsonValue| {
+        navigate(right, &positional_path(right, path).0).cloned().unwrap_or(fallback)
+    };
+
+    let (mut changes, mut removed, mut added) = (Vec::new(), Vec::new(), Vec::new());
+    for op in patch.0 {
+        match op {
+            PatchOperation::Add(mut op) => {
+                let path = segments(&op.path);
+                let Some((last, parent)) = path.split_last() else {
+                    changes.push(PatchOperation::Add(op));
+                    continue;
+                };
+                let (mut positional, _) = positional_path(left, parent);
+                let (in_right, item) = positional_path(right, &path);
+                positional.push(if item { in_right.last().cloned().unwrap_or_default() } else { last.clone() });
+                op.value = value_at(&path, op.value);
+                op.path = PointerBuf::from_tokens(&positional);
+                match positional.last().and_then(|index| index.parse::<usize>().ok()).filter(|_| item) {
+                    Some(index) => added.push((index, PatchOperation::Add(op))),
+                    None => changes.push(PatchOperation::Add(op))
+                }
+            }
+            PatchOperation::Remove(mut op) => {
+                let (positional, item) = positional_path(left, &segments(&op.path));
+                op.path = PointerBuf::from_tokens(&positional);
+                match positional.last().and_then(|index| index.parse::<usize>().ok()).filter(|_| item) {
+                    Some(index) => removed.push((index, PatchOperation::Remove(op))),
+                    None => changes.push(PatchOperation::Remove(op))
+                }
+            }
+            PatchOperation::Replace(mut op) => {
+                let path = segments(&op.path);
+                op.value = value_at(&path, op.value);
+                op.path = PointerBuf::from_tokens(&positional_path(left, &path).0);
+                changes.push(PatchOperation::Replace(op));
+            }
+            // `json_patch::diff` only emits the three above
+            other => changes.push(other)
+        }
+    }
+
+    removed.sort_by_key(|(index, _)| Reverse(*index));
+    added.sort_by_key(|(index, _)| *index);
+    changes.extend(removed.into_iter().chain(added).map(|(_, op)| op));
+    Patch(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn events_carry_labels_similarity_and_the_diff() {
+        let (left, right) = (json!({"a": 1, "b": 2, "c": 3, "d": 4}), json!({"a": 1, "b": 2, "c": 3, "d": 5}));
+        let event = DiffEvent::new("left", "right", &left, &right, DiffResult::between(&left, &right));
+
+        assert_eq!(event.similarity, 0.75);
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            json!({
+                "left_label": "left",
+                "right_label": "right",
+                "similarity": 0.75,
+                "identical": false,
+                "ops_count": 1,
+                "patch": [{"op": "replace", "path": "/d", "value": 5}]
+            })
+        );
+    }
+
+    #[test]
+    fn identical_results_are_fully_similar() {
+        let event = DiffEvent::new("left", "right", &json!([1]), &json!([2]), DiffResult::default());
+        assert_eq!(event.similarity, 1.0);
+    }
+}
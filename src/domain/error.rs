@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use super::{MissingKeys, SourceError, TrackerOutcome, TypeChange};
+
 #[derive(Debug, Error)]
 pub enum TrackerError {
     #[error("websocket error: {0}")]
@@ -7,5 +9,21 @@ pub enum TrackerError {
     #[error("json error: {0}")]
     Json(#[from] serde_json::Error),
     #[error("channel closed")]
-    ChannelClosed
+    ChannelClosed,
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid report: {0}")]
+    InvalidReport(String),
+    #[error("config error: {0}")]
+    Config(String),
+    #[error("type change: {0}")]
+    TypeChange(TypeChange),
+    #[error("source keeps failing: {0}")]
+    SourceFailed(SourceError),
+    #[error("required key(s) never seen: {0}")]
+    MissingKeys(MissingKeys),
+    #[error("alignment stalled: {0}")]
+    AlignmentStalled(String),
+    #[error("sides diverged: {0}")]
+    Diverged(TrackerOutcome)
 }
@@ -0,0 +1,82 @@
+use json_patch::PatchOperation;
+use serde_json::Value as JsonValue;
+
+use super::{json_type, navigate, parse_pointer};
+
+/// Longest value rendered inline in an explanation before it is cut short
+const MAX_VALUE_LEN: usize = 40;
+
+/// One plain-language sentence per change between `left` and `right`, e.g. "field `hp` decreased
+/// from 100 to 80" or "new field `buff` added with value true"
+pub fn explain_changes(left: &JsonValue, right: &JsonValue) -> Vec<String> {
+    json_patch::diff(left, right)
+        .0
+        .iter()
+        .map(|op| {
+            let path = parse_pointer(op.path().as_str());
+            let target = describe_path(&path);
+            match op {
+                PatchOperation::Add(add) => format!("new {target} added with value {}", short(&add.value)),
+                PatchOperation::Remove(_) => match navigate(left, &path) {
+                    Some(old) => format!("{target} removed (was {})", short(old)),
+                    None => format!("{target} removed")
+                },
+                PatchOperation::Replace(replace) => match navigate(left, &path) {
+                    Some(old) => explain_replace(&target, old, &replace.value),
+                    None => format!("{target} set to {}", short(&replace.value))
+                },
+                _ => format!("{target} changed")
+            }
+        })
+        .collect()
+}
+
+fn explain_replace(target: &str, old: &JsonValue, new: &JsonValue) -> String {
+    match (old, new) {
+        (JsonValue::Number(a), JsonValue::Number(b)) => match (a.as_f64(), b.as_f64()) {
+            (Some(x), Some(y)) if y > x => format!("{target} increased from {a} to {b}"),
+            (Some(x), Some(y)) if y < x => format!("{target} decreased from {a} to {b}"),
+            _ => format!("{target} changed from {a} to {b}")
+        },
+        (JsonValue::Bool(a), JsonValue::Bool(b)) => format!("{target} flipped from {a} to {b}"),
+        (JsonValue::String(_), JsonValue::String(_)) => {
+            format!("{target} changed from {} to {}", short(old), short(new))
+        }
+        _ if json_type(old) != json_type(new) => {
+            format!("{target} changed type from {} {} to {} {}", json_type(old), short(old), json_type(new), short(new))
+        }
+        _ => format!("{target} replaced: {} → {}", short(old), short(new))
+    }
+}
+
+/// "field `a.b`", "item `items[2]`" or "the whole document" for a parsed JSON Pointer
+fn describe_path(segments: &[String]) -> String {
+    if segments.is_empty() {
+        return "the whole document".to_string();
+    }
+
+    let mut path = String::new();
+    let mut is_index = false;
+    for segment in segments {
+        is_index = !segment.is_empty() && segment.chars().all(|c| c.is_ascii_digit());
+        if is_index {
+            path.push_str(&format!("[{segment}]"));
+        } else {
+            if !path.is_empty() {
+                path.push('.');
+            }
+            path.push_str(segment);
+        }
+    }
+
+    if is_index { format!("item `{path}`") } else { format!("field `{path}`") }
+}
+
+fn short(value: &JsonValue) -> String {
+    let text = value.to_string();
+    if text.chars().count() <= MAX_VALUE_LEN {
+        return text;
+    }
+    let cut: String = text.chars().take(MAX_VALUE_LEN - 1).collect();
+    format!("{cut}…")
+}
@@ -0,0 +1,131 @@
+use std::fmt;
+
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use serde_json_diff::{ArrayDifference, Difference, EntryDifference};
+
+/// What happened at a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeType {
+    /// Only the right side has it
+    Added,
+    /// Only the left side has it
+    Removed,
+    /// Both have it with different values of the same type
+    Changed,
+    /// Both have it with values of different types
+    TypeChanged
+}
+
+impl fmt::Display for ChangeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ChangeType::Added => "added",
+            ChangeType::Removed => "removed",
+            ChangeType::Changed => "changed",
+            ChangeType::TypeChanged => "type_changed"
+        })
+    }
+}
+
+/// One leaf of a `serde_json_diff` difference, addressed by JSON Pointer
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FlatChange {
+    pub path:        String,
+    pub change_type: ChangeType,
+    /// The left value (absent when added)
+    pub old:         Option<JsonValue>,
+    /// The right value (absent when removed)
+    pub new:         Option<JsonValue>
+}
+
+/// Every change between `left` and `right` as found by `serde_json_diff`, flattened into one entry
+/// per JSON Pointer path (e.g., "/players/0/hp") in the order the structured diff lists them
+pub fn flat_changes(left: &JsonValue, right: &JsonValue) -> Vec<FlatChange> {
+    let mut changes = Vec::new();
+    if let Some(diff) = serde_json_diff::values(left.clone(), right.clone()) {
+        flatten(&diff, String::new(), left, right, &mut changes);
+    }
+    changes
+}
+
+fn flatten(diff: &Difference, path: String, left: &JsonValue, right: &JsonValue, out: &mut Vec<FlatChange>) {
+    // Both values are read back from the documents; the missing side's lookup comes up empty
+    let change = |path: String, change_type| {
+        let (old, new) = (left.pointer(&path).cloned(), right.pointer(&path).cloned());
+        FlatChange { path, change_type, old, new }
+    };
+    match diff {
+        Difference::Scalar(_) => out.push(change(path, ChangeType::Changed)),
+        Difference::Type { .. } => out.push(change(path, ChangeType::TypeChanged)),
+        Difference::Object { different_entries } => {
+            for (key, entry) in &different_entries.0 {
+                let path = format!("{path}/{}", escape(key));
+                match entry {
+                    EntryDifference::Missing { .. } => out.push(change(path, ChangeType::Added)),
+                    EntryDifference::Extra => out.push(change(path, ChangeType::Removed)),
+                    EntryDifference::Value { value_diff } => flatten(value_diff, path, left, right, out)
+                }
+            }
+        }
+        Difference::Array(array) => {
+            let pairs = match array {
+                ArrayDifference::PairsOnly { different_pairs } => Some(different_pairs),
+                ArrayDifference::Shorter { different_pairs, .. } | ArrayDifference::Longer { different_pairs, .. } => {
+                    different_pairs.as_ref()
+                }
+            };
+            for (index, value_diff) in pairs.into_iter().flat_map(|pairs| &pairs.0) {
+                flatten(value_diff, format!("{path}/{index}"), left, right, out);
+            }
+
+            // Items past the end of the shorter array
+            let len = |value: &JsonValue| value.pointer(&path).and_then(JsonValue::as_array).map_or(0, Vec::len);
+            let (left_len, right_len) = (len(left), len(right));
+            for index in right_len..left_len {
+                out.push(change(format!("{path}/{index}"), ChangeType::Removed));
+            }
+            for index in left_len..right_len {
+                out.push(change(format!("{path}/{index}"), ChangeType::Added));
+            }
+        }
+    }
+}
+
+/// Escape an object key for use as a JSON Pointer segment
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn change(path: &str, change_type: ChangeType, old: Option<JsonValue>, new: Option<JsonValue>) -> FlatChange {
+        FlatChange { path: path.to_string(), change_type, old, new }
+    }
+
+    #[test]
+    fn one_change_per_pointer() {
+        let left = json!({"gone": 1, "hp": 10, "kind": 1, "a/b": [1, 2, 3], "nested": {"x": 1}});
+        let right = json!({"new": true, "hp": 7, "kind": "1", "a/b": [1, 5], "nested": {"x": 1}});
+        let mut changes = flat_changes(&left, &right);
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+
+        assert_eq!(
+            changes,
+            vec![
+                change("/a~1b/1", ChangeType::Changed, Some(json!(2)), Some(json!(5))),
+                change("/a~1b/2", ChangeType::Removed, Some(json!(3)), None),
+                change("/gone", ChangeType::Removed, Some(json!(1)), None),
+                change("/hp", ChangeType::Changed, Some(json!(10)), Some(json!(7))),
+                change("/kind", ChangeType::TypeChanged, Some(json!(1)), Some(json!("1"))),
+                change("/new", ChangeType::Added, None, Some(json!(true))),
+            ]
+        );
+        assert!(flat_changes(&left, &left).is_empty());
+    }
+}
@@ -0,0 +1,52 @@
+/// Whether `pattern` has glob wildcards: `*` (any run of characters, including none) or `?` (any
+/// single character)
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+/// Match `text` against a glob `pattern` in full (e.g., "round.*.completed" matches
+/// "round.12.completed"); every other character matches itself
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Where the last `*` was and the text position it's currently standing in for
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => (p, t) = (p + 1, t + 1),
+            _ => match star {
+                // Let the last `*` swallow one more character and retry
+                Some((star_p, star_t)) => {
+                    star = Some((star_p, star_t + 1));
+                    (p, t) = (star_p + 1, star_t + 1);
+                }
+                None => return false
+            }
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards_match_whole_keys() {
+        assert!(glob_match("round.*.completed", "round.12.completed"));
+        assert!(glob_match("round.*.completed", "round..completed"));
+        assert!(glob_match("*ed", "round.completed.completed"));
+        assert!(glob_match("phase-?", "phase-3"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("phase-?", "phase-10"));
+        assert!(!glob_match("round.*", "game.round.1"));
+        assert!(!glob_match("a*b", "a-b-c"));
+        assert!(is_glob("round.*") && is_glob("phase-?") && !is_glob("GameCleared"));
+    }
+}
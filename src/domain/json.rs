@@ -0,0 +1,69 @@
+use serde_json::Value as JsonValue;
+
+use super::DiffResult;
+
+/// Deep copy with object keys in sorted order. With serde_json's `preserve_order` feature (which
+/// any crate in the build can switch on) maps iterate in insertion order, so without this diffs and
+/// hashes would depend on the order each side happened to serialize its fields.
+pub fn canonicalize(value: &JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by_key(|(k, _)| *k);
+            JsonValue::Object(entries.into_iter().map(|(k, v)| (k.clone(), canonicalize(v))).collect())
+        }
+        JsonValue::Array(items) => JsonValue::Array(items.iter().map(canonicalize).collect()),
+        other => other.clone()
+    }
+}
+
+/// Number of leaf values in a document; empty objects and arrays count as one leaf
+pub fn leaf_count(value: &JsonValue) -> usize {
+    match value {
+        JsonValue::Object(map) if !map.is_empty() => map.values().map(leaf_count).sum(),
+        JsonValue::Array(items) if !items.is_empty() => items.iter().map(leaf_count).sum(),
+        _ => 1
+    }
+}
+
+/// How alike two documents are, from 1.0 (identical) down to 0.0: one minus the operations of
+/// `result` (the differ's diff of the two, so its ignores and tolerances apply), relative to the
+/// larger document's leaf count
+pub fn similarity(result: &DiffResult, left: &JsonValue, right: &JsonValue) -> f64 {
+    if result.identical {
+        return 1.0;
+    }
+    let size = leaf_count(left).max(leaf_count(right));
+    (1.0 - result.ops_count as f64 / size as f64).max(0.0)
+}
+
+/// Copy with every non-integer number rounded to `digits` significant digits, for display only
+/// (e.g., `0.30000000000000004` shows as `0.3` with 15 digits); integers are left as they are
+pub fn round_numbers(value: &JsonValue, digits: u32) -> JsonValue {
+    match value {
+        JsonValue::Number(n) if n.is_f64() => {
+            let Some(x) = n.as_f64() else {
+                return value.clone();
+            };
+            let rounded: f64 = format!("{:.*e}", digits.saturating_sub(1) as usize, x).parse().unwrap_or(x);
+            serde_json::Number::from_f64(rounded).map_or_else(|| value.clone(), JsonValue::Number)
+        }
+        JsonValue::Object(map) => {
+            JsonValue::Object(map.iter().map(|(k, v)| (k.clone(), round_numbers(v, digits))).collect())
+        }
+        JsonValue::Array(items) => JsonValue::Array(items.iter().map(|v| round_numbers(v, digits)).collect()),
+        other => other.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_documents_serialize_with_sorted_keys() {
+        let value: JsonValue = serde_json::from_str(r#"{"b": 1, "a": [{"d": 1, "c": 2}], "_": null}"#).unwrap();
+        assert_eq!(serde_json::to_string(&canonicalize(&value)).unwrap(), r#"{"_":null,"a":[{"c":2,"d":1}],"b":1}"#);
+        assert_eq!(canonicalize(&value), value);
+    }
+}
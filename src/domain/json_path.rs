@@ -0,0 +1,277 @@
+//! Path navigation shared by alignment keys, ignore/projection rules and entity lookup. A path is
+//! a list of segments; a segment names an object key, or an index when the value is an array, as
+//! in JSON Pointer. Removal also takes `*` as a wildcard segment matching every item or member.
+
+use serde_json::{Map, Value as JsonValue};
+
+/// Split a JSON Pointer into unescaped segments ("/a/b~1c" -> ["a", "b/c"], "" -> [])
+pub fn parse_pointer(pointer: &str) -> Vec<String> {
+    pointer.split('/').skip(1).map(unescape_segment).collect()
+}
+
+/// Split a dot-separated field path ("message.phase" -> ["message", "phase"]); bracketed indices
+/// are segments of their own, so "items[0].id" and "items.0.id" are the same path
+pub fn parse_dotted(path: &str) -> Vec<String> {
+    let mut segments = Vec::new();
+    for part in path.split('.') {
+        let Some((name, indices)) = part.split_once('[') else {
+            segments.push(part.to_string());
+            continue;
+        };
+        if !name.is_empty() {
+            segments.push(name.to_string());
+        }
+        segments.extend(indices.split(['[', ']']).filter(|index| !index.is_empty()).map(str::to_string));
+    }
+    segments
+}
+
+/// Decode one JSON Pointer segment (`~1` -> `/`, `~0` -> `~`)
+pub fn unescape_segment(segment: &str) -> String {
+    segment.replace("~1", "/").replace("~0", "~")
+}
+
+/// The value at `path`, if every segment resolves
+pub fn navigate<'a>(value: &'a JsonValue, path: &[String]) -> Option<&'a JsonValue> {
+    path.iter().try_fold(value, |current, segment| match current {
+        JsonValue::Object(map) => map.get(segment),
+        JsonValue::Array(items) => items.get(array_index(segment)?),
+        _ => None
+    })
+}
+
+/// Mutable variant of `navigate`
+pub fn navigate_mut<'a>(value: &'a mut JsonValue, path: &[String]) -> Option<&'a mut JsonValue> {
+    path.iter().try_fold(value, |current, segment| match current {
+        JsonValue::Object(map) => map.get_mut(segment),
+        JsonValue::Array(items) => items.get_mut(array_index(segment)?),
+        _ => None
+    })
+}
+
+/// Remove the value at `path`, if present; later array items shift down
+pub fn prune(value: &mut JsonValue, path: &[String]) {
+    let Some((last, parent)) = path.split_last() else { return };
+    match navigate_mut(value, parent) {
+        Some(JsonValue::Object(map)) => {
+            map.remove(last);
+        }
+        Some(JsonValue::Array(items)) => {
+            if let Some(index) = array_index(last)
+                && index < items.len()
+            {
+                items.remove(index);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Set the value at `path`, creating missing parent objects on the way. An empty path replaces
+/// the whole value; a parent that exists but isn't an object leaves `root` unchanged.
+pub fn insert(root: &mut JsonValue, path: &[String], value: JsonValue) {
+    let Some((last, parents)) = path.split_last() else {
+        *root = value;
+        return;
+    };
+
+    let mut current = root;
+    for segment in parents {
+        let JsonValue::Object(map) = current else { return };
+        current = map.entry(segment.clone()).or_insert_with(|| JsonValue::Object(Map::new()));
+    }
+
+    if let JsonValue::Object(map) = current {
+        map.insert(last.clone(), value);
+    }
+}
+
+/// Remove the values at `path`, where a `*` segment matches every array item or object member
+/// (e.g., ["items", "*", "id"] removes the id of each item)
+pub fn prune_matching(value: &mut JsonValue, path: &[String]) {
+    let Some((first, rest)) = path.split_first() else { return };
+    if first != "*" {
+        if rest.is_empty() {
+            prune(value, path);
+        } else if let Some(child) = navigate_mut(value, std::slice::from_ref(first)) {
+            prune_matching(child, rest);
+        }
+        return;
+    }
+
+    match value {
+        JsonValue::Object(map) if rest.is_empty() => map.clear(),
+        JsonValue::Array(items) if rest.is_empty() => items.clear(),
+        JsonValue::Object(map) => map.values_mut().for_each(|child| prune_matching(child, rest)),
+        JsonValue::Array(items) => items.iter_mut().for_each(|child| prune_matching(child, rest)),
+        _ => {}
+    }
+}
+
+/// Turn the arrays at `path` (a `*` segment matching every item or member) into objects keyed by
+/// each item's `field`, as "[field=value]", so items are compared by identity rather than by
+/// position. Items without the field keep their position as "[#index]"; a repeated id gets a
+/// "#2", "#3", ... suffix.
+pub fn key_array_items(value: &mut JsonValue, path: &[String], field: &str) {
+    let Some((first, rest)) = path.split_first() else {
+        if let JsonValue::Array(items) = value {
+            *value = keyed_items(std::mem::take(items), field);
+        }
+        return;
+    };
+    match value {
+        JsonValue::Object(map) if first == "*" => {
+            map.values_mut().for_each(|child| key_array_items(child, rest, field))
+        }
+        JsonValue::Array(items) if first == "*" => {
+            items.iter_mut().for_each(|child| key_array_items(child, rest, field))
+        }
+        _ => {
+            if let Some(child) = navigate_mut(value, std::slice::from_ref(first)) {
+                key_array_items(child, rest, field);
+            }
+        }
+    }
+}
+
+fn keyed_items(items: Vec<JsonValue>, field: &str) -> JsonValue {
+    JsonValue::Object(item_keys(&items, field).into_iter().zip(items).collect())
+}
+
+/// The key `keyed_items` gives each item, in order
+fn item_keys(items: &[JsonValue], field: &str) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::with_capacity(items.len());
+    for (index, item) in items.iter().enumerate() {
+        let key = match item.get(field) {
+            Some(JsonValue::String(id)) => format!("[{field}={id}]"),
+            Some(id @ (JsonValue::Number(_) | JsonValue::Bool(_))) => format!("[{field}={id}]"),
+            _ => format!("[#{index}]")
+        };
+        let key = if keys.contains(&key) {
+            (2..).map(|n| format!("{key}#{n}")).find(|key| !keys.contains(key)).unwrap_or(key)
+        } else {
+            key
+        };
+        keys.push(key);
+    }
+    keys
+}
+
+/// Position of the item a `key_array_items` segment ("[id=7]", "[id=7]#2", "[#3]") names in the
+/// array it was made from
+fn keyed_index(items: &[JsonValue], segment: &str) -> Option<usize> {
+    let inner = segment.strip_prefix('[')?;
+    if let Some(index) = inner.strip_prefix('#') {
+        return index.strip_suffix(']')?.parse().ok();
+    }
+    let (field, _) = inner.split_once('=')?;
+    item_keys(items, field).iter().position(|key| key == segment)
+}
+
+/// Translate a path into a document with keyed arrays (see `key_array_items`) to the same location
+/// in the `original` document, e.g. "/orders/[id=2]/qty" -> "/orders/1/qty". Also tells whether
+/// the last segment named an item of a keyed array.
+pub fn positional_path(original: &JsonValue, path: &[String]) -> (Vec<String>, bool) {
+    let mut current = Some(original);
+    let mut segments = Vec::with_capacity(path.len());
+    let mut item = false;
+    for segment in path {
+        let index = match current {
+            Some(JsonValue::Array(items)) => keyed_index(items, segment),
+            _ => None
+        };
+        item = index.is_some();
+        let segment = index.map_or_else(|| segment.clone(), |index| index.to_string());
+        current = current.and_then(|value| navigate(value, std::slice::from_ref(&segment)));
+        segments.push(segment);
+    }
+    (segments, item)
+}
+
+/// Remove the value(s) at a JSON Pointer, if present; `*` segments match every item or member
+pub fn remove_pointer(value: &mut JsonValue, pointer: &str) {
+    prune_matching(value, &parse_pointer(pointer));
+}
+
+/// Array index for a segment; JSON Pointer allows no sign or leading zeros
+fn array_index(segment: &str) -> Option<usize> {
+    if segment.len() > 1 && segment.starts_with('0') {
+        return None;
+    }
+    segment.parse().ok().filter(|_| segment.bytes().all(|b| b.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn segments(path: &[&str]) -> Vec<String> {
+        path.iter().map(|segment| segment.to_string()).collect()
+    }
+
+    #[test]
+    fn pointers_and_dotted_paths_parse_to_the_same_segments() {
+        assert_eq!(parse_pointer("/items/0/a~1b~0c"), segments(&["items", "0", "a/b~c"]));
+        assert_eq!(parse_pointer(""), Vec::<String>::new());
+        assert_eq!(parse_dotted("items[0].id"), segments(&["items", "0", "id"]));
+        assert_eq!(parse_dotted("items.0.id"), parse_dotted("items[0].id"));
+        assert_eq!(parse_dotted("grid[1][2]"), segments(&["grid", "1", "2"]));
+    }
+
+    #[test]
+    fn navigates_objects_and_arrays() {
+        let value = json!({"items": [{"id": 1}, {"id": 2}], "01": "key"});
+        assert_eq!(navigate(&value, &segments(&["items", "1", "id"])), Some(&json!(2)));
+        assert_eq!(navigate(&value, &segments(&["01"])), Some(&json!("key")));
+        assert_eq!(navigate(&value, &segments(&["items", "01"])), None);
+        assert_eq!(navigate(&value, &segments(&["items", "-1"])), None);
+        assert_eq!(navigate(&value, &segments(&["items", "2"])), None);
+        assert_eq!(navigate(&value, &[]), Some(&value));
+    }
+
+    #[test]
+    fn prunes_and_inserts_at_paths() {
+        let mut value = json!({"data": {"a": 1, "b": 2}, "items": [1, 2, 3]});
+        prune(&mut value, &segments(&["data", "a"]));
+        prune(&mut value, &segments(&["items", "0"]));
+        prune(&mut value, &segments(&["missing", "x"]));
+        assert_eq!(value, json!({"data": {"b": 2}, "items": [2, 3]}));
+
+        let mut projected = json!({});
+        insert(&mut projected, &segments(&["data", "amount"]), json!(5));
+        insert(&mut projected, &segments(&["data", "amount", "deeper"]), json!(6));
+        assert_eq!(projected, json!({"data": {"amount": 5}}));
+        insert(&mut projected, &[], json!(null));
+        assert_eq!(projected, json!(null));
+    }
+
+    #[test]
+    fn wildcards_remove_from_every_item_or_member() {
+        let mut value = json!({
+            "items": [{"id": 1, "q": 1}, {"id": 2, "q": 2}, "plain"],
+            "by_name": {"a": {"ts": 1, "v": 1}, "b": {"ts": 2}},
+            "tags": ["x", "y"]
+        });
+        remove_pointer(&mut value, "/items/*/id");
+        remove_pointer(&mut value, "/by_name/*/ts");
+        remove_pointer(&mut value, "/tags/*");
+        remove_pointer(&mut value, "/missing/*/id");
+        assert_eq!(
+            value,
+            json!({"items": [{"q": 1}, {"q": 2}, "plain"], "by_name": {"a": {"v": 1}, "b": {}}, "tags": []})
+        );
+    }
+
+    #[test]
+    fn keyed_segments_map_back_to_positions() {
+        let original = json!({"orders": [{"id": 7}, {"id": 9}, {"id": 9}, {"name": "x"}]});
+        let path = |pointer: &str| positional_path(&original, &parse_pointer(pointer));
+
+        assert_eq!(path("/orders/[id=9]/id"), (vec!["orders".into(), "1".into(), "id".into()], false));
+        assert_eq!(path("/orders/[id=9]#2"), (vec!["orders".into(), "2".into()], true));
+        assert_eq!(path("/orders/[#3]"), (vec!["orders".into(), "3".into()], true));
+        assert_eq!(path("/orders/0"), (vec!["orders".into(), "0".into()], false));
+    }
+}
@@ -0,0 +1,314 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashSet}
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{State, glob_match, is_glob};
+
+/// How states of a completed round are paired up for comparison
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchStrategy {
+    /// Pair the i-th left state with the i-th right state, whatever their keys
+    PositionalIndex,
+    /// Pair each left state with the first right state of the same key (right states may be
+    /// reused)
+    #[default]
+    FirstByKey,
+    /// Pair each left state with the earliest unused right state of the same key
+    SequentialByKey,
+    /// Within each key, pick the pairing with the smallest total diff
+    OptimalByKey
+}
+
+/// How alignment keys are normalized before they're compared; the keys stored on states (and
+/// shown in reports) stay as received
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Normalize {
+    /// Compare keys ignoring case (e.g., "GameCleared" and "gamecleared")
+    pub lowercase: bool,
+    /// Ignore leading and trailing whitespace
+    pub trim:      bool
+}
+
+impl Normalize {
+    pub fn apply<'a>(&self, key: &'a str) -> Cow<'a, str> {
+        let key = if self.trim { key.trim() } else { key };
+        if self.lowercase { Cow::Owned(key.to_lowercase()) } else { Cow::Borrowed(key) }
+    }
+}
+
+/// How two alignment keys are judged equal: exactly, or, with a tolerance, as numbers when both
+/// parse as one (so "1.2300001" and "1.23" align); either way after any normalization
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyComparison {
+    tolerance: Option<f64>,
+    normalize: Normalize
+}
+
+impl KeyComparison {
+    /// Keys that both parse as numbers match when they differ by at most `tolerance`; other keys
+    /// still need to be equal strings
+    pub fn numeric(tolerance: f64) -> Self {
+        Self::default().with_tolerance(tolerance)
+    }
+
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = Some(tolerance);
+        self
+    }
+
+    pub fn with_normalization(mut self, normalize: Normalize) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    pub fn matches(&self, left: &str, right: &str) -> bool {
+        let (left, right) = (self.normalize.apply(left), self.normalize.apply(right));
+        if left == right {
+            return true;
+        }
+        let Some(tolerance) = self.tolerance else {
+            return false;
+        };
+        match (left.trim().parse::<f64>(), right.trim().parse::<f64>()) {
+            (Ok(l), Ok(r)) => (l - r).abs() <= tolerance,
+            _ => false
+        }
+    }
+
+    /// Whether `key` matches `signal` (e.g., a round-end signal): as a key first, then, when the
+    /// signal has `*` or `?` wildcards, as a glob over the normalized key
+    pub fn matches_signal(&self, key: &str, signal: &str) -> bool {
+        self.matches(key, signal)
+            || is_glob(signal) && glob_match(&self.normalize.apply(signal), &self.normalize.apply(key))
+    }
+
+    /// Optional keys as stored on states: two missing keys count as the same
+    pub fn same(&self, left: Option<&str>, right: Option<&str>) -> bool {
+        match (left, right) {
+            (Some(l), Some(r)) => self.matches(l, r),
+            _ => left == right
+        }
+    }
+}
+
+/// One entry of a round pairing: indices into the left/right states, `None` when a state has no
+/// counterpart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundPair {
+    pub left:  Option<usize>,
+    pub right: Option<usize>
+}
+
+impl RoundPair {
+    fn new(left: Option<usize>, right: Option<usize>) -> Self {
+        Self { left, right }
+    }
+}
+
+/// Pair up the states of a round. Pairs come in left order, followed by right states that were
+/// left unpaired. Keyed strategies skip states without an alignment key; `cost(i, j)` of pairing
+/// `left[i]` with `right[j]` (e.g., the number of diff ops) is only consulted by `OptimalByKey`, so
+/// payloads can be loaded on demand; `keys` decides which keys count as the same.
+pub fn match_states<C>(
+    strategy: MatchStrategy,
+    keys: KeyComparison,
+    left: &[State],
+    right: &[State],
+    cost: C
+) -> Vec<RoundPair>
+where
+    C: Fn(usize, usize) -> usize
+{
+    match strategy {
+        MatchStrategy::PositionalIndex => (0..left.len().max(right.len()))
+            .map(|i| RoundPair::new((i < left.len()).then_some(i), (i < right.len()).then_some(i)))
+            .collect(),
+        MatchStrategy::FirstByKey => {
+            let mut pairs: Vec<RoundPair> = keyed(left)
+                .map(|(i, key)| {
+                    RoundPair::new(Some(i), keyed(right).find(|(_, k)| keys.matches(key, k)).map(|(j, _)| j))
+                })
+                .collect();
+            let left_keys: HashSet<&str> = keyed(left).map(|(_, key)| key).collect();
+            pairs.extend(
+                keyed(right)
+                    .filter(|(_, key)| !left_keys.iter().any(|left_key| keys.matches(left_key, key)))
+                    .map(|(j, _)| RoundPair::new(None, Some(j)))
+            );
+            pairs
+        }
+        MatchStrategy::SequentialByKey => {
+            let mut used = vec![false; right.len()];
+            let mut pairs: Vec<RoundPair> = keyed(left)
+                .map(|(i, key)| {
+                    let j = keyed(right).find(|(j, k)| keys.matches(key, k) && !used[*j]).map(|(j, _)| j);
+                    if let Some(j) = j {
+                        used[j] = true;
+                    }
+                    RoundPair::new(Some(i), j)
+                })
+                .collect();
+            pairs.extend(keyed(right).filter(|(j, _)| !used[*j]).map(|(j, _)| RoundPair::new(None, Some(j))));
+            pairs
+        }
+        MatchStrategy::OptimalByKey => {
+            let mut groups: BTreeMap<&str, (Vec<usize>, Vec<usize>)> = BTreeMap::new();
+            for (i, key) in keyed(left) {
+                groups.entry(key).or_default().0.push(i);
+            }
+            for (j, key) in keyed(right) {
+                // Join the first group whose key matches, so near-equal keys share a group
+                let group = groups.keys().copied().find(|group| keys.matches(group, key)).unwrap_or(key);
+                groups.entry(group).or_default().1.push(j);
+            }
+
+            let mut partner: Vec<Option<usize>> = vec![None; left.len()];
+            let mut used = vec![false; right.len()];
+            for (lefts, rights) in groups.values() {
+                let costs: Vec<Vec<usize>> =
+                    lefts.iter().map(|&i| rights.iter().map(|&j| cost(i, j)).collect()).collect();
+                for (a, b) in min_cost_assignment(&costs) {
+                    partner[lefts[a]] = Some(rights[b]);
+                    used[rights[b]] = true;
+                }
+            }
+
+            let mut pairs: Vec<RoundPair> = keyed(left).map(|(i, _)| RoundPair::new(Some(i), partner[i])).collect();
+            pairs.extend(keyed(right).filter(|(j, _)| !used[*j]).map(|(j, _)| RoundPair::new(None, Some(j))));
+            pairs
+        }
+    }
+}
+
+/// Indices and keys of the states that have an alignment key
+fn keyed(states: &[State]) -> impl Iterator<Item = (usize, &str)> {
+    states.iter().enumerate().filter_map(|(i, s)| s.alignment_key.as_deref().map(|key| (i, key)))
+}
+
+/// Hungarian algorithm on a rows x cols cost matrix. Returns `(row, col)` pairs covering
+/// `min(rows, cols)` entries with the smallest total cost.
+fn min_cost_assignment(costs: &[Vec<usize>]) -> Vec<(usize, usize)> {
+    let rows = costs.len();
+    let cols = costs.first().map_or(0, |row| row.len());
+    if rows == 0 || cols == 0 {
+        return Vec::new();
+    }
+
+    // The algorithm needs rows <= cols; solve the transpose otherwise
+    if rows > cols {
+        let transposed: Vec<Vec<usize>> = (0..cols).map(|c| (0..rows).map(|r| costs[r][c]).collect()).collect();
+        return min_cost_assignment(&transposed).into_iter().map(|(c, r)| (r, c)).collect();
+    }
+
+    // 1-based potentials formulation: u/v are row/column potentials, matched[c] is the row assigned
+    // to column c (0 = none)
+    let cost = |r: usize, c: usize| costs[r - 1][c - 1] as i64;
+    let mut u = vec![0i64; rows + 1];
+    let mut v = vec![0i64; cols + 1];
+    let mut matched = vec![0usize; cols + 1];
+    let mut way = vec![0usize; cols + 1];
+
+    for row in 1..=rows {
+        matched[0] = row;
+        let mut col0 = 0;
+        let mut min_to = vec![i64::MAX; cols + 1];
+        let mut visited = vec![false; cols + 1];
+
+        loop {
+            visited[col0] = true;
+            let r = matched[col0];
+            let mut delta = i64::MAX;
+            let mut next = 0;
+            for c in 1..=cols {
+                if !visited[c] {
+                    let reduced = cost(r, c) - u[r] - v[c];
+                    if reduced < min_to[c] {
+                        min_to[c] = reduced;
+                        way[c] = col0;
+                    }
+                    if min_to[c] < delta {
+                        delta = min_to[c];
+                        next = c;
+                    }
+                }
+            }
+            for c in 0..=cols {
+                if visited[c] {
+                    u[matched[c]] += delta;
+                    v[c] -= delta;
+                } else {
+                    min_to[c] -= delta;
+                }
+            }
+            col0 = next;
+            if matched[col0] == 0 {
+                break;
+            }
+        }
+
+        // Flip the augmenting path
+        while col0 != 0 {
+            let prev = way[col0];
+            matched[col0] = matched[prev];
+            col0 = prev;
+        }
+    }
+
+    (1..=cols).filter(|&c| matched[c] != 0).map(|c| (matched[c] - 1, c - 1)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn states(keys: &[&str]) -> Vec<State> {
+        keys.iter().map(|key| State::new(json!({}), Some(key.to_string()))).collect()
+    }
+
+    #[test]
+    fn numeric_keys_match_within_the_tolerance() {
+        let keys = KeyComparison::numeric(0.001);
+        assert!(keys.matches("1.23", "1.2300001"));
+        assert!(keys.matches(" 2 ", "2.0"));
+        assert!(!keys.matches("1.23", "1.25"));
+        assert!(!keys.matches("a", "A"));
+        assert!(!KeyComparison::default().matches("1.23", "1.2300001"));
+        assert!(keys.same(None, None) && !keys.same(Some("1"), None));
+    }
+
+    #[test]
+    fn signals_match_exactly_first_then_as_globs() {
+        let keys = KeyComparison::default().with_normalization(Normalize { lowercase: true, trim: false });
+        assert!(keys.matches_signal("Round.7.Completed", "round.*.completed"));
+        assert!(keys.matches_signal("round.*.completed", "round.*.completed"));
+        assert!(!keys.matches_signal("round.7.started", "round.*.completed"));
+        assert!(!KeyComparison::default().matches_signal("round.7.completed", "round.7"));
+    }
+
+    #[test]
+    fn pairs_near_equal_keys_with_a_tolerance() {
+        let (left, right) = (states(&["1.0", "2.0"]), states(&["2.0000001", "1.0000001"]));
+        let pairs = |keys| match_states(MatchStrategy::SequentialByKey, keys, &left, &right, |_, _| 0);
+
+        assert_eq!(
+            pairs(KeyComparison::numeric(1e-6)),
+            vec![RoundPair::new(Some(0), Some(1)), RoundPair::new(Some(1), Some(0))]
+        );
+        assert_eq!(
+            pairs(KeyComparison::default()),
+            vec![
+                RoundPair::new(Some(0), None),
+                RoundPair::new(Some(1), None),
+                RoundPair::new(None, Some(0)),
+                RoundPair::new(None, Some(1))
+            ]
+        );
+    }
+}
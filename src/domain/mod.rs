@@ -1,5 +1,39 @@
+mod diff;
 mod error;
+mod explain;
+mod flat_diff;
+mod glob;
+mod json;
+mod json_path;
+mod matching;
+mod metadata;
+mod outcome;
+mod required;
+mod rollup;
+mod schema;
+mod source_error;
 mod state;
+mod timeline;
+mod tolerance;
+mod triage;
+mod unified;
 
+pub use diff::*;
 pub use error::*;
+pub use explain::*;
+pub use flat_diff::*;
+pub use glob::*;
+pub use json::*;
+pub use json_path::*;
+pub use matching::*;
+pub use metadata::*;
+pub use outcome::*;
+pub use required::*;
+pub use rollup::*;
+pub use schema::*;
+pub use source_error::*;
 pub use state::*;
+pub use timeline::*;
+pub use tolerance::*;
+pub use triage::*;
+pub use unified::*;
@@ -0,0 +1,32 @@
+use std::fmt;
+
+/// What a tracking session found, for deciding whether the two sides agreed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrackerOutcome {
+    /// Rounds compared
+    pub rounds:          usize,
+    /// Rounds with at least one differing, mismatched or missing state
+    pub diverged_rounds: usize,
+    /// Aligned pairs whose payloads differed
+    pub differing:       usize,
+    /// Pairs whose alignment keys didn't match
+    pub mismatched:      usize,
+    /// States without a counterpart on the other side
+    pub missing:         usize
+}
+
+impl TrackerOutcome {
+    /// Whether anything differed, mismatched or went missing
+    pub fn diverged(&self) -> bool {
+        self.differing + self.mismatched + self.missing > 0
+    }
+}
+
+impl fmt::Display for TrackerOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.rounds > 0 {
+            write!(f, "{} of {} round(s) diverged, ", self.diverged_rounds, self.rounds)?;
+        }
+        write!(f, "{} differing, {} mismatched, {} missing", self.differing, self.mismatched, self.missing)
+    }
+}
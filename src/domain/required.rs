@@ -0,0 +1,41 @@
+use std::fmt;
+
+/// Required alignment keys that never showed up, with the side(s) each one is missing from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingKeys {
+    pub keys: Vec<(String, Vec<&'static str>)>
+}
+
+impl MissingKeys {
+    /// Check `required` against what each side has seen; `None` when every key was seen on both
+    pub fn check(
+        required: &[String],
+        seen_left: impl Fn(&str) -> bool,
+        seen_right: impl Fn(&str) -> bool
+    ) -> Option<Self> {
+        let keys: Vec<_> = required
+            .iter()
+            .filter_map(|key| {
+                let sides: Vec<_> = [("left", seen_left(key)), ("right", seen_right(key))]
+                    .into_iter()
+                    .filter_map(|(side, seen)| (!seen).then_some(side))
+                    .collect();
+                (!sides.is_empty()).then(|| (key.clone(), sides))
+            })
+            .collect();
+
+        (!keys.is_empty()).then_some(Self { keys })
+    }
+}
+
+impl fmt::Display for MissingKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (key, sides)) in self.keys.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{key} ({})", sides.join(", "))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt
+};
+
+use serde::Serialize;
+
+use super::DiffResult;
+
+/// Union of the paths changed across a round's comparisons, with how many compared states each
+/// path changed in
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PathRollup {
+    /// Number of state pairs compared
+    pub comparisons: usize,
+    /// JSON Pointer -> number of compared states it changed in
+    pub paths:       BTreeMap<String, usize>
+}
+
+impl PathRollup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the changed paths of one comparison, as the differ found them (so ignored paths,
+    /// tolerances and array keys apply); a path counts once per state however many operations
+    /// touch it
+    pub fn record(&mut self, result: &DiffResult) {
+        self.comparisons += 1;
+        let changed: BTreeSet<String> = result.patch.0.iter().map(|op| op.path().to_string()).collect();
+        for path in changed {
+            let path = if path.is_empty() { "(root)".to_string() } else { path };
+            *self.paths.entry(path).or_default() += 1;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Paths ordered by how many states they changed in (most first), then by path
+    pub fn ranked(&self) -> Vec<(&str, usize)> {
+        let mut ranked: Vec<(&str, usize)> = self.paths.iter().map(|(path, &count)| (path.as_str(), count)).collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        ranked
+    }
+}
+
+impl fmt::Display for PathRollup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ranked = self.ranked();
+        let width = ranked.iter().map(|(path, _)| path.len()).max().unwrap_or_default().max("PATH".len());
+        writeln!(f, "{:<width$}  STATES", "PATH")?;
+        for (path, count) in ranked {
+            writeln!(f, "{path:<width$}  {count}/{}", self.comparisons)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{adapter::JsonPatchDiffer, port::Differ};
+
+    #[test]
+    fn paths_count_once_per_comparison() {
+        let mut rollup = PathRollup::new();
+        rollup.record(&DiffResult::between(&json!({"a": [1, 2], "b": 1}), &json!({"a": [3, 4], "b": 2})));
+        rollup.record(&DiffResult::between(&json!({"a": [1, 2], "b": 1}), &json!({"a": [1, 2], "b": 3})));
+        rollup.record(&DiffResult::default());
+
+        assert_eq!(rollup.comparisons, 3);
+        assert_eq!(rollup.ranked(), vec![("/b", 2), ("/a/0", 1), ("/a/1", 1)]);
+    }
+
+    #[test]
+    fn ignored_paths_stay_out_of_the_rollup() {
+        let differ = JsonPatchDiffer::default().with_ignore_paths(vec!["/ts".to_string()]);
+        let mut rollup = PathRollup::new();
+        rollup.record(&differ.compute_diff(&json!({"ts": 1, "hp": 10}), &json!({"ts": 2, "hp": 8})));
+        assert_eq!(rollup.ranked(), vec![("/hp", 1)]);
+    }
+
+    #[test]
+    fn root_changes_are_labeled() {
+        let mut rollup = PathRollup::new();
+        rollup.record(&DiffResult::between(&json!(1), &json!(2)));
+        assert!(rollup.to_string().contains("(root)  1/1"));
+    }
+}
@@ -0,0 +1,56 @@
+use std::fmt;
+
+use serde_json::Value as JsonValue;
+
+/// A path where both sides hold a value, but of a different JSON type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeChange {
+    /// JSON Pointer to the value ("" is the document root)
+    pub path:  String,
+    pub left:  &'static str,
+    pub right: &'static str
+}
+
+impl fmt::Display for TypeChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() { "/" } else { &self.path };
+        write!(f, "{path} is {} on left but {} on right", self.left, self.right)
+    }
+}
+
+/// Name of a value's JSON type
+pub fn json_type(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "boolean",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object"
+    }
+}
+
+/// Find the first path present on both sides whose JSON type differs. Fields or elements that
+/// exist on one side only are not type changes and are skipped.
+pub fn find_type_change(left: &JsonValue, right: &JsonValue) -> Option<TypeChange> {
+    find_at(String::new(), left, right)
+}
+
+fn find_at(path: String, left: &JsonValue, right: &JsonValue) -> Option<TypeChange> {
+    match (left, right) {
+        (JsonValue::Object(l), JsonValue::Object(r)) => l.iter().find_map(|(key, l_value)| {
+            let r_value = r.get(key)?;
+            let segment = key.replace('~', "~0").replace('/', "~1");
+            find_at(format!("{path}/{segment}"), l_value, r_value)
+        }),
+        (JsonValue::Array(l), JsonValue::Array(r)) => l
+            .iter()
+            .zip(r)
+            .enumerate()
+            .find_map(|(i, (l_value, r_value))| find_at(format!("{path}/{i}"), l_value, r_value)),
+        _ if json_type(left) != json_type(right) => {
+            Some(TypeChange { path, left: json_type(left), right: json_type(right) })
+        }
+        _ => None
+    }
+}
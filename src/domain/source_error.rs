@@ -0,0 +1,22 @@
+use std::fmt;
+
+/// A failure a source recovered from (connect/read error, peer close), reported so the tracker can
+/// react to persistent source trouble instead of it only showing up in logs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceError {
+    /// Name of the failing source (e.g., "left")
+    pub source:  String,
+    pub message: String
+}
+
+impl SourceError {
+    pub fn new<S: Into<String>, M: Into<String>>(source: S, message: M) -> Self {
+        Self { source: source.into(), message: message.into() }
+    }
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.source, self.message)
+    }
+}
@@ -1,7 +1,18 @@
+use std::{
+    borrow::Borrow,
+    collections::{VecDeque, vec_deque},
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path
+};
+
+#[cfg(feature = "compression")]
+use flate2::{Compression, read::MultiGzDecoder, write::GzEncoder};
+use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
 /// Generic state with an optional alignment key
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct State {
     /// The raw JSON data
     pub data: JsonValue,
@@ -23,34 +34,36 @@ impl State {
     }
 }
 
+/// The newest `max_size` states, oldest first; past capacity each push evicts the oldest in O(1)
 #[derive(Debug, Clone)]
 pub struct StateBuffer {
-    states:   Vec<State>,
+    states:   VecDeque<State>,
     max_size: usize
 }
 
 impl StateBuffer {
     pub fn new(max_size: usize) -> Self {
-        Self { states: Vec::new(), max_size }
+        Self { states: VecDeque::new(), max_size }
     }
 
     pub fn push(&mut self, state: State) {
-        self.states.push(state);
+        self.states.push_back(state);
         if self.states.len() > self.max_size {
-            self.states.remove(0);
+            self.states.pop_front();
         }
     }
 
     pub fn latest(&self) -> Option<&State> {
-        self.states.last()
+        self.states.back()
     }
 
     pub fn latest_alignment_key(&self) -> Option<&str> {
         self.latest().and_then(|s| s.alignment_key.as_deref())
     }
 
-    pub fn states(&self) -> &[State] {
-        &self.states
+    /// Buffered states, oldest first
+    pub fn states(&self) -> vec_deque::Iter<'_, State> {
+        self.states.iter()
     }
 
     pub fn clear(&mut self) {
@@ -64,4 +77,168 @@ impl StateBuffer {
     pub fn is_empty(&self) -> bool {
         self.states.is_empty()
     }
+
+    /// Write the buffered states to `path` as NDJSON, oldest first (see `save_states`)
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        save_states(path, &self.states)
+    }
+
+    /// Read states written by `save` (gzipped when the path ends in ".gz"); the buffer's capacity
+    /// is the number of states loaded
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = File::open(&path)?;
+        let reader: Box<dyn BufRead> =
+            if is_gz(path.as_ref()) { gunzip(file)? } else { Box::new(BufReader::new(file)) };
+        let mut states = VecDeque::new();
+        for line in reader.lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                states.push_back(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(Self { max_size: states.len(), states })
+    }
+}
+
+/// Write states to `path` as NDJSON, one `{"data", "alignment_key", "timestamp"}` object per line,
+/// gzip-compressed when the path ends in ".gz"
+pub fn save_states<P: AsRef<Path>, S: Borrow<State>>(path: P, states: impl IntoIterator<Item = S>) -> io::Result<()> {
+    if !is_gz(path.as_ref()) {
+        return write_states(BufWriter::new(File::create(&path)?), states)?.flush();
+    }
+    gzip_states(path.as_ref(), states)
+}
+
+#[cfg(feature = "compression")]
+fn gzip_states<S: Borrow<State>>(path: &Path, states: impl IntoIterator<Item = S>) -> io::Result<()> {
+    let file = BufWriter::new(File::create(path)?);
+    write_states(GzEncoder::new(file, Compression::default()), states)?.finish()?.flush()
+}
+
+#[cfg(not(feature = "compression"))]
+fn gzip_states<S: Borrow<State>>(_path: &Path, _states: impl IntoIterator<Item = S>) -> io::Result<()> {
+    Err(gzip_unsupported())
+}
+
+#[cfg(feature = "compression")]
+fn gunzip(file: File) -> io::Result<Box<dyn BufRead>> {
+    Ok(Box::new(BufReader::new(MultiGzDecoder::new(file))))
+}
+
+#[cfg(not(feature = "compression"))]
+fn gunzip(_file: File) -> io::Result<Box<dyn BufRead>> {
+    Err(gzip_unsupported())
+}
+
+/// The error for a ".gz" file in a build without the `compression` feature
+#[cfg(not(feature = "compression"))]
+pub fn gzip_unsupported() -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, "gzip files need a build with the `compression` feature")
+}
+
+fn write_states<W: Write, S: Borrow<State>>(mut out: W, states: impl IntoIterator<Item = S>) -> io::Result<W> {
+    for state in states {
+        serde_json::to_writer(&mut out, state.borrow())?;
+        out.write_all(b"\n")?;
+    }
+    Ok(out)
+}
+
+fn is_gz(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
+
+/// Lightweight record kept in place of a full state once it is known to match its counterpart
+#[derive(Debug, Clone)]
+pub struct MatchedSummary {
+    pub alignment_key: Option<String>,
+    pub timestamp:     chrono::DateTime<chrono::Utc>
+}
+
+impl From<&State> for MatchedSummary {
+    fn from(state: &State) -> Self {
+        Self { alignment_key: state.alignment_key.clone(), timestamp: state.timestamp }
+    }
+}
+
+/// Two-tier buffered state: full data where it matters, a summary for matched states
+#[derive(Debug, Clone)]
+pub enum BufferedState {
+    Full(State),
+    Summary(MatchedSummary)
+}
+
+impl BufferedState {
+    pub fn alignment_key(&self) -> Option<&str> {
+        match self {
+            BufferedState::Full(state) => state.alignment_key.as_deref(),
+            BufferedState::Summary(summary) => summary.alignment_key.as_deref()
+        }
+    }
+
+    pub fn timestamp(&self) -> chrono::DateTime<chrono::Utc> {
+        match self {
+            BufferedState::Full(state) => state.timestamp,
+            BufferedState::Summary(summary) => summary.timestamp
+        }
+    }
+
+    /// The raw JSON data, if it was retained
+    pub fn data(&self) -> Option<&JsonValue> {
+        match self {
+            BufferedState::Full(state) => Some(&state.data),
+            BufferedState::Summary(_) => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn full_buffers_evict_the_oldest_state() {
+        let mut buffer = StateBuffer::new(2);
+        for seq in 0..4 {
+            buffer.push(State::new(json!({"seq": seq}), Some(seq.to_string())));
+        }
+        let keys: Vec<_> = buffer.states().filter_map(|state| state.alignment_key.as_deref()).collect();
+        assert_eq!(keys, ["2", "3"]);
+        assert_eq!(buffer.latest_alignment_key(), Some("3"));
+    }
+
+    #[test]
+    fn saved_states_load_back_from_plain_and_gzipped_files() {
+        let dir = std::env::temp_dir().join(format!("pica-state-dump-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut buffer = StateBuffer::new(10);
+        buffer.push(State::new(json!({"type": "bet", "v": 1}), Some("bet".to_string())));
+        buffer.push(State::with_data(json!([1, 2])));
+
+        let names = ["states.ndjson", "states.ndjson.gz"];
+        for name in names.into_iter().filter(|name| cfg!(feature = "compression") || !name.ends_with(".gz")) {
+            let path = dir.join(name);
+            buffer.save(&path).unwrap();
+            assert_eq!(std::fs::read(&path).unwrap().starts_with(&[0x1f, 0x8b]), name.ends_with(".gz"));
+
+            let loaded = StateBuffer::load(&path).unwrap();
+            assert_eq!(loaded.len(), 2);
+            for (saved, loaded) in buffer.states().zip(loaded.states()) {
+                assert_eq!((&saved.data, &saved.alignment_key), (&loaded.data, &loaded.alignment_key));
+                assert_eq!(saved.timestamp, loaded.timestamp);
+            }
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(not(feature = "compression"))]
+    #[test]
+    fn gzipped_files_need_the_compression_feature() {
+        let path = std::env::temp_dir().join(format!("pica-state-gz-{}.ndjson.gz", std::process::id()));
+        let err = StateBuffer::new(1).save(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        assert!(!path.exists());
+    }
 }
@@ -0,0 +1,43 @@
+/// How a report's timeline interleaves the two sides
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimelineAnchor {
+    /// Sort all events by timestamp; fine when both sides' clocks agree
+    #[default]
+    Time,
+    /// Keep the left side's arrival order and slot each right event after its matching left one
+    Left,
+    /// Keep the right side's arrival order and slot each left event after its matching right one
+    Right
+}
+
+/// Interleave an anchor side with another side without looking at timestamps. Events are given as
+/// alignment keys in arrival order; the result lists `(is_anchor, index)` pairs. The n-th other
+/// event with key `k` goes right after the n-th anchor event with key `k`; an unmatched other event
+/// follows the one before it, so the other side's own order is always kept.
+pub fn anchor_order(anchor: &[&str], other: &[&str]) -> Vec<(bool, usize)> {
+    // Slot of each other event: number of anchor events it comes after
+    let mut slots = Vec::with_capacity(other.len());
+    let mut used = vec![false; anchor.len()];
+    let mut floor = 0;
+    for key in other {
+        let matched = (0..anchor.len()).find(|&i| !used[i] && anchor[i] == *key);
+        if let Some(i) = matched {
+            used[i] = true;
+            floor = floor.max(i + 1);
+        }
+        slots.push(floor);
+    }
+
+    let mut order = Vec::with_capacity(anchor.len() + other.len());
+    let mut next_other = 0;
+    for slot in 0..=anchor.len() {
+        while next_other < other.len() && slots[next_other] == slot {
+            order.push((false, next_other));
+            next_other += 1;
+        }
+        if slot < anchor.len() {
+            order.push((true, slot));
+        }
+    }
+    order
+}
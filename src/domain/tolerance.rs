@@ -0,0 +1,76 @@
+use serde_json::Value as JsonValue;
+
+/// How far apart two numbers may be and still count as equal: at most `absolute` apart, or at most
+/// `relative` times the larger magnitude apart (e.g., 0.01 for 1%), whichever allows more
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Tolerance {
+    pub absolute: f64,
+    pub relative: f64
+}
+
+impl Tolerance {
+    pub fn absolute(absolute: f64) -> Self {
+        Self { absolute, relative: 0.0 }
+    }
+
+    pub fn relative(relative: f64) -> Self {
+        Self { absolute: 0.0, relative }
+    }
+
+    pub fn close(&self, a: f64, b: f64) -> bool {
+        let distance = (a - b).abs();
+        distance <= self.absolute || distance <= self.relative * a.abs().max(b.abs())
+    }
+}
+
+/// Copy left's number into right wherever both sides hold numbers within `tolerance`, so a diff
+/// of the two no longer reports them
+pub fn reconcile_numbers(left: &JsonValue, right: &mut JsonValue, tolerance: Tolerance) {
+    match (left, right) {
+        (JsonValue::Number(l), right @ JsonValue::Number(_)) => {
+            if let (Some(a), Some(b)) = (l.as_f64(), right.as_f64())
+                && tolerance.close(a, b)
+            {
+                *right = left.clone();
+            }
+        }
+        (JsonValue::Object(l), JsonValue::Object(r)) => {
+            for (key, r_val) in r.iter_mut() {
+                if let Some(l_val) = l.get(key) {
+                    reconcile_numbers(l_val, r_val, tolerance);
+                }
+            }
+        }
+        (JsonValue::Array(l), JsonValue::Array(r)) => {
+            for (l_val, r_val) in l.iter().zip(r.iter_mut()) {
+                reconcile_numbers(l_val, r_val, tolerance);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn absolute_and_relative_bounds() {
+        assert!(Tolerance::absolute(0.001).close(19.99, 19.990001));
+        assert!(!Tolerance::absolute(0.001).close(1000.0, 1001.0));
+        assert!(Tolerance::relative(0.01).close(1000.0, 1009.0));
+        assert!(!Tolerance::relative(0.01).close(1.0, 1.02));
+        assert!(Tolerance { absolute: 0.05, relative: 0.01 }.close(1.0, 1.02));
+        assert!(Tolerance::default().close(2.0, 2.0));
+    }
+
+    #[test]
+    fn close_numbers_take_the_left_value() {
+        let left = json!({"price": 19.99, "items": [1000, 5], "name": "a"});
+        let mut right = json!({"price": 19.990001, "items": [1005, 6], "name": "b"});
+        reconcile_numbers(&left, &mut right, Tolerance { absolute: 0.001, relative: 0.01 });
+        assert_eq!(right, json!({"price": 19.99, "items": [1000, 6], "name": "b"}));
+    }
+}
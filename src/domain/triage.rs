@@ -0,0 +1,47 @@
+use std::fmt;
+
+use super::DiffResult;
+
+/// Outcome of comparing two candidates against a known-good baseline for one key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TriageVerdict {
+    /// Both candidates match the baseline
+    Match,
+    /// Only left differs from the baseline
+    LeftRegressed,
+    /// Only right differs from the baseline
+    RightRegressed,
+    /// Both differ from the baseline the same way (an intended change, or a stale baseline)
+    BothChanged,
+    /// Both differ from the baseline, and from each other
+    BothRegressed
+}
+
+impl TriageVerdict {
+    /// Classify from the baseline↔left, baseline↔right and left↔right comparisons
+    pub fn classify(baseline_left: &DiffResult, baseline_right: &DiffResult, left_right: &DiffResult) -> Self {
+        match (baseline_left.identical, baseline_right.identical) {
+            (true, true) => Self::Match,
+            (false, true) => Self::LeftRegressed,
+            (true, false) => Self::RightRegressed,
+            (false, false) if left_right.identical => Self::BothChanged,
+            (false, false) => Self::BothRegressed
+        }
+    }
+
+    pub fn is_match(self) -> bool {
+        self == Self::Match
+    }
+}
+
+impl fmt::Display for TriageVerdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Match => "match",
+            Self::LeftRegressed => "left regressed",
+            Self::RightRegressed => "right regressed",
+            Self::BothChanged => "both changed alike",
+            Self::BothRegressed => "both regressed"
+        })
+    }
+}
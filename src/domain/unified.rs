@@ -0,0 +1,271 @@
+/// One line of a unified diff
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffLine<'a> {
+    Context(&'a str),
+    Removed(&'a str),
+    Added(&'a str)
+}
+
+/// A run of changed lines with the context around them, as headed by `@@ -a,b +c,d @@`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk<'a> {
+    /// First left line covered (1-based)
+    pub left_start:  usize,
+    pub left_len:    usize,
+    /// First right line covered (1-based)
+    pub right_start: usize,
+    pub right_len:   usize,
+    pub lines:       Vec<DiffLine<'a>>
+}
+
+impl Hunk<'_> {
+    /// The `@@ -a,b +c,d @@` header line
+    pub fn header(&self) -> String {
+        format!("@@ -{},{} +{},{} @@", self.left_start, self.left_len, self.right_start, self.right_len)
+    }
+}
+
+/// Line-based diff of two texts as unified-diff hunks with `context` unchanged lines around each
+/// change; hunks whose context would overlap are merged. Identical texts give no hunks.
+pub fn unified_hunks<'a>(left: &'a str, right: &'a str, context: usize) -> Vec<Hunk<'a>> {
+    let left: Vec<&str> = left.lines().collect();
+    let right: Vec<&str> = right.lines().collect();
+    let script = edit_script(&left, &right);
+
+    // Indices into the script of every change, grouped where the context between them overlaps
+    let changes: Vec<usize> =
+        script.iter().enumerate().filter(|(_, line)| !matches!(line, DiffLine::Context(_))).map(|(i, _)| i).collect();
+    let mut groups: Vec<(usize, usize)> = Vec::new();
+    for &i in &changes {
+        match groups.last_mut() {
+            Some((_, end)) if i <= *end + 2 * context + 1 => *end = i,
+            _ => groups.push((i, i))
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(first, last)| {
+            let start = first.saturating_sub(context);
+            let end = (last + context + 1).min(script.len());
+            // Lines of each side before the hunk
+            let (mut left_line, mut right_line) = (0, 0);
+            for line in &script[..start] {
+                match line {
+                    DiffLine::Context(_) => (left_line, right_line) = (left_line + 1, right_line + 1),
+                    DiffLine::Removed(_) => left_line += 1,
+                    DiffLine::Added(_) => right_line += 1
+                }
+            }
+            let lines = script[start..end].to_vec();
+            let left_len = lines.iter().filter(|line| !matches!(line, DiffLine::Added(_))).count();
+            let right_len = lines.iter().filter(|line| !matches!(line, DiffLine::Removed(_))).count();
+            Hunk {
+                // An empty range points at the line before it, as `diff -u` does
+                left_start: if left_len == 0 { left_line } else { left_line + 1 },
+                left_len,
+                right_start: if right_len == 0 { right_line } else { right_line + 1 },
+                right_len,
+                lines
+            }
+        })
+        .collect()
+}
+
+/// Shortest edit script between two line lists, removals before additions within each change.
+/// Myers' divide-and-conquer variant, so memory stays linear in the number of lines.
+fn edit_script<'a>(left: &[&'a str], right: &[&'a str]) -> Vec<DiffLine<'a>> {
+    let mut script = Vec::with_capacity(left.len().max(right.len()));
+    let max_d = (left.len() + right.len()).div_ceil(2) + 1;
+    let (mut forward, mut backward) = (Diagonals::new(max_d), Diagonals::new(max_d));
+    conquer(left, right, &mut forward, &mut backward, &mut script);
+
+    // Within each run of changes, removals first
+    let mut start = 0;
+    while start < script.len() {
+        if matches!(script[start], DiffLine::Context(_)) {
+            start += 1;
+            continue;
+        }
+        let end = script[start..]
+            .iter()
+            .position(|line| matches!(line, DiffLine::Context(_)))
+            .map_or(script.len(), |n| start + n);
+        script[start..end].sort_by_key(|line| matches!(line, DiffLine::Added(_)));
+        start = end;
+    }
+    script
+}
+
+/// Furthest x reached on each diagonal k = x - y, for k in -max..=max
+struct Diagonals {
+    offset: isize,
+    x:      Vec<usize>
+}
+
+impl Diagonals {
+    fn new(max: usize) -> Self {
+        Self { offset: max as isize, x: vec![0; 2 * max + 1] }
+    }
+}
+
+impl std::ops::Index<isize> for Diagonals {
+    type Output = usize;
+
+    fn index(&self, k: isize) -> &usize {
+        &self.x[(k + self.offset) as usize]
+    }
+}
+
+impl std::ops::IndexMut<isize> for Diagonals {
+    fn index_mut(&mut self, k: isize) -> &mut usize {
+        &mut self.x[(k + self.offset) as usize]
+    }
+}
+
+/// Append the edit script of `l` to `r`: split both at a point on an optimal path (the middle
+/// snake) and recurse into each half
+fn conquer<'a>(
+    l: &[&'a str],
+    r: &[&'a str],
+    forward: &mut Diagonals,
+    backward: &mut Diagonals,
+    script: &mut Vec<DiffLine<'a>>
+) {
+    let prefix = l.iter().zip(r).take_while(|(a, b)| a == b).count();
+    let suffix = l[prefix..].iter().rev().zip(r[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    script.extend(l[..prefix].iter().map(|line| DiffLine::Context(line)));
+    let (inner_l, inner_r) = (&l[prefix..l.len() - suffix], &r[prefix..r.len() - suffix]);
+
+    if inner_l.is_empty() || inner_r.is_empty() {
+        script.extend(inner_l.iter().map(|line| DiffLine::Removed(line)));
+        script.extend(inner_r.iter().map(|line| DiffLine::Added(line)));
+    } else {
+        let (x, y) = middle_snake(inner_l, inner_r, forward, backward);
+        conquer(&inner_l[..x], &inner_r[..y], forward, backward, script);
+        conquer(&inner_l[x..], &inner_r[y..], forward, backward, script);
+    }
+    script.extend(l[l.len() - suffix..].iter().map(|line| DiffLine::Context(line)));
+}
+
+/// A point on a shortest edit path between two non-empty lists that differ at both ends, found by
+/// searching from both corners until the paths overlap
+fn middle_snake(l: &[&str], r: &[&str], forward: &mut Diagonals, backward: &mut Diagonals) -> (usize, usize) {
+    let (n, m) = (l.len(), r.len());
+    let delta = n as isize - m as isize;
+    let odd = delta & 1 == 1;
+    forward[1] = 0;
+    backward[1] = 0;
+    let max_d = (n + m).div_ceil(2) as isize;
+    for d in 0..=max_d {
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && forward[k - 1] < forward[k + 1]) {
+                forward[k + 1]
+            } else {
+                forward[k - 1] + 1
+            };
+            let (x0, y0) = (x, (x as isize - k) as usize);
+            if x < n && y0 < m {
+                x += l[x..].iter().zip(&r[y0..]).take_while(|(a, b)| a == b).count();
+            }
+            forward[k] = x;
+            if odd && (k - delta).abs() < d && forward[k] + backward[delta - k] >= n {
+                return (x0, y0);
+            }
+        }
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && backward[k - 1] < backward[k + 1]) {
+                backward[k + 1]
+            } else {
+                backward[k - 1] + 1
+            };
+            let mut y = (x as isize - k) as usize;
+            if x < n && y < m {
+                let common = l[..n - x].iter().rev().zip(r[..m - y].iter().rev()).take_while(|(a, b)| a == b).count();
+                (x, y) = (x + common, y + common);
+            }
+            backward[k] = x;
+            if !odd && (k - delta).abs() <= d && backward[k] + forward[delta - k] >= n {
+                return (n - x, m - y);
+            }
+        }
+    }
+    unreachable!("the forward and backward paths always meet")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Both sides rebuilt from a script
+    fn sides<'a>(script: &[DiffLine<'a>]) -> (Vec<&'a str>, Vec<&'a str>) {
+        let (mut left, mut right) = (Vec::new(), Vec::new());
+        for line in script {
+            match *line {
+                DiffLine::Context(text) => {
+                    left.push(text);
+                    right.push(text);
+                }
+                DiffLine::Removed(text) => left.push(text),
+                DiffLine::Added(text) => right.push(text)
+            }
+        }
+        (left, right)
+    }
+
+    fn changes(script: &[DiffLine]) -> usize {
+        script.iter().filter(|line| !matches!(line, DiffLine::Context(_))).count()
+    }
+
+    #[test]
+    fn scripts_rebuild_both_sides_with_the_fewest_changes() {
+        let cases: [(&str, &str, usize); 6] = [
+            ("a b c a b b a", "c b a b a c", 5),
+            ("a b c", "a b c", 0),
+            ("", "x y", 2),
+            ("x y", "", 2),
+            ("a x b y c", "a b c", 2),
+            ("1 2 3 4 5", "0 1 2 9 4 5 6", 4)
+        ];
+        for (left, right, expected) in cases {
+            let (left, right): (Vec<&str>, Vec<&str>) =
+                (left.split_whitespace().collect(), right.split_whitespace().collect());
+            let script = edit_script(&left, &right);
+            assert_eq!(sides(&script), (left.clone(), right.clone()));
+            assert_eq!(changes(&script), expected, "{left:?} -> {right:?}");
+        }
+    }
+
+    #[test]
+    fn removals_come_before_additions() {
+        let script = edit_script(&["a", "old", "b"], &["a", "new", "b"]);
+        assert_eq!(
+            script,
+            [DiffLine::Context("a"), DiffLine::Removed("old"), DiffLine::Added("new"), DiffLine::Context("b")]
+        );
+    }
+
+    #[test]
+    fn large_inputs_with_edits_at_both_ends() {
+        let left: Vec<String> = (0..20_000).map(|i| format!("line {i}")).collect();
+        let mut right = left.clone();
+        right[0] = "changed".to_string();
+        right[19_999] = "changed".to_string();
+        let (left, right): (Vec<&str>, Vec<&str>) =
+            (left.iter().map(String::as_str).collect(), right.iter().map(String::as_str).collect());
+        let script = edit_script(&left, &right);
+        assert_eq!(changes(&script), 4);
+        assert_eq!(sides(&script), (left, right));
+    }
+
+    #[test]
+    fn hunks_carry_context_and_headers() {
+        let hunks = unified_hunks("a\nb\nc\nd\ne\n", "a\nb\nC\nd\ne\n", 1);
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].header(), "@@ -2,3 +2,3 @@");
+        assert_eq!(
+            hunks[0].lines,
+            [DiffLine::Context("b"), DiffLine::Removed("c"), DiffLine::Added("C"), DiffLine::Context("d")]
+        );
+    }
+}
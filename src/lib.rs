@@ -6,5 +6,5 @@ mod port;
 mod service;
 
 pub mod prelude {
-    pub use super::{adapter::*, domain::*, port::*, service::*};
+    pub use super::{adapter::*, domain::*, metric::*, port::*, service::*};
 }
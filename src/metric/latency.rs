@@ -0,0 +1,131 @@
+use std::{collections::VecDeque, fmt};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Sorted buffer of latency samples in milliseconds (how far right lags left for an aligned key;
+/// negative when right arrives first)
+#[derive(Debug, Clone, Default)]
+pub struct LatencyHistogram {
+    samples: Vec<f64>
+}
+
+/// Summary of a latency distribution, in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct LatencyStats {
+    pub count: usize,
+    pub min:   f64,
+    pub max:   f64,
+    pub avg:   f64,
+    pub p50:   f64,
+    pub p95:   f64,
+    pub p99:   f64
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one sample, keeping the buffer sorted so percentiles are a lookup
+    pub fn record(&mut self, millis: f64) {
+        let at = self.samples.partition_point(|&s| s <= millis);
+        self.samples.insert(at, millis);
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Nearest-rank percentile (`p` in 0..=100)
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let rank = ((p / 100.0) * self.samples.len() as f64).ceil() as usize;
+        Some(self.samples[rank.clamp(1, self.samples.len()) - 1])
+    }
+
+    pub fn stats(&self) -> Option<LatencyStats> {
+        let (&min, &max) = (self.samples.first()?, self.samples.last()?);
+        Some(LatencyStats {
+            count: self.samples.len(),
+            min,
+            max,
+            avg: self.samples.iter().sum::<f64>() / self.samples.len() as f64,
+            p50: self.percentile(50.0)?,
+            p95: self.percentile(95.0)?,
+            p99: self.percentile(99.0)?
+        })
+    }
+}
+
+impl LatencyStats {
+    /// The sample furthest from zero, keeping its sign (negative when right was furthest ahead)
+    pub fn peak(&self) -> f64 {
+        if self.min.abs() > self.max.abs() { self.min } else { self.max }
+    }
+}
+
+impl fmt::Display for LatencyStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} samples, p50 {:.1}ms, p95 {:.1}ms, p99 {:.1}ms (min {:.1}ms, avg {:.1}ms, max {:.1}ms)",
+            self.count, self.p50, self.p95, self.p99, self.min, self.avg, self.max
+        )
+    }
+}
+
+/// The latest lag samples, averaged so the figure follows the current lag rather than the whole
+/// session's
+#[derive(Debug, Clone)]
+pub struct RollingLag {
+    samples:  VecDeque<f64>,
+    capacity: usize
+}
+
+impl RollingLag {
+    pub fn new(capacity: usize) -> Self {
+        Self { samples: VecDeque::with_capacity(capacity), capacity: capacity.max(1) }
+    }
+
+    pub fn record(&mut self, millis: f64) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(millis);
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn average(&self) -> Option<f64> {
+        (!self.samples.is_empty()).then(|| self.samples.iter().sum::<f64>() / self.samples.len() as f64)
+    }
+}
+
+/// Which side was ahead and by how much, e.g., "left led by 320ms" for a positive lag
+pub fn describe_lead(millis: f64) -> String {
+    if millis.abs() < 0.5 {
+        "arrived together".to_string()
+    } else if millis > 0.0 {
+        format!("left led by {millis:.0}ms")
+    } else {
+        format!("right led by {:.0}ms", -millis)
+    }
+}
+
+/// How many milliseconds `right` arrived after `left` (negative when right was first)
+pub fn lag_millis(left: DateTime<Utc>, right: DateTime<Utc>) -> f64 {
+    (right - left).num_microseconds().unwrap_or_default() as f64 / 1000.0
+}
@@ -0,0 +1,221 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering}
+    },
+    time::Instant
+};
+
+use super::{DiffOpsHistogram, LatencyHistogram, LatencyStats, RollingLag, StatsdClient, Throughput};
+
+/// Aligned pairs behind the rolling lag average
+const LAG_WINDOW: usize = 100;
+
+/// Counters collected over a tracking session
+#[derive(Debug)]
+pub struct Metrics {
+    pub left:   Throughput,
+    pub right:  Throughput,
+    /// Right-vs-left lag of aligned states
+    latency:    Mutex<LatencyHistogram>,
+    /// Lag of the most recently aligned pair
+    last_lag:   Mutex<Option<f64>>,
+    /// Lag of the latest aligned pairs
+    recent_lag: Mutex<RollingLag>,
+    /// Patch operations of each compared pair whose payloads differed
+    diff_ops:   DiffOpsHistogram,
+    /// Compared pairs by alignment outcome, indexed by `AlignmentStatus`
+    alignment:  [AtomicU64; 3],
+    /// Source reconnects, by source name
+    reconnects: Mutex<BTreeMap<String, u64>>,
+    started:    Instant,
+    /// Optional StatsD sink; per-event metrics are batched, throughput is sent as deltas on flush
+    statsd:     Option<StatsdClient>,
+    /// Throughput totals already reported to StatsD: left/right messages, left/right bytes
+    reported:   Mutex<[u64; 4]>
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            left:       Throughput::default(),
+            right:      Throughput::default(),
+            latency:    Mutex::new(LatencyHistogram::new()),
+            last_lag:   Mutex::new(None),
+            recent_lag: Mutex::new(RollingLag::new(LAG_WINDOW)),
+            diff_ops:   DiffOpsHistogram::new(),
+            alignment:  Default::default(),
+            reconnects: Mutex::new(BTreeMap::new()),
+            started:    Instant::now(),
+            statsd:     None,
+            reported:   Mutex::new([0; 4])
+        }
+    }
+
+    pub fn with_statsd(mut self, client: StatsdClient) -> Self {
+        self.statsd = Some(client);
+        self
+    }
+
+    /// Record the lag of an aligned pair with this key
+    pub fn record_latency(&self, key: &str, millis: f64) {
+        self.latency.lock().unwrap_or_else(|e| e.into_inner()).record(millis);
+        *self.last_lag.lock().unwrap_or_else(|e| e.into_inner()) = Some(millis);
+        self.recent_lag.lock().unwrap_or_else(|e| e.into_inner()).record(millis);
+        if let Some(statsd) = &self.statsd {
+            statsd.timing(&format!("latency.{key}"), millis);
+        }
+    }
+
+    /// Count a compared pair whose payloads differed by `ops` patch operations
+    pub fn record_diff(&self, ops: usize) {
+        self.diff_ops.record(ops);
+        if let Some(statsd) = &self.statsd {
+            statsd.count("diffs", 1);
+            statsd.count("diff_ops", ops as u64);
+        }
+    }
+
+    /// Count a pair of a round (or a live alignment) by how its keys lined up
+    pub fn record_alignment(&self, status: AlignmentStatus) {
+        self.alignment[status as usize].fetch_add(1, Ordering::Relaxed);
+        if let Some(statsd) = &self.statsd {
+            statsd.count(&format!("alignment.{status}"), 1);
+        }
+    }
+
+    /// Count a reconnect of the named source
+    pub fn record_reconnect(&self, side: &str) {
+        *self.reconnects.lock().unwrap_or_else(|e| e.into_inner()).entry(side.to_string()).or_default() += 1;
+        if let Some(statsd) = &self.statsd {
+            statsd.count(&format!("{side}.reconnects"), 1);
+        }
+    }
+
+    pub fn diffs(&self) -> u64 {
+        self.diff_ops.count()
+    }
+
+    pub fn diff_ops(&self) -> &DiffOpsHistogram {
+        &self.diff_ops
+    }
+
+    pub fn alignment(&self, status: AlignmentStatus) -> u64 {
+        self.alignment[status as usize].load(Ordering::Relaxed)
+    }
+
+    /// "N aligned, N mismatched, N missing", if any pair's alignment was recorded
+    pub fn alignment_summary(&self) -> Option<String> {
+        let statuses = [AlignmentStatus::Aligned, AlignmentStatus::Mismatched, AlignmentStatus::Missing];
+        if statuses.iter().all(|&status| self.alignment(status) == 0) {
+            return None;
+        }
+        let counts: Vec<String> =
+            statuses.iter().map(|&status| format!("{} {status}", self.alignment(status))).collect();
+        Some(counts.join(", "))
+    }
+
+    pub fn reconnects(&self) -> u64 {
+        self.reconnects_by_source().values().sum()
+    }
+
+    pub fn reconnects_by_source(&self) -> BTreeMap<String, u64> {
+        self.reconnects.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Lag of the most recently aligned pair, in milliseconds
+    pub fn current_lag(&self) -> Option<f64> {
+        *self.last_lag.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Average lag of the latest aligned pairs (up to 100) and how many that was
+    pub fn rolling_lag(&self) -> Option<(f64, usize)> {
+        let recent = self.recent_lag.lock().unwrap_or_else(|e| e.into_inner());
+        Some((recent.average()?, recent.len()))
+    }
+
+    /// Lag percentiles so far, if any aligned pair has been seen
+    pub fn latency_stats(&self) -> Option<LatencyStats> {
+        self.latency.lock().unwrap_or_else(|e| e.into_inner()).stats()
+    }
+
+    /// Send message/byte counts accumulated since the last flush plus any buffered StatsD lines
+    pub fn flush_statsd(&self) {
+        let Some(statsd) = &self.statsd else {
+            return;
+        };
+
+        let totals = [self.left.messages(), self.right.messages(), self.left.bytes(), self.right.bytes()];
+        let mut reported = self.reported.lock().unwrap_or_else(|e| e.into_inner());
+        let names = ["left.messages", "right.messages", "left.bytes", "right.bytes"];
+        for ((name, total), seen) in names.iter().zip(totals).zip(reported.iter_mut()) {
+            if total > *seen {
+                statsd.count(name, total - *seen);
+                *seen = total;
+            }
+        }
+        statsd.flush();
+    }
+
+    /// One-line throughput summary for both sides (e.g., for a periodic stats line or shutdown)
+    pub fn summary(&self) -> String {
+        let elapsed = self.started.elapsed().as_secs_f64().max(f64::EPSILON);
+        let side = |name: &str, t: &Throughput| {
+            format!(
+                "{name}: {} msgs ({:.1}/s), {} ({}/s)",
+                t.messages(),
+                t.messages() as f64 / elapsed,
+                format_bytes(t.bytes() as f64),
+                format_bytes(t.bytes() as f64 / elapsed)
+            )
+        };
+
+        let mut summary = format!("{} | {}", side("left", &self.left), side("right", &self.right));
+        // Signed: positive when right trails left
+        if let (Some((average, count)), Some(stats)) = (self.rolling_lag(), self.latency_stats()) {
+            summary += &format!(" | right lag: avg {average:+.1}ms (last {count}), max {:+.1}ms", stats.peak());
+        }
+        summary
+    }
+}
+
+/// How a compared pair's keys lined up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlignmentStatus {
+    /// Both sides had a state with the same key
+    Aligned,
+    /// Both sides had a state, with different keys
+    Mismatched,
+    /// Only one side had a state
+    Missing
+}
+
+impl fmt::Display for AlignmentStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Aligned => "aligned",
+            Self::Mismatched => "mismatched",
+            Self::Missing => "missing"
+        })
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 { format!("{value:.0} {}", UNITS[unit]) } else { format!("{value:.1} {}", UNITS[unit]) }
+}
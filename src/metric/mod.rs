@@ -1 +1,15 @@
+mod latency;
+mod metrics;
+mod ops;
+#[cfg(feature = "metrics")]
+mod prometheus;
+mod statsd;
+mod throughput;
 
+pub use latency::*;
+pub use metrics::*;
+pub use ops::*;
+#[cfg(feature = "metrics")]
+pub use prometheus::*;
+pub use statsd::*;
+pub use throughput::*;
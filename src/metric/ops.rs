@@ -0,0 +1,93 @@
+use std::{
+    fmt,
+    sync::atomic::{AtomicU64, Ordering}
+};
+
+/// Upper bounds (inclusive) of the op-count buckets; the last bucket takes everything above
+const BUCKETS: [usize; 6] = [1, 2, 5, 10, 25, 100];
+
+/// Lock-free histogram of how many patch operations each differing pair took
+#[derive(Debug, Default)]
+pub struct DiffOpsHistogram {
+    counts: [AtomicU64; BUCKETS.len() + 1],
+    total:  AtomicU64,
+    max:    AtomicU64
+}
+
+impl DiffOpsHistogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, ops: usize) {
+        let bucket = BUCKETS.iter().position(|&bound| ops <= bound).unwrap_or(BUCKETS.len());
+        self.counts[bucket].fetch_add(1, Ordering::Relaxed);
+        self.total.fetch_add(ops as u64, Ordering::Relaxed);
+        self.max.fetch_max(ops as u64, Ordering::Relaxed);
+    }
+
+    /// Number of diffs recorded
+    pub fn count(&self) -> u64 {
+        self.counts.iter().map(|count| count.load(Ordering::Relaxed)).sum()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        let count = self.count();
+        (count > 0).then(|| self.total.load(Ordering::Relaxed) as f64 / count as f64)
+    }
+
+    /// Sum of the op counts of every recorded diff
+    pub fn total(&self) -> u64 {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    /// (upper bound, diffs with at most that many ops) per bucket, the last one unbounded
+    pub fn cumulative(&self) -> Vec<(Option<usize>, u64)> {
+        let mut running = 0;
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(i, count)| {
+                running += count.load(Ordering::Relaxed);
+                (BUCKETS.get(i).copied(), running)
+            })
+            .collect()
+    }
+
+    pub fn max(&self) -> u64 {
+        self.max.load(Ordering::Relaxed)
+    }
+
+    /// Non-empty buckets as (label, count), smallest first, e.g. ("3-5", 4)
+    pub fn buckets(&self) -> Vec<(String, u64)> {
+        let mut lower = 0;
+        let mut buckets = Vec::new();
+        for (i, count) in self.counts.iter().enumerate() {
+            let label = match BUCKETS.get(i) {
+                Some(&upper) if upper == lower => upper.to_string(),
+                Some(&upper) if lower == 0 => format!("≤{upper}"),
+                Some(&upper) => format!("{lower}-{upper}"),
+                None => format!(">{}", BUCKETS[BUCKETS.len() - 1])
+            };
+            lower = BUCKETS.get(i).map_or(lower, |&upper| upper + 1);
+            let count = count.load(Ordering::Relaxed);
+            if count > 0 {
+                buckets.push((label, count));
+            }
+        }
+        buckets
+    }
+}
+
+impl fmt::Display for DiffOpsHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(mean) = self.mean() else {
+            return write!(f, "no diffs");
+        };
+        write!(f, "{} diff(s), {mean:.1} ops avg, {} max | ops:", self.count(), self.max())?;
+        for (label, count) in self.buckets() {
+            write!(f, " {label}={count}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use std::{fmt::Write as _, io, sync::Arc};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle
+};
+use tracing::debug;
+
+use super::Metrics;
+
+/// Session counters in the Prometheus text exposition format
+pub fn render_prometheus(metrics: &Metrics) -> String {
+    let mut out = String::new();
+    let mut family = |name: &str, kind: &str, help: &str, samples: Vec<(String, String)>| {
+        let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} {kind}");
+        for (labels, value) in samples {
+            let _ = writeln!(out, "{name}{labels} {value}");
+        }
+    };
+
+    family(
+        "pica_messages_total",
+        "counter",
+        "Messages received per side",
+        vec![
+            (r#"{side="left"}"#.to_string(), metrics.left.messages().to_string()),
+            (r#"{side="right"}"#.to_string(), metrics.right.messages().to_string()),
+        ]
+    );
+    family(
+        "pica_bytes_total",
+        "counter",
+        "Bytes received per side",
+        vec![
+            (r#"{side="left"}"#.to_string(), metrics.left.bytes().to_string()),
+            (r#"{side="right"}"#.to_string(), metrics.right.bytes().to_string()),
+        ]
+    );
+    family(
+        "pica_diffs_total",
+        "counter",
+        "Compared pairs whose payloads differed",
+        vec![(String::new(), metrics.diffs().to_string())]
+    );
+
+    let ops = metrics.diff_ops();
+    let mut buckets: Vec<(String, String)> = ops
+        .cumulative()
+        .into_iter()
+        .map(|(bound, count)| {
+            let le = bound.map_or_else(|| "+Inf".to_string(), |bound| bound.to_string());
+            (format!("_bucket{{le=\"{le}\"}}"), count.to_string())
+        })
+        .collect();
+    buckets.push(("_sum".to_string(), ops.total().to_string()));
+    buckets.push(("_count".to_string(), ops.count().to_string()));
+    family("pica_diff_ops", "histogram", "Patch operations per differing pair", buckets);
+
+    family(
+        "pica_reconnects_total",
+        "counter",
+        "Reconnects per source",
+        metrics
+            .reconnects_by_source()
+            .into_iter()
+            .map(|(source, count)| (format!("{{source=\"{}\"}}", escape(&source)), count.to_string()))
+            .collect()
+    );
+    family(
+        "pica_alignment_lag_milliseconds",
+        "gauge",
+        "How far right lagged left on the most recently aligned pair",
+        metrics.current_lag().map(|lag| (String::new(), lag.to_string())).into_iter().collect()
+    );
+    out
+}
+
+/// Bind `addr` and serve `render_prometheus` to every HTTP request on it, on the current runtime,
+/// until the returned task is aborted
+pub async fn serve_prometheus(addr: &str, metrics: Arc<Metrics>) -> io::Result<JoinHandle<()>> {
+    let listener = TcpListener::bind(addr).await?;
+    Ok(tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(respond(stream, Arc::clone(&metrics)));
+                }
+                Err(err) => debug!("metrics accept failed: {err}")
+            }
+        }
+    }))
+}
+
+/// Answer one request: the metrics for `GET /metrics` (or `/`), 404 for other paths
+async fn respond(mut stream: TcpStream, metrics: Arc<Metrics>) {
+    // The request line is all that's needed; headers and body are ignored
+    let mut request = [0; 1024];
+    let Ok(read) = stream.read(&mut request).await else {
+        return;
+    };
+    let request = String::from_utf8_lossy(&request[..read]);
+    let mut words = request.split_ascii_whitespace();
+    let response = match (words.next(), words.next()) {
+        (Some("GET"), Some("/metrics" | "/")) => {
+            let body = render_prometheus(&metrics);
+            let headers = "Content-Type: text/plain; version=0.0.4\r\nConnection: close";
+            format!("HTTP/1.1 200 OK\r\n{headers}\r\nContent-Length: {}\r\n\r\n{body}", body.len())
+        }
+        _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    };
+    if let Err(err) = stream.write_all(response.as_bytes()).await {
+        debug!("metrics response failed: {err}");
+    }
+    let _ = stream.shutdown().await;
+}
+
+/// Escape a label value (backslash, quote, newline)
+fn escape(value: &str) -> String {
+    value.replace('\\', r"\\").replace('"', r#"\""#).replace('\n', r"\n")
+}
@@ -0,0 +1,67 @@
+use std::{io, net::UdpSocket, sync::Mutex};
+
+use tracing::debug;
+
+/// Keep packets under a typical MTU so they aren't fragmented
+const MAX_PACKET: usize = 1432;
+
+/// Batching StatsD client over UDP: metric lines are buffered and sent as one packet when the
+/// buffer is full or on `flush`
+#[derive(Debug)]
+pub struct StatsdClient {
+    socket: UdpSocket,
+    prefix: String,
+    buffer: Mutex<String>
+}
+
+impl StatsdClient {
+    /// Connect a UDP socket to `addr` (e.g., "localhost:8125"); metric names get `prefix.` in front
+    pub fn connect(addr: &str, prefix: &str) -> io::Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect(addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self { socket, prefix: prefix.to_string(), buffer: Mutex::new(String::new()) })
+    }
+
+    pub fn count(&self, name: &str, value: u64) {
+        self.push(format!("{}.{}:{value}|c", self.prefix, metric_name(name)));
+    }
+
+    /// Record a duration in milliseconds
+    pub fn timing(&self, name: &str, millis: f64) {
+        self.push(format!("{}.{}:{millis:.3}|ms", self.prefix, metric_name(name)));
+    }
+
+    /// Send whatever is buffered
+    pub fn flush(&self) {
+        let mut buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+        self.send(&mut buffer);
+    }
+
+    fn push(&self, line: String) {
+        let mut buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+        if !buffer.is_empty() && buffer.len() + 1 + line.len() > MAX_PACKET {
+            self.send(&mut buffer);
+        }
+        if !buffer.is_empty() {
+            buffer.push('\n');
+        }
+        buffer.push_str(&line);
+    }
+
+    fn send(&self, buffer: &mut String) {
+        if buffer.is_empty() {
+            return;
+        }
+        // StatsD is fire-and-forget: a dropped packet must never slow down tracking
+        if let Err(err) = self.socket.send(buffer.as_bytes()) {
+            debug!("statsd send failed: {err}");
+        }
+        buffer.clear();
+    }
+}
+
+/// Make an arbitrary key (e.g., an alignment key) safe as a StatsD metric name segment
+pub fn metric_name(raw: &str) -> String {
+    raw.chars().map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') { c } else { '_' }).collect()
+}
@@ -0,0 +1,75 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering}
+    }
+};
+
+use serde_json::Value as JsonValue;
+
+/// Message and byte counters for one stream
+#[derive(Debug, Default)]
+pub struct Throughput {
+    messages: AtomicU64,
+    bytes:    AtomicU64,
+    /// How often each alignment key was seen
+    keys:     Mutex<HashMap<String, u64>>
+}
+
+impl Throughput {
+    pub fn record(&self, bytes: usize) {
+        self.messages.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Record a message, sizing it by its compact JSON serialization
+    pub fn record_value(&self, value: &JsonValue) {
+        self.record(serialized_len(value));
+    }
+
+    /// Count a message carrying this alignment key
+    pub fn record_key(&self, key: &str) {
+        let mut keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+        match keys.get_mut(key) {
+            Some(count) => *count += 1,
+            None => {
+                keys.insert(key.to_string(), 1);
+            }
+        }
+    }
+
+    /// Whether a message with this alignment key has been seen
+    pub fn has_seen_key(&self, key: &str) -> bool {
+        self.keys.lock().unwrap_or_else(|e| e.into_inner()).contains_key(key)
+    }
+
+    pub fn messages(&self) -> u64 {
+        self.messages.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+}
+
+/// Length of the compact JSON serialization, computed without allocating the string
+pub fn serialized_len(value: &JsonValue) -> usize {
+    struct Counter(usize);
+
+    impl Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    let _ = serde_json::to_writer(&mut counter, value);
+    counter.0
+}
@@ -1,6 +1,67 @@
+use anstream::println;
+use owo_colors::OwoColorize;
 use serde_json::Value as JsonValue;
 
+use crate::domain::DiffResult;
+
 /// Port for diffing two JSON values and producing output
 pub trait Differ: Send + Sync {
-    fn print_diff(&self, left_label: &str, right_label: &str, left: &JsonValue, right: &JsonValue);
+    /// The changes between the two sides, after any normalization the differ applies
+    fn compute_diff(&self, left: &JsonValue, right: &JsonValue) -> DiffResult {
+        DiffResult::between(left, right)
+    }
+
+    /// Print the diff
+    fn print_diff(&self, left_label: &str, right_label: &str, left: &JsonValue, right: &JsonValue) {
+        self.print_result(left_label, right_label, left, right, &self.compute_diff(left, right));
+    }
+
+    /// Print `result`, this differ's `compute_diff` of the two sides, so callers that also count
+    /// the changes diff each pair once; by default its patch operations
+    fn print_result(
+        &self,
+        left_label: &str,
+        right_label: &str,
+        _left: &JsonValue,
+        _right: &JsonValue,
+        result: &DiffResult
+    ) {
+        if result.identical {
+            tracing::info!("states are identical");
+            return;
+        }
+        println!("\n{} {left_label} -> {right_label} ({} ops)", "diff".bold(), result.ops_count);
+        println!("{}", serde_json::to_string_pretty(&result.patch).unwrap_or_else(|_| "[]".to_string()));
+    }
+
+    /// Number of changes `print_diff` would report; 0 means the two sides count as identical
+    fn count_changes(&self, left: &JsonValue, right: &JsonValue) -> usize {
+        self.compute_diff(left, right).ops_count
+    }
+}
+
+/// Allows a differ chosen at runtime (`Box<dyn Differ>`) to be used wherever a `Differ` is expected
+impl<D: Differ + ?Sized> Differ for Box<D> {
+    fn compute_diff(&self, left: &JsonValue, right: &JsonValue) -> DiffResult {
+        (**self).compute_diff(left, right)
+    }
+
+    fn print_diff(&self, left_label: &str, right_label: &str, left: &JsonValue, right: &JsonValue) {
+        (**self).print_diff(left_label, right_label, left, right)
+    }
+
+    fn print_result(
+        &self,
+        left_label: &str,
+        right_label: &str,
+        left: &JsonValue,
+        right: &JsonValue,
+        result: &DiffResult
+    ) {
+        (**self).print_result(left_label, right_label, left, right, result)
+    }
+
+    fn count_changes(&self, left: &JsonValue, right: &JsonValue) -> usize {
+        (**self).count_changes(left, right)
+    }
 }
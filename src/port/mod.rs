@@ -1,7 +1,9 @@
 mod differ;
 mod parser;
+mod reporter;
 mod source;
 
 pub use differ::*;
 pub use parser::*;
+pub use reporter::*;
 pub use source::*;
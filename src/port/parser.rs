@@ -1,10 +1,51 @@
+use std::{
+    collections::{HashMap, VecDeque, hash_map::DefaultHasher},
+    hash::{Hash, Hasher},
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering}
+    }
+};
+
+use regex_automata::meta::Regex;
 use serde_json::Value as JsonValue;
+use tracing::info;
+
+use crate::domain::{TrackerError, canonicalize, navigate, parse_dotted, remove_pointer};
 
 /// Port for extracting alignment keys from JSON states
 pub trait AlignmentKeyExtractor: Send + Sync {
     /// Extract an alignment key from a JSON state (e.g., message type, phase, etc.)
     /// Returns None if no alignment key can be extracted
     fn extract_key(&self, state: &JsonValue) -> Option<String>;
+
+    /// The key of a state from one side; the same as `extract_key` unless keys depend on which side
+    /// a state came from (e.g., `SequenceExtractor`)
+    fn extract_side_key(&self, is_left: bool, state: &JsonValue) -> Option<String> {
+        let _ = is_left;
+        self.extract_key(state)
+    }
+
+    /// Where keys are read from, for messages about it (e.g., "`message.phase`")
+    fn describe(&self) -> String {
+        "the alignment extractor".to_string()
+    }
+}
+
+/// Allows an extractor chosen at runtime (`Box<dyn AlignmentKeyExtractor>`) to be used wherever an
+/// `AlignmentKeyExtractor` is expected
+impl<E: AlignmentKeyExtractor + ?Sized> AlignmentKeyExtractor for Box<E> {
+    fn extract_key(&self, state: &JsonValue) -> Option<String> {
+        (**self).extract_key(state)
+    }
+
+    fn extract_side_key(&self, is_left: bool, state: &JsonValue) -> Option<String> {
+        (**self).extract_side_key(is_left, state)
+    }
+
+    fn describe(&self) -> String {
+        (**self).describe()
+    }
 }
 
 /// Extractor that uses a JSON path to extract the alignment key
@@ -13,64 +54,368 @@ pub struct JsonPathExtractor {
 }
 
 impl JsonPathExtractor {
-    /// Create extractor with a dot-separated path (e.g., "message.type" or "event_type")
+    /// Create extractor with a dot-separated path (e.g., "message.type" or "event_type"); numeric
+    /// segments index into arrays ("events.0.type" or "events[0].type"), and an index past the end
+    /// yields no key
     pub fn new(path: &str) -> Self {
-        Self { field_path: path.split('.').map(|s| s.to_string()).collect() }
+        Self { field_path: parse_dotted(path) }
     }
 }
 
 impl AlignmentKeyExtractor for JsonPathExtractor {
     fn extract_key(&self, state: &JsonValue) -> Option<String> {
-        let mut current = state;
-
-        // Navigate through the path
-        for field in &self.field_path {
-            current = current.get(field)?;
-        }
-
         // Extract the final value as a string
-        match current {
+        match navigate(state, &self.field_path)? {
             JsonValue::String(s) => Some(s.clone()),
             JsonValue::Number(n) => Some(n.to_string()),
             JsonValue::Bool(b) => Some(b.to_string()),
             _ => None
         }
     }
+
+    fn describe(&self) -> String {
+        format!("`{}`", self.field_path.join("."))
+    }
+}
+
+/// Extractor that joins the values of several fields into one key (e.g., "order.created|42" from
+/// `event_type` and `user_id`), for streams where no single field is unique. A state missing any of
+/// the fields has no key.
+pub struct CompositeExtractor {
+    parts:     Vec<JsonPathExtractor>,
+    separator: String
+}
+
+impl CompositeExtractor {
+    /// Join the values at these dot-separated paths, in the order given, with "|"
+    pub fn new<S: AsRef<str>>(paths: &[S]) -> Self {
+        let parts = paths.iter().map(|path| JsonPathExtractor::new(path.as_ref())).collect();
+        Self { parts, separator: "|".to_string() }
+    }
+
+    pub fn with_separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+}
+
+impl AlignmentKeyExtractor for CompositeExtractor {
+    fn extract_key(&self, state: &JsonValue) -> Option<String> {
+        let values = self.parts.iter().map(|part| part.extract_key(state)).collect::<Option<Vec<_>>>()?;
+        Some(values.join(&self.separator))
+    }
+
+    fn describe(&self) -> String {
+        self.parts.iter().map(|part| part.describe()).collect::<Vec<_>>().join(" + ")
+    }
+}
+
+/// Extractor for an `--align-by` value: comma-separated paths (e.g., "event_type,user_id") form a
+/// `CompositeExtractor`, a single path a `JsonPathExtractor`
+pub fn field_extractor(spec: &str) -> Box<dyn AlignmentKeyExtractor> {
+    let paths: Vec<&str> = spec.split(',').map(str::trim).filter(|path| !path.is_empty()).collect();
+    match paths.as_slice() {
+        [] => Box::new(JsonPathExtractor::new(spec)),
+        [path] => Box::new(JsonPathExtractor::new(path)),
+        _ => Box::new(CompositeExtractor::new(&paths))
+    }
+}
+
+/// Extractor that pulls the key out of a larger string with a regex, e.g. "LOBBY" from
+/// `"msg": "phase=LOBBY ready"` with `phase=(\w+)`. The key is the first capture group that
+/// participated in the match (named or not), or the whole match when no group did (e.g., for a
+/// pattern without groups).
+pub struct RegexExtractor {
+    field:   JsonPathExtractor,
+    pattern: Regex
+}
+
+impl RegexExtractor {
+    /// Match `pattern` against the value at `path` (numbers and booleans are matched as text)
+    pub fn new(path: &str, pattern: &str) -> Result<Self, TrackerError> {
+        let pattern = Regex::new(pattern).map_err(|err| {
+            let reason = err.syntax_error().map_or_else(|| err.to_string(), |syntax| syntax.to_string());
+            TrackerError::Config(format!("invalid --align-regex: {reason}"))
+        })?;
+        Ok(Self { field: JsonPathExtractor::new(path), pattern })
+    }
+}
+
+impl AlignmentKeyExtractor for RegexExtractor {
+    fn extract_key(&self, state: &JsonValue) -> Option<String> {
+        let text = self.field.extract_key(state)?;
+        let mut captures = self.pattern.create_captures();
+        self.pattern.captures(text.as_str(), &mut captures);
+        if !captures.is_match() {
+            return None;
+        }
+
+        let span = (1..captures.group_len())
+            .find_map(|group| captures.get_group(group))
+            .or_else(|| captures.get_match().map(|m| m.span()))?;
+        Some(text[span.range()].to_string())
+    }
+
+    fn describe(&self) -> String {
+        format!("{} matched against a regex", self.field.describe())
+    }
 }
 
-/// Extractor that tries multiple common field names
+/// Extractor that tries multiple common field names, the first one with a scalar value winning.
+/// The field chosen for the first key is logged, so it's clear what the streams aligned on.
 pub struct AutoExtractor {
-    common_fields: Vec<String>
+    common_fields: Vec<String>,
+    /// Whether the chosen field was logged yet
+    announced:     AtomicBool
+}
+
+impl AutoExtractor {
+    /// Try these top-level fields in order of priority
+    pub fn new<S: Into<String>>(fields: impl IntoIterator<Item = S>) -> Self {
+        Self { common_fields: fields.into_iter().map(Into::into).collect(), announced: AtomicBool::new(false) }
+    }
+
+    /// The candidate fields, in order of priority
+    pub fn fields(&self) -> &[String] {
+        &self.common_fields
+    }
+
+    /// The matched field's name and its value as the key
+    pub fn extract_key_with_field(&self, state: &JsonValue) -> Option<(String, String)> {
+        self.common_fields.iter().find_map(|field| {
+            let key = match state.get(field)? {
+                JsonValue::String(s) => s.clone(),
+                JsonValue::Number(n) => n.to_string(),
+                JsonValue::Bool(b) => b.to_string(),
+                _ => return None
+            };
+            Some((field.clone(), key))
+        })
+    }
 }
 
 impl Default for AutoExtractor {
     fn default() -> Self {
-        Self {
-            common_fields: vec![
-                "type".to_string(),
-                "event_type".to_string(),
-                "message_type".to_string(),
-                "phase".to_string(),
-                "state".to_string(),
-                "action".to_string(),
-                "args".to_string(),
-            ]
-        }
+        Self::new(["type", "event_type", "message_type", "phase", "state", "action", "args"])
     }
 }
 
 impl AlignmentKeyExtractor for AutoExtractor {
     fn extract_key(&self, state: &JsonValue) -> Option<String> {
-        for field in &self.common_fields {
-            if let Some(value) = state.get(field) {
-                match value {
-                    JsonValue::String(s) => return Some(s.clone()),
-                    JsonValue::Number(n) => return Some(n.to_string()),
-                    JsonValue::Bool(b) => return Some(b.to_string()),
-                    _ => continue
-                }
-            }
+        let (field, key) = self.extract_key_with_field(state)?;
+        if !self.announced.swap(true, Ordering::Relaxed) {
+            info!("🔎 auto-aligning on field `{field}`");
         }
+        Some(key)
+    }
+
+    fn describe(&self) -> String {
+        format!("any of `{}`", self.common_fields.join("`, `"))
+    }
+}
+
+/// Extractor that keys each state by a short hash of its whole payload, so identical payloads
+/// align even without a key field. Fields listed in `ignore` (JSON Pointers, e.g. "/timestamp")
+/// are removed before hashing.
+pub struct HashExtractor {
+    ignore: Vec<String>
+}
+
+impl HashExtractor {
+    pub fn new(ignore: Vec<String>) -> Self {
+        Self { ignore }
+    }
+}
+
+impl AlignmentKeyExtractor for HashExtractor {
+    fn extract_key(&self, state: &JsonValue) -> Option<String> {
+        let mut projected = canonicalize(state);
+        for pointer in &self.ignore {
+            remove_pointer(&mut projected, pointer);
+        }
+
+        // FNV-1a: stable across runs and platforms, unlike `DefaultHasher`
+        let hash = serde_json::to_string(&projected)
+            .ok()?
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3));
+
+        Some(format!("{:012x}", hash >> 16))
+    }
+}
+
+/// Extractor that keys each state by its position in its own stream ("#1", "#2", ...), so the
+/// i-th states of both sides align, e.g. to compare two captures record by record. Only
+/// `extract_side_key` counts; `extract_key` has no side to count for and gives no key.
+#[derive(Debug, Default)]
+pub struct SequenceExtractor {
+    left:  AtomicUsize,
+    right: AtomicUsize
+}
+
+impl AlignmentKeyExtractor for SequenceExtractor {
+    fn extract_key(&self, _state: &JsonValue) -> Option<String> {
         None
     }
+
+    fn extract_side_key(&self, is_left: bool, _state: &JsonValue) -> Option<String> {
+        let counter = if is_left { &self.left } else { &self.right };
+        Some(format!("#{}", counter.fetch_add(1, Ordering::Relaxed) + 1))
+    }
+
+    fn describe(&self) -> String {
+        "the record position".to_string()
+    }
+}
+
+/// Memoizes another extractor by a hash of the whole message, for extractors that cost more than
+/// that hash (e.g., `HashExtractor`) on streams that repeat payloads (e.g., a looping replay).
+/// Holds at most `capacity` results; the oldest is evicted first.
+pub struct CachingExtractor<E: AlignmentKeyExtractor> {
+    inner:    E,
+    capacity: usize,
+    cache:    Mutex<KeyCache>
+}
+
+/// Cached keys by message hash, plus insertion order for eviction
+#[derive(Default)]
+struct KeyCache {
+    keys:  HashMap<u64, Option<String>>,
+    order: VecDeque<u64>
+}
+
+impl<E: AlignmentKeyExtractor> CachingExtractor<E> {
+    pub fn new(inner: E, capacity: usize) -> Self {
+        Self { inner, capacity, cache: Mutex::new(KeyCache::default()) }
+    }
+}
+
+impl<E: AlignmentKeyExtractor> AlignmentKeyExtractor for CachingExtractor<E> {
+    fn extract_key(&self, state: &JsonValue) -> Option<String> {
+        let mut hasher = DefaultHasher::new();
+        hash_value(state, &mut hasher);
+        let hash = hasher.finish();
+
+        if let Some(key) = self.cache.lock().unwrap_or_else(|e| e.into_inner()).keys.get(&hash) {
+            return key.clone();
+        }
+
+        let key = self.inner.extract_key(state);
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        if cache.keys.insert(hash, key.clone()).is_none() {
+            cache.order.push_back(hash);
+        }
+        while cache.order.len() > self.capacity {
+            if let Some(oldest) = cache.order.pop_front() {
+                cache.keys.remove(&oldest);
+            }
+        }
+        key
+    }
+
+    fn describe(&self) -> String {
+        self.inner.describe()
+    }
+}
+
+/// Feed a JSON value to a hasher without serializing it; each variant is tagged so that, e.g.,
+/// `"1"` and `1` hash differently
+fn hash_value(value: &JsonValue, hasher: &mut impl Hasher) {
+    match value {
+        JsonValue::Null => 0u8.hash(hasher),
+        JsonValue::Bool(b) => (1u8, b).hash(hasher),
+        JsonValue::Number(n) => (2u8, n).hash(hasher),
+        JsonValue::String(s) => (3u8, s).hash(hasher),
+        JsonValue::Array(items) => {
+            (4u8, items.len()).hash(hasher);
+            items.iter().for_each(|item| hash_value(item, hasher));
+        }
+        JsonValue::Object(map) => {
+            (5u8, map.len()).hash(hasher);
+            for (key, item) in map {
+                key.hash(hasher);
+                hash_value(item, hasher);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Reads `type`, counting how often it was asked to
+    #[derive(Default)]
+    struct CountingExtractor {
+        calls: AtomicUsize
+    }
+
+    impl AlignmentKeyExtractor for CountingExtractor {
+        fn extract_key(&self, state: &JsonValue) -> Option<String> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            state.get("type").map(ToString::to_string)
+        }
+    }
+
+    #[test]
+    fn comma_separated_paths_join_into_one_key() {
+        let state = json!({"event_type": "order.created", "user": {"id": 42}, "items": [{"sku": "x"}]});
+        assert_eq!(field_extractor("event_type, user.id").extract_key(&state), Some("order.created|42".to_string()));
+        assert_eq!(field_extractor("items[0].sku,").extract_key(&state), Some("x".to_string()));
+        assert_eq!(field_extractor("event_type,missing").extract_key(&state), None);
+        assert_eq!(
+            CompositeExtractor::new(&["user.id", "event_type"]).with_separator("/").extract_key(&state),
+            Some("42/order.created".to_string())
+        );
+    }
+
+    fn regex_key(pattern: &str, state: JsonValue) -> Option<String> {
+        RegexExtractor::new("id", pattern).unwrap().extract_key(&state)
+    }
+
+    #[test]
+    fn regex_keys_are_the_first_participating_group() {
+        assert_eq!(regex_key(r"order-(\d+)", json!({"id": "order-17-b"})), Some("17".to_string()));
+        assert_eq!(regex_key(r"(?P<kind>[a-z]+)-(\d+)", json!({"id": "bet-3"})), Some("bet".to_string()));
+        assert_eq!(regex_key(r"a(\d)|b(?P<b>\d)", json!({"id": "b7"})), Some("7".to_string()));
+    }
+
+    #[test]
+    fn regex_keys_fall_back_to_the_whole_match() {
+        assert_eq!(regex_key(r"round-\d+", json!({"id": "x round-4 y"})), Some("round-4".to_string()));
+        assert_eq!(regex_key(r"(x)?\d+", json!({"id": "r42"})), Some("42".to_string()));
+    }
+
+    #[test]
+    fn regex_keys_match_numbers_and_booleans_as_text() {
+        assert_eq!(regex_key(r"^(\d{2})", json!({"id": 12345})), Some("12".to_string()));
+        assert_eq!(regex_key(r"t(ru)e", json!({"id": true})), Some("ru".to_string()));
+    }
+
+    #[test]
+    fn regex_keys_are_none_without_a_match() {
+        assert_eq!(regex_key(r"order-(\d+)", json!({"id": "refund-17"})), None);
+        assert_eq!(regex_key(r"order-(\d+)", json!({"other": "order-17"})), None);
+        assert!(matches!(RegexExtractor::new("id", "(unclosed"), Err(TrackerError::Config(_))));
+    }
+
+    #[test]
+    fn caches_keys_by_message_and_evicts_the_oldest() {
+        let extractor = CachingExtractor::new(CountingExtractor::default(), 2);
+        let calls = || extractor.inner.calls.load(Ordering::Relaxed);
+
+        assert_eq!(extractor.extract_key(&json!({"type": 1})), Some("1".to_string()));
+        assert_eq!(extractor.extract_key(&json!({"type": 1})), Some("1".to_string()));
+        assert_eq!(calls(), 1);
+
+        assert_eq!(extractor.extract_key(&json!({"type": "1"})), Some("\"1\"".to_string()));
+        assert_eq!(extractor.extract_key(&json!({})), None);
+        assert_eq!(extractor.extract_key(&json!({})), None);
+        assert_eq!(calls(), 3);
+
+        extractor.extract_key(&json!({"type": 1}));
+        assert_eq!(calls(), 4);
+    }
 }
@@ -0,0 +1,12 @@
+use crate::domain::{PathRollup, RunMetadata, State};
+
+/// Port for collecting tracked states and writing them out as a report
+pub trait Reporter: Send {
+    fn add_left(&mut self, state: State);
+    fn add_right(&mut self, state: State);
+    /// Attach the round's changed-path summary; reporters without a place for it ignore it
+    fn set_rollup(&mut self, _rollup: PathRollup) {}
+    /// Attach how the session was run (version, arguments, sources)
+    fn set_metadata(&mut self, _metadata: RunMetadata) {}
+    fn generate(&self, output_path: &str) -> std::io::Result<()>;
+}
@@ -1,8 +1,32 @@
 use serde_json::Value;
-use tokio::sync::mpsc::Receiver;
+use tokio::sync::mpsc::{Receiver, UnboundedSender};
+
+use crate::domain::SourceError;
+
+/// Channel sources report recoverable errors on
+pub type SourceErrors = UnboundedSender<SourceError>;
 
 /// Abstraction for a source of JSON state updates.
 /// Implementations should spawn an internal task and return a Receiver of states.
 pub trait StateSource: Send + Sync {
     fn spawn(&self) -> Receiver<Value>;
+
+    /// Like `spawn`, additionally reporting errors the source recovers from on `errors`. Sources
+    /// that can't fail that way just spawn.
+    fn spawn_with_errors(&self, errors: SourceErrors) -> Receiver<Value> {
+        drop(errors);
+        self.spawn()
+    }
+}
+
+/// Allows a source chosen at runtime (`Box<dyn StateSource>`) to be used wherever a `StateSource`
+/// is expected
+impl<S: StateSource + ?Sized> StateSource for Box<S> {
+    fn spawn(&self) -> Receiver<Value> {
+        (**self).spawn()
+    }
+
+    fn spawn_with_errors(&self, errors: SourceErrors) -> Receiver<Value> {
+        (**self).spawn_with_errors(errors)
+    }
 }
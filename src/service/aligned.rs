@@ -1,29 +1,144 @@
-use std::io::Write;
+use std::{
+    borrow::Cow,
+    cell::OnceCell,
+    collections::{HashMap, HashSet},
+    fs,
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+    pin::pin,
+    sync::{Arc, Mutex},
+    time::Duration
+};
 
+use anstream::{print, println};
 use owo_colors::OwoColorize;
-use tracing::info;
+use serde_json::Value as JsonValue;
+use tokio::{
+    sync::mpsc,
+    time::{Instant, sleep_until}
+};
+use tracing::{debug, info, warn};
 
+use super::{
+    LimitMode,
+    health::SourceHealth,
+    key_watch::MissingKeyWatch,
+    limit::MessageLimit,
+    report_pool::ReportPool,
+    round::RoundState,
+    spill::SpillingStateBuffer,
+    swap::SwapDetector,
+    throttle::DiffThrottle,
+    tracker::{coalesce_latest, next_tick, start_ticker}
+};
 use crate::{
-    adapter::{HtmlReporter, TimelineVisualizer},
-    domain::{State, StateBuffer, TrackerError},
-    port::{AlignmentKeyExtractor, Differ, StateSource}
+    adapter::{
+        CsvReporter, DEFAULT_VISUAL_HISTORY, DEFAULT_VISUAL_WIDTH, HtmlReporter, JsonReporter, Theme,
+        TimelineVisualizer, split_gz, terminal_width
+    },
+    domain::{
+        DiffResult, KeyComparison, MatchStrategy, MissingKeys, Normalize, PathRollup, RunMetadata, State,
+        TimelineAnchor, TrackerError, TrackerOutcome, find_type_change, match_states, save_states, similarity
+    },
+    metric::{AlignmentStatus, Metrics, describe_lead, lag_millis},
+    port::{AlignmentKeyExtractor, Differ, Reporter, StateSource}
 };
 
 pub struct AlignedTracker<L: StateSource, R: StateSource, D: Differ, E: AlignmentKeyExtractor> {
-    left:             L,
-    right:            R,
-    differ:           D,
-    extractor:        E,
+    left:                L,
+    right:               R,
+    /// Shared with the HTML reports, which diff the pairs they render
+    differ:              Arc<D>,
+    extractor:           E,
     /// Optional signal key/value that marks end of a round (e.g., "type=GameCleared")
-    round_end_signal: Option<String>,
+    round_end_signal:    Option<String>,
+    /// Where the round-end signal is read from, when not the alignment key
+    round_end_extractor: Option<Box<dyn AlignmentKeyExtractor>>,
+    /// What ends a round: the signal above, or a fixed time window
+    window:              WindowMode,
     /// Enable visual timeline rendering
-    visual:           bool,
-    /// Optional output file for HTML report
-    report_output:    Option<String>,
+    visual:              bool,
+    /// Rows kept per side in the visual timeline
+    visual_history:      usize,
+    /// Visual frame width; the terminal's when unset
+    visual_width:        Option<usize>,
+    /// Report output files; the format is picked from each extension (`.json` or HTML)
+    report_outputs:      Vec<String>,
     /// Enable pretty diff output
-    pretty_diff:      bool,
+    pretty_diff:         bool,
     /// Maximum number of rounds to track (None = infinite)
-    max_rounds:       Option<usize>
+    max_rounds:          Option<usize>,
+    /// Keep full payloads in reports only for mismatched/missing states
+    selective_buffer:    bool,
+    /// Only diff aligned states whose key is in this list (empty = diff every aligned key)
+    diff_on:             Vec<String>,
+    /// Skip to the newest queued message per side (alignment mode only; rounds need every state)
+    realtime:            bool,
+    /// Differs configured for specific alignment keys (e.g., from a policy file)
+    key_differs:         HashMap<String, Arc<dyn Differ>>,
+    /// Shared session counters (readable from outside while the tracker runs)
+    metrics:             Arc<Metrics>,
+    /// Print a throughput line at this interval
+    stats_interval:      Option<Duration>,
+    /// How states are paired when a round completes
+    match_strategy:      MatchStrategy,
+    /// How HTML report timelines interleave the two sides
+    timeline_anchor:     TimelineAnchor,
+    /// Left/right colors for the visual timeline
+    theme:               Theme,
+    /// Stop with an error the first time an aligned pair has a path that changed JSON type
+    fail_on_type_change: bool,
+    /// After both sides signal round end, keep absorbing messages this long before comparing
+    round_grace:         Option<Duration>,
+    /// Stop with an error once a source reports this many errors in a row
+    max_source_errors:   Option<usize>,
+    /// Stop reading a side after this many states
+    message_limit:       Option<usize>,
+    /// Whether the limit ends the session once both sides reach it or either does
+    limit_mode:          LimitMode,
+    /// Round reports written concurrently in the background
+    report_workers:      usize,
+    /// How the session was run, attached to every report
+    metadata:            Option<RunMetadata>,
+    /// Compare only the first state of each alignment key per side when a round completes
+    first_per_key:       bool,
+    /// When one stream closes, keep reading the other until it closes too, then compare what's left
+    drain_on_close:      bool,
+    /// Without a round-end signal, compare both streams in full as one round once they close
+    whole_stream:        bool,
+    /// Alignment keys each round (and the whole session) must contain on both sides
+    required_keys:       Vec<String>,
+    /// Warn when no pair has aligned for this long while the sides are out of sync
+    alignment_timeout:   Option<Duration>,
+    /// Fail with `TrackerError::AlignmentStalled` instead of warning when the timeout passes
+    strict_alignment:    bool,
+    /// In visual mode, stop at the first divergent row until Enter is pressed
+    freeze_on_diff:      bool,
+    /// Directory for round states beyond the in-memory window (rounds only)
+    spill_dir:           Option<PathBuf>,
+    /// Directory each completed round's buffers are saved to as NDJSON
+    dump_dir:            Option<PathBuf>,
+    /// Gzip the round dumps
+    dump_gzip:           bool,
+    /// Whether two alignment keys count as the same (exact, or numeric within a tolerance)
+    key_comparison:      KeyComparison,
+    /// Round payload numbers in reports to this many significant digits
+    display_precision:   Option<u32>,
+    /// Collapse a diff identical to the last one shown for the same key (when enabled)
+    diff_throttle:       Option<Mutex<DiffThrottle>>,
+    /// What the session found so far; readable after `start` returns or is cancelled
+    outcome:             Mutex<TrackerOutcome>
+}
+
+/// How the stream is cut into rounds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WindowMode {
+    /// Rounds end when both sides see the round-end signal
+    #[default]
+    Signal,
+    /// Whatever both sides buffered is compared every period, for streams that never signal a
+    /// round boundary
+    Duration(Duration)
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -33,33 +148,149 @@ enum OutputMode {
     Logs        // Priority 3: default (structured logs)
 }
 
-impl<L: StateSource, R: StateSource, D: Differ, E: AlignmentKeyExtractor> AlignedTracker<L, R, D, E> {
+impl<L: StateSource, R: StateSource, D: Differ + 'static, E: AlignmentKeyExtractor> AlignedTracker<L, R, D, E> {
     pub fn new(left: L, right: R, differ: D, extractor: E) -> Self {
         Self {
             left,
             right,
-            differ,
+            differ: Arc::new(differ),
             extractor,
             round_end_signal: None,
+            round_end_extractor: None,
+            window: WindowMode::default(),
             visual: false,
-            report_output: None,
+            visual_history: DEFAULT_VISUAL_HISTORY,
+            visual_width: None,
+            report_outputs: Vec::new(),
             pretty_diff: false,
-            max_rounds: None
+            max_rounds: None,
+            selective_buffer: false,
+            diff_on: Vec::new(),
+            realtime: false,
+            key_differs: HashMap::new(),
+            metrics: Arc::new(Metrics::new()),
+            stats_interval: None,
+            match_strategy: MatchStrategy::default(),
+            timeline_anchor: TimelineAnchor::default(),
+            theme: Theme::default(),
+            fail_on_type_change: false,
+            round_grace: None,
+            max_source_errors: None,
+            message_limit: None,
+            limit_mode: LimitMode::default(),
+            report_workers: 2,
+            metadata: None,
+            first_per_key: false,
+            drain_on_close: false,
+            whole_stream: false,
+            required_keys: Vec::new(),
+            alignment_timeout: None,
+            strict_alignment: false,
+            freeze_on_diff: false,
+            spill_dir: None,
+            dump_dir: None,
+            dump_gzip: false,
+            key_comparison: KeyComparison::default(),
+            display_precision: None,
+            diff_throttle: None,
+            outcome: Mutex::default()
         }
     }
 
+    /// Write at most `workers` round reports at the same time (default 2); with all of them busy,
+    /// the next round waits for one to finish
+    pub fn with_report_workers(mut self, workers: usize) -> Self {
+        self.report_workers = workers;
+        self
+    }
+
+    /// Ignore repeats of a key within a round: only the first state of each key on each side is
+    /// compared (the report still shows every state)
+    pub fn with_first_per_key(mut self, enabled: bool) -> Self {
+        self.first_per_key = enabled;
+        self
+    }
+
+    /// Fail with `TrackerError::MissingKeys` when a completed round lacks one of these keys on
+    /// either side
+    pub fn with_required_keys(mut self, keys: Vec<String>) -> Self {
+        self.required_keys = keys;
+        self
+    }
+
+    pub fn required_keys(&self) -> &[String] {
+        &self.required_keys
+    }
+
+    /// Keep consuming the still-open stream after the other one closes; once both are closed the
+    /// unfinished round is compared and reported as a final one
+    pub fn with_drain_on_close(mut self, enabled: bool) -> Self {
+        self.drain_on_close = enabled;
+        self
+    }
+
+    /// Treat each stream as a single round, compared in full once both have closed (e.g., recorded
+    /// captures, which have no round-end signal). A round-end signal, when set, takes precedence.
+    /// Without a spill directory the whole streams are kept in memory.
+    pub fn with_whole_stream_round(mut self, enabled: bool) -> Self {
+        self.whole_stream = enabled;
+        self
+    }
+
+    /// Record how the session was run (version, arguments, sources) in every report
+    pub fn with_metadata(mut self, metadata: RunMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// The key that ends a round. A signal with `*` or `?` wildcards also works as a glob (e.g.,
+    /// "round.*.completed"); an exact match is tried first, so keys that literally contain them
+    /// still end the round.
     pub fn with_round_end_signal(mut self, signal: String) -> Self {
         self.round_end_signal = Some(signal);
         self
     }
 
+    /// The value that ends a round, matched against `with_round_end_extractor`'s output when one is
+    /// set and against the alignment key otherwise (same as `with_round_end_signal`)
+    pub fn with_round_end_value(self, value: String) -> Self {
+        self.with_round_end_signal(value)
+    }
+
+    /// Detect round ends with this extractor instead of the alignment one, e.g. align by
+    /// `event_type` but end a round when `data.status` is "final"
+    pub fn with_round_end_extractor<X: AlignmentKeyExtractor + 'static>(mut self, extractor: X) -> Self {
+        self.round_end_extractor = Some(Box::new(extractor));
+        self
+    }
+
+    /// Cut rounds by signal (default) or into fixed time windows; a window flushes and compares
+    /// both buffers whenever it elapses, skipping windows in which nothing arrived
+    pub fn with_window_mode(mut self, window: WindowMode) -> Self {
+        self.window = window;
+        self
+    }
+
     pub fn with_visual(mut self, enabled: bool) -> Self {
         self.visual = enabled;
         self
     }
 
+    /// Draw the visual timeline this many columns wide instead of the terminal's width
+    pub fn with_visual_width(mut self, width: usize) -> Self {
+        self.visual_width = Some(width);
+        self
+    }
+
+    /// Keep this many rows per side in the visual timeline (15 by default)
+    pub fn with_visual_history(mut self, rows: usize) -> Self {
+        self.visual_history = rows;
+        self
+    }
+
+    /// Add a report output (may be called several times to emit several reports)
     pub fn with_report_output(mut self, path: String) -> Self {
-        self.report_output = Some(path);
+        self.report_outputs.push(path);
         self
     }
 
@@ -73,6 +304,242 @@ impl<L: StateSource, R: StateSource, D: Differ, E: AlignmentKeyExtractor> Aligne
         self
     }
 
+    pub fn with_selective_buffering(mut self, enabled: bool) -> Self {
+        self.selective_buffer = enabled;
+        self
+    }
+
+    /// Restrict diffing to aligned states with one of these keys; other keys are still tracked for
+    /// the timeline and report
+    pub fn with_diff_on(mut self, keys: Vec<String>) -> Self {
+        self.diff_on = keys;
+        self
+    }
+
+    pub fn with_realtime(mut self, enabled: bool) -> Self {
+        self.realtime = enabled;
+        self
+    }
+
+    /// Use a dedicated differ for states aligned on `key` instead of the default one
+    pub fn with_key_differ(mut self, key: String, differ: Box<dyn Differ>) -> Self {
+        self.key_differs.insert(key, Arc::from(differ));
+        self
+    }
+
+    pub fn with_stats_interval(mut self, interval: Duration) -> Self {
+        self.stats_interval = Some(interval);
+        self
+    }
+
+    /// Print a diff only when it differs from the last one shown for the same key; repeats are
+    /// collapsed into a "(same diff as before, Nx)" line
+    pub fn with_dedup_diffs(mut self, enabled: bool) -> Self {
+        self.diff_throttle = enabled.then(Mutex::default);
+        self
+    }
+
+    /// Write report payloads with numbers rounded to `digits` significant digits (display only)
+    pub fn with_display_precision(mut self, digits: u32) -> Self {
+        self.display_precision = Some(digits);
+        self
+    }
+
+    /// Order report timelines by timestamp or by one side's arrival order
+    pub fn with_timeline_anchor(mut self, anchor: TimelineAnchor) -> Self {
+        self.timeline_anchor = anchor;
+        self
+    }
+
+    /// Align keys that both parse as numbers when they differ by at most `tolerance` (e.g., "1.23"
+    /// and "1.2300001"); other keys must still be equal
+    pub fn with_key_tolerance(mut self, tolerance: f64) -> Self {
+        self.key_comparison = self.key_comparison.with_tolerance(tolerance);
+        self
+    }
+
+    /// Lowercase and/or trim alignment keys before comparing them (in alignment, round pairing,
+    /// round-end detection and per-round required keys); reports keep the keys as received
+    pub fn with_key_normalization(mut self, normalize: Normalize) -> Self {
+        self.key_comparison = self.key_comparison.with_normalization(normalize);
+        self
+    }
+
+    pub fn with_match_strategy(mut self, strategy: MatchStrategy) -> Self {
+        self.match_strategy = strategy;
+        self
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn with_fail_on_type_change(mut self, enabled: bool) -> Self {
+        self.fail_on_type_change = enabled;
+        self
+    }
+
+    /// Wait this long after both sides complete a round so trailing in-flight messages still count
+    /// toward it
+    pub fn with_round_grace(mut self, grace: Duration) -> Self {
+        self.round_grace = Some(grace);
+        self
+    }
+
+    /// Without round tracking, warn when one side gets ahead and no pair aligns for `timeout`,
+    /// naming the side that's behind and the last key of each side; a zero timeout is ignored
+    pub fn with_alignment_timeout(mut self, timeout: Duration) -> Self {
+        self.alignment_timeout = Some(timeout).filter(|timeout| !timeout.is_zero());
+        self
+    }
+
+    /// Fail with `TrackerError::AlignmentStalled` instead of warning when the alignment timeout
+    /// passes
+    pub fn with_strict_alignment(mut self, enabled: bool) -> Self {
+        self.strict_alignment = enabled;
+        self
+    }
+
+    /// Keep only the newest states of a round in memory and append older ones to a file in `dir`,
+    /// read back when the round is compared. Without it a round keeps its last 100 states per side.
+    pub fn with_spill_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.spill_dir = Some(dir.into());
+        self
+    }
+
+    /// Save both buffers of every completed round to "round_<timestamp>.left.ndjson" and
+    /// "round_<timestamp>.right.ndjson" in `dir`, loadable with `StateBuffer::load`
+    pub fn with_dump_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dump_dir = Some(dir.into());
+        self
+    }
+
+    /// Gzip the round dumps (".ndjson.gz"); `StateBuffer::load` reads them back all the same
+    #[cfg(feature = "compression")]
+    pub fn with_dump_compression(mut self, enabled: bool) -> Self {
+        self.dump_gzip = enabled;
+        self
+    }
+
+    /// In visual mode, stop consuming both streams at the first row whose keys differ and wait for
+    /// Enter, so the divergence can be read before the timeline scrolls on
+    pub fn with_freeze_on_diff(mut self, enabled: bool) -> Self {
+        self.freeze_on_diff = enabled;
+        self
+    }
+
+    /// Share session counters created elsewhere (e.g., wired to a StatsD sink or to the sources)
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Give up with `TrackerError::SourceFailed` after `max` consecutive errors from one source
+    pub fn with_max_source_errors(mut self, max: usize) -> Self {
+        self.max_source_errors = Some(max);
+        self
+    }
+
+    /// Stop reading each side after `limit` states and end the session once both have delivered
+    /// them (or, with `LimitMode::Either`, once one has); in round mode the unfinished round is
+    /// compared as a final one
+    pub fn with_message_limit(mut self, limit: usize) -> Self {
+        self.message_limit = Some(limit);
+        self
+    }
+
+    pub fn with_limit_mode(mut self, mode: LimitMode) -> Self {
+        self.limit_mode = mode;
+        self
+    }
+
+    pub fn metrics(&self) -> Arc<Metrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// Rounds compared and how many pairs differed, mismatched or went missing so far
+    pub fn outcome(&self) -> TrackerOutcome {
+        *self.outcome.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn update_outcome(&self, update: impl FnOnce(&mut TrackerOutcome)) {
+        update(&mut self.outcome.lock().unwrap_or_else(|e| e.into_inner()));
+    }
+
+    fn same_key(&self, left: &State, right: &State) -> bool {
+        self.key_comparison.same(left.alignment_key.as_deref(), right.alignment_key.as_deref())
+    }
+
+    fn differ_for(&self, key: &str) -> &dyn Differ {
+        self.key_differs.get(key).map(|d| d.as_ref()).unwrap_or(self.differ.as_ref())
+    }
+
+    /// Print the diff of an aligned pair, or just count it when it repeats the key's last diff
+    fn print_diff(&self, key: &str, left: &JsonValue, right: &JsonValue, result: &DiffResult) {
+        if let Some(throttle) = &self.diff_throttle
+            && let Some(times) = throttle.lock().unwrap_or_else(|e| e.into_inner()).repeat(key, result)
+        {
+            info!("  (same diff as before, {times}x)");
+            return;
+        }
+        self.differ_for(key).print_result("left", "right", left, right, result);
+    }
+
+    fn should_diff(&self, key: &str) -> bool {
+        self.diff_on.is_empty() || self.diff_on.iter().any(|k| k == key)
+    }
+
+    /// Fail on the first path whose JSON type differs between two aligned states, if enabled
+    fn check_types(&self, left: &JsonValue, right: &JsonValue) -> Result<(), TrackerError> {
+        if self.fail_on_type_change
+            && let Some(change) = find_type_change(left, right)
+        {
+            return Err(TrackerError::TypeChange(change));
+        }
+        Ok(())
+    }
+
+    /// Pick the reporter for an output path by its extension (ignoring a trailing ".gz"), with the
+    /// run metadata attached
+    fn reporter_for(&self, path: &str) -> Box<dyn Reporter> {
+        let mut reporter = self.new_reporter(path);
+        if let Some(metadata) = &self.metadata {
+            reporter.set_metadata(metadata.clone());
+        }
+        reporter
+    }
+
+    fn new_reporter(&self, path: &str) -> Box<dyn Reporter> {
+        match Path::new(split_gz(path).0).extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => {
+                let reporter = JsonReporter::new()
+                    .with_key_comparison(self.key_comparison)
+                    .with_match_strategy(self.match_strategy)
+                    .with_differs(Arc::clone(&self.differ) as Arc<dyn Differ>, self.key_differs.clone());
+                match self.display_precision {
+                    Some(digits) => Box::new(reporter.with_display_precision(digits)),
+                    None => Box::new(reporter)
+                }
+            }
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => Box::new(CsvReporter::new(self.html_reporter())),
+            _ => Box::new(self.html_reporter())
+        }
+    }
+
+    fn html_reporter(&self) -> HtmlReporter {
+        let reporter = HtmlReporter::new(Arc::clone(&self.differ) as Arc<dyn Differ>)
+            .with_key_differs(self.key_differs.clone())
+            .with_selective_buffering(self.selective_buffer)
+            .with_timeline_anchor(self.timeline_anchor)
+            .with_key_comparison(self.key_comparison)
+            .with_match_strategy(self.match_strategy);
+        match self.display_precision {
+            Some(digits) => reporter.with_display_precision(digits),
+            None => reporter
+        }
+    }
+
     fn output_mode(&self) -> OutputMode {
         // Priority: visual > pretty_diff > logs
         if self.visual {
@@ -84,22 +551,54 @@ impl<L: StateSource, R: StateSource, D: Differ, E: AlignmentKeyExtractor> Aligne
         }
     }
 
-    pub async fn start(&self) -> Result<(), TrackerError> {
-        let mut left_rx = self.left.spawn();
-        let mut right_rx = self.right.spawn();
+    /// Track until the streams close or the round limit is reached, returning what was found
+    pub async fn start(&self) -> Result<TrackerOutcome, TrackerError> {
+        self.start_until(std::future::pending()).await
+    }
+
+    /// Like `start`, but stop early once `shutdown` completes (e.g., on Ctrl-C). Reports already
+    /// submitted are finished and the session reports are written with everything received so
+    /// far, as they are when the session fails; the unfinished round is left uncompared.
+    pub async fn start_until(&self, shutdown: impl Future<Output = ()>) -> Result<TrackerOutcome, TrackerError> {
+        let mut shutdown = pin!(shutdown);
+        let (left_errors_tx, mut left_errors) = mpsc::unbounded_channel();
+        let (right_errors_tx, mut right_errors) = mpsc::unbounded_channel();
+        let mut left_rx = self.left.spawn_with_errors(left_errors_tx);
+        let mut right_rx = self.right.spawn_with_errors(right_errors_tx);
+        let mut health = SourceHealth::new(self.max_source_errors);
 
-        let mut left_buffer = StateBuffer::new(100);
-        let mut right_buffer = StateBuffer::new(100);
+        let mut left_buffer = self.new_buffer("left");
+        let mut right_buffer = self.new_buffer("right");
 
-        let mut left_round_complete = false;
-        let mut right_round_complete = false;
-        let mut rounds_completed: usize = 0;
+        let mut round = RoundState::default();
+        let reports = ReportPool::new(self.report_workers);
 
         let mode = self.output_mode();
 
-        let mut visualizer = if mode == OutputMode::Visual { Some(TimelineVisualizer::new(15, 100)) } else { None };
+        let mut visualizer = if mode == OutputMode::Visual {
+            let width = self.visual_width.or_else(terminal_width).unwrap_or(DEFAULT_VISUAL_WIDTH);
+            let visualizer = TimelineVisualizer::new(self.visual_history, width).with_theme(self.theme);
+            Some(visualizer)
+        } else {
+            None
+        };
+        // Enter presses that resume the visual view after it froze at a divergence
+        let mut resume = (self.freeze_on_diff && visualizer.is_some()).then(spawn_enter_reader);
+
+        let mut reporters: Vec<Box<dyn Reporter>> =
+            self.report_outputs.iter().map(|path| self.reporter_for(path)).collect();
 
-        let mut reporter = if self.report_output.is_some() { Some(HtmlReporter::new()) } else { None };
+        // Coalescing would drop states that belong to a round, so it only applies to alignment mode
+        let rounds = self.tracks_rounds();
+        let drain_on_close = self.drain_on_close || rounds && self.round_end_signal.is_none();
+        let mut window = start_ticker(self.window_period());
+        let coalesce = self.realtime && !rounds;
+        if self.realtime && !coalesce {
+            warn!("--realtime is ignored with --round-end: every state is needed to compare full rounds");
+        }
+        if self.spill_dir.is_some() && !rounds {
+            warn!("--spill-dir is ignored without --round-end: only round buffers are spilled");
+        }
 
         // Show initial status for non-visual modes
         if mode != OutputMode::Visual {
@@ -107,193 +606,451 @@ impl<L: StateSource, R: StateSource, D: Differ, E: AlignmentKeyExtractor> Aligne
                 OutputMode::PrettyDiff => println!("🎨 Pretty Diff Mode - Showing aligned state comparisons\n"),
                 OutputMode::Logs => {
                     info!("📊 State tracker started");
-                    if let Some(ref signal) = self.round_end_signal {
+                    if let Some(period) = self.window_period() {
+                        info!("🎯 Comparing rounds every {:?}", period);
+                    } else if let Some(ref signal) = self.round_end_signal {
                         info!("🎯 Waiting for round completion signal: {}", signal);
+                    } else if rounds {
+                        info!("🎯 Comparing both streams as one round once they close");
                     }
                 }
                 _ => {}
             }
         }
 
-        loop {
-            tokio::select! {
-                msg = left_rx.recv() => {
-                    match msg {
-                        Some(data) => {
-                            let alignment_key = self.extractor.extract_key(&data);
-                            let state = State::new(data, alignment_key.clone());
-
-                            // Always add to visualizer (even if no key extracted)
-                            if let Some(ref mut viz) = visualizer {
-                                let display_key = alignment_key.as_deref().unwrap_or("<no-key>");
-                                viz.add_left(display_key);
+        let mut ticker = start_ticker(self.stats_interval);
+        let mut swap = SwapDetector::default();
+        let mut grace_until: Option<Instant> = None;
+        let mut stall_deadline = self.alignment_deadline();
+        let mut last_aligned = Instant::now();
+        let (mut left_open, mut right_open) = (true, true);
+        let mut interrupted = false;
+        let mut limit = MessageLimit::new(self.message_limit, self.limit_mode);
+        let mut key_watch = MissingKeyWatch::default();
+
+        // Errors end the session too, but only after the reports below are written
+        let session: Result<(), TrackerError> = async {
+            loop {
+                if limit.close_reached(&mut left_open, &mut right_open) {
+                    break;
+                }
+                tokio::select! {
+                    _ = &mut shutdown => {
+                        if mode != OutputMode::Visual {
+                            info!("🛑 stopping early, writing reports");
+                        }
+                        interrupted = true;
+                        break;
+                    }
+                    Some(error) = left_errors.recv() => health.failed(true, error)?,
+                    Some(error) = right_errors.recv() => health.failed(false, error)?,
+                    _ = next_tick(&mut ticker) => {
+                        if mode != OutputMode::Visual {
+                            info!("📈 {}", self.metrics.summary());
+                        }
+                    }
+                    _ = deadline_elapsed(stall_deadline) => {
+                        self.alignment_stalled(&left_buffer, &right_buffer, last_aligned)?;
+                        stall_deadline = self.alignment_deadline();
+                    }
+                    _ = next_tick(&mut window) => {
+                        if left_buffer.is_empty() && right_buffer.is_empty() {
+                            continue;
+                        }
+                        round.mark(true);
+                        round.mark(false);
+                        let should_exit = self.check_round_completion(
+                            &mut left_buffer,
+                            &mut right_buffer,
+                            &mut round,
+                            visualizer.as_mut(),
+                            &reports,
+                        ).await?;
+
+                        if should_exit {
+                            if mode != OutputMode::Visual {
+                                info!("🏁 Completed {} round(s), exiting", round.completed());
                             }
+                            return Ok(());
+                        }
+                    }
+                    _ = deadline_elapsed(grace_until) => {
+                        grace_until = None;
+                        let should_exit = self.check_round_completion(
+                            &mut left_buffer,
+                            &mut right_buffer,
+                            &mut round,
+                            visualizer.as_mut(),
+                            &reports,
+                        ).await?;
 
-                            // Add to reporter
-                            if let Some(ref mut rep) = reporter {
-                                rep.add_left(state.clone());
+                        if should_exit {
+                            if mode != OutputMode::Visual {
+                                info!("🏁 Completed {} round(s), exiting", round.completed());
                             }
+                            return Ok(());
+                        }
+                    }
+                    msg = left_rx.recv(), if left_open => {
+                        match msg {
+                            Some(mut data) => {
+                                self.metrics.left.record_value(&data);
+                                health.ok(true);
+                                limit.received(true);
+                                if coalesce {
+                                    let skipped;
+                                    (data, skipped) = coalesce_latest(data, &mut left_rx, &self.metrics.left);
+                                    if skipped > 0 {
+                                        debug!("left coalesced {skipped} queued message(s)");
+                                    }
+                                }
+
+                                let alignment_key = self.extractor.extract_side_key(true, &data);
+                                key_watch.observe(true, &data, alignment_key.is_some(), || self.extractor.describe());
+                                let state = State::new(data, alignment_key.clone());
+
+                                // Always add to visualizer (even if no key extracted)
+                                if let Some(ref mut viz) = visualizer {
+                                    let display_key = alignment_key.as_deref().unwrap_or("<no-key>");
+                                    viz.add_left(display_key);
+                                }
 
-                            if let Some(key) = &alignment_key {
-                                // Only log in Logs mode
-                                if mode == OutputMode::Logs {
-                                    info!("left: {}", key);
+                                // Add to reporters
+                                for rep in reporters.iter_mut() {
+                                    rep.add_left(state.clone());
+                                }
+
+                                if let Some(key) = &alignment_key {
+                                    self.metrics.left.record_key(key);
+                                    // Only log in Logs mode
+                                    if mode == OutputMode::Logs {
+                                        info!("left: {}", key);
+                                    }
                                 }
 
                                 // Check if this is the round end signal
-                                if let Some(ref signal) = self.round_end_signal {
-                                    if key == signal {
-                                        if mode == OutputMode::Logs {
-                                            info!("✓ left round complete");
-                                        }
-                                        left_round_complete = true;
+                                if self.ends_round(&state) {
+                                    if mode == OutputMode::Logs {
+                                        info!("✓ left round complete");
                                     }
+                                    round.mark(true);
                                 }
-                            }
 
-                            left_buffer.push(state);
+                                left_buffer.push(state)?;
 
-                            // Render visual if enabled
-                            if let Some(ref viz) = visualizer {
-                                viz.render();
-                            }
+                                // Render visual if enabled
+                                if let Some(ref viz) = visualizer {
+                                    viz.render();
+                                    freeze_on_divergence(viz, true, &mut resume).await;
+                                }
 
-                            // Check alignment or round completion
-                            if self.round_end_signal.is_some() {
-                                let should_exit = self.check_round_completion(
-                                    &mut left_buffer,
-                                    &mut right_buffer,
-                                    left_round_complete,
-                                    right_round_complete,
-                                    &mut left_round_complete,
-                                    &mut right_round_complete,
-                                    visualizer.as_mut(),
-                                    &mut rounds_completed,
-                                );
+                                // Check alignment or round completion
+                                if rounds {
+                                    if self.hold_round(round.both_done(), &mut grace_until) {
+                                        continue;
+                                    }
+                                    let should_exit = self.check_round_completion(
+                                        &mut left_buffer,
+                                        &mut right_buffer,
+                                        &mut round,
+                                        visualizer.as_mut(),
+                                        &reports,
+                                    ).await?;
 
-                                if should_exit {
-                                    if mode != OutputMode::Visual {
-                                        info!("🏁 Completed {} round(s), exiting", rounds_completed);
+                                    if should_exit {
+                                        if mode != OutputMode::Visual {
+                                            info!("🏁 Completed {} round(s), exiting", round.completed());
+                                        }
+                                        return Ok(());
+                                    }
+                                } else {
+                                    if self.check_alignment(&left_buffer, &right_buffer, &mut swap)? {
+                                        stall_deadline = self.alignment_deadline();
+                                        last_aligned = Instant::now();
                                     }
-                                    return Ok(());
                                 }
-                            } else {
-                                self.check_alignment(&left_buffer, &right_buffer);
                             }
-                        }
-                        None => {
-                            if mode != OutputMode::Visual {
-                                info!("left stream closed");
+                            None => {
+                                if mode != OutputMode::Visual {
+                                    info!("left stream closed");
+                                }
+                                left_open = false;
+                                if !drain_on_close || !right_open {
+                                    break;
+                                }
                             }
-                            break;
                         }
                     }
-                }
-                msg = right_rx.recv() => {
-                    match msg {
-                        Some(data) => {
-                            let alignment_key = self.extractor.extract_key(&data);
-                            let state = State::new(data, alignment_key.clone());
-
-                            // Always add to visualizer (even if no key extracted)
-                            if let Some(ref mut viz) = visualizer {
-                                let display_key = alignment_key.as_deref().unwrap_or("<no-key>");
-                                viz.add_right(display_key);
-                            }
+                    msg = right_rx.recv(), if right_open => {
+                        match msg {
+                            Some(mut data) => {
+                                self.metrics.right.record_value(&data);
+                                health.ok(false);
+                                limit.received(false);
+                                if coalesce {
+                                    let skipped;
+                                    (data, skipped) = coalesce_latest(data, &mut right_rx, &self.metrics.right);
+                                    if skipped > 0 {
+                                        debug!("right coalesced {skipped} queued message(s)");
+                                    }
+                                }
 
-                            // Add to reporter
-                            if let Some(ref mut rep) = reporter {
-                                rep.add_right(state.clone());
-                            }
+                                let alignment_key = self.extractor.extract_side_key(false, &data);
+                                key_watch.observe(false, &data, alignment_key.is_some(), || self.extractor.describe());
+                                let state = State::new(data, alignment_key.clone());
+
+                                // Always add to visualizer (even if no key extracted)
+                                if let Some(ref mut viz) = visualizer {
+                                    let display_key = alignment_key.as_deref().unwrap_or("<no-key>");
+                                    viz.add_right(display_key);
+                                }
 
-                            if let Some(key) = &alignment_key {
-                                // Only log in Logs mode
-                                if mode == OutputMode::Logs {
-                                    info!("right: {}", key);
+                                // Add to reporters
+                                for rep in reporters.iter_mut() {
+                                    rep.add_right(state.clone());
+                                }
+
+                                if let Some(key) = &alignment_key {
+                                    self.metrics.right.record_key(key);
+                                    // Only log in Logs mode
+                                    if mode == OutputMode::Logs {
+                                        info!("right: {}", key);
+                                    }
                                 }
 
                                 // Check if this is the round end signal
-                                if let Some(ref signal) = self.round_end_signal {
-                                    if key == signal {
-                                        if mode == OutputMode::Logs {
-                                            info!("✓ right round complete");
-                                        }
-                                        right_round_complete = true;
+                                if self.ends_round(&state) {
+                                    if mode == OutputMode::Logs {
+                                        info!("✓ right round complete");
                                     }
+                                    round.mark(false);
                                 }
-                            }
 
-                            right_buffer.push(state);
+                                right_buffer.push(state)?;
 
-                            // Render visual if enabled
-                            if let Some(ref viz) = visualizer {
-                                viz.render();
-                            }
+                                // Render visual if enabled
+                                if let Some(ref viz) = visualizer {
+                                    viz.render();
+                                    freeze_on_divergence(viz, false, &mut resume).await;
+                                }
 
-                            // Check alignment or round completion
-                            if self.round_end_signal.is_some() {
-                                let should_exit = self.check_round_completion(
-                                    &mut left_buffer,
-                                    &mut right_buffer,
-                                    left_round_complete,
-                                    right_round_complete,
-                                    &mut left_round_complete,
-                                    &mut right_round_complete,
-                                    visualizer.as_mut(),
-                                    &mut rounds_completed,
-                                );
+                                // Check alignment or round completion
+                                if rounds {
+                                    if self.hold_round(round.both_done(), &mut grace_until) {
+                                        continue;
+                                    }
+                                    let should_exit = self.check_round_completion(
+                                        &mut left_buffer,
+                                        &mut right_buffer,
+                                        &mut round,
+                                        visualizer.as_mut(),
+                                        &reports,
+                                    ).await?;
 
-                                if should_exit {
-                                    if mode != OutputMode::Visual {
-                                        info!("🏁 Completed {} round(s), exiting", rounds_completed);
+                                    if should_exit {
+                                        if mode != OutputMode::Visual {
+                                            info!("🏁 Completed {} round(s), exiting", round.completed());
+                                        }
+                                        return Ok(());
+                                    }
+                                } else {
+                                    if self.check_alignment(&left_buffer, &right_buffer, &mut swap)? {
+                                        stall_deadline = self.alignment_deadline();
+                                        last_aligned = Instant::now();
                                     }
-                                    return Ok(());
                                 }
-                            } else {
-                                self.check_alignment(&left_buffer, &right_buffer);
                             }
-                        }
-                        None => {
-                            if mode != OutputMode::Visual {
-                                info!("right stream closed");
+                            None => {
+                                if mode != OutputMode::Visual {
+                                    info!("right stream closed");
+                                }
+                                right_open = false;
+                                if !drain_on_close || !left_open {
+                                    break;
+                                }
                             }
-                            break;
                         }
                     }
                 }
             }
+
+            // Both streams are done (or cut off by the limit): whatever the last round holds is all it will
+            // ever get
+            let finishing = (drain_on_close || limit.hit()) && rounds && !interrupted;
+            if finishing && !(left_buffer.is_empty() && right_buffer.is_empty()) {
+                if mode == OutputMode::Logs && limit.hit() {
+                    info!("🧹 Message limit reached; comparing the unfinished round");
+                } else if mode == OutputMode::Logs
+                    && (self.round_end_signal.is_some() || self.window_period().is_some())
+                {
+                    info!("🧹 Both streams closed; comparing the unfinished round");
+                }
+                round.mark(true);
+                round.mark(false);
+                self.check_round_completion(
+                    &mut left_buffer,
+                    &mut right_buffer,
+                    &mut round,
+                    visualizer.as_mut(),
+                    &reports
+                )
+                .await?;
+            }
+            Ok(())
         }
+        .await;
+
+        reports.drain().await;
 
-        // Generate HTML report if requested
-        if let (Some(output_path), Some(rep)) = (self.report_output.as_ref(), reporter) {
+        // Generate reports if requested
+        for (output_path, rep) in self.report_outputs.iter().zip(&reporters) {
             if let Err(e) = rep.generate(output_path) {
-                eprintln!("⚠️  Failed to generate report: {}", e);
+                eprintln!("⚠️  Failed to generate report {}: {}", output_path, e);
+            }
+        }
+
+        session.map(|()| self.outcome())
+    }
+
+    /// With a grace window, keep a completed round open to absorb trailing messages. Starts the
+    /// window when both sides first complete; returns whether the comparison should wait.
+    fn hold_round(&self, both_complete: bool, grace_until: &mut Option<Instant>) -> bool {
+        let Some(grace) = self.round_grace else {
+            return false;
+        };
+        if both_complete && grace_until.is_none() {
+            if self.output_mode() == OutputMode::Logs {
+                info!("⏳ both rounds complete, absorbing trailing messages for {}ms", grace.as_millis());
             }
+            *grace_until = Some(Instant::now() + grace);
         }
+        grace_until.is_some()
+    }
 
+    /// Buffer for one side; spilling only applies to rounds, as without them nothing ever clears it
+    fn new_buffer(&self, side: &'static str) -> SpillingStateBuffer {
+        match &self.spill_dir {
+            Some(dir) if self.tracks_rounds() => SpillingStateBuffer::new(100).with_spill_dir(dir, side),
+            // A whole-stream round has to keep every state to compare it in full
+            None if self.whole_stream && self.round_end_signal.is_none() && self.window_period().is_none() => {
+                SpillingStateBuffer::new(usize::MAX)
+            }
+            _ => SpillingStateBuffer::new(100)
+        }
+    }
+
+    /// Whether a state carries the round-end signal
+    fn ends_round(&self, state: &State) -> bool {
+        let Some(signal) = &self.round_end_signal else {
+            return false;
+        };
+        match &self.round_end_extractor {
+            Some(extractor) => {
+                extractor.extract_key(&state.data).is_some_and(|key| self.key_comparison.matches_signal(&key, signal))
+            }
+            None => state.alignment_key.as_deref().is_some_and(|key| self.key_comparison.matches_signal(key, signal))
+        }
+    }
+
+    /// Whether states are buffered and compared per round (by signal, or whole streams) rather
+    /// than aligned as they arrive
+    fn tracks_rounds(&self) -> bool {
+        self.round_end_signal.is_some() || self.whole_stream || self.window_period().is_some()
+    }
+
+    /// How often a time-windowed round is compared; a zero-length window counts as none
+    fn window_period(&self) -> Option<Duration> {
+        match self.window {
+            WindowMode::Signal => None,
+            WindowMode::Duration(period) => Some(period).filter(|period| !period.is_zero())
+        }
+    }
+
+    /// When the alignment timeout next passes, counting from now; never with round tracking, whose
+    /// sides are compared per round rather than kept in step
+    fn alignment_deadline(&self) -> Option<Instant> {
+        if self.tracks_rounds() {
+            return None;
+        }
+        self.alignment_timeout.map(|timeout| Instant::now() + timeout)
+    }
+
+    /// Report that no pair aligned within the timeout. The side whose latest state is older (or
+    /// that sent nothing) is the one stalled.
+    fn alignment_stalled(
+        &self,
+        left_buffer: &SpillingStateBuffer,
+        right_buffer: &SpillingStateBuffer,
+        last_aligned: Instant
+    ) -> Result<(), TrackerError> {
+        let (left, right) = (left_buffer.latest(), right_buffer.latest());
+        let stalled = match (left, right) {
+            (None, None) => return Ok(()),
+            (Some(l), Some(r)) if l.alignment_key.is_some() && self.same_key(l, r) => return Ok(()),
+            (Some(_), None) => "right",
+            (None, Some(_)) => "left",
+            (Some(l), Some(r)) if l.timestamp <= r.timestamp => "left",
+            (Some(_), Some(_)) => "right"
+        };
+
+        let key =
+            |state: Option<&State>| state.and_then(|s| s.alignment_key.clone()).unwrap_or_else(|| "—".to_string());
+        let message = format!(
+            "{stalled} side stalled: nothing aligned for {:.0?} (last keys: left={}, right={})",
+            last_aligned.elapsed(),
+            key(left),
+            key(right)
+        );
+        if self.strict_alignment {
+            return Err(TrackerError::AlignmentStalled(message));
+        }
+        warn!("⏰ {}", message);
         Ok(())
     }
 
-    fn check_alignment(&self, left_buffer: &StateBuffer, right_buffer: &StateBuffer) {
+    /// Compare the latest states when their keys line up; returns whether they did
+    fn check_alignment(
+        &self,
+        left_buffer: &SpillingStateBuffer,
+        right_buffer: &SpillingStateBuffer,
+        swap: &mut SwapDetector
+    ) -> Result<bool, TrackerError> {
         let left_key = left_buffer.latest_alignment_key();
         let right_key = right_buffer.latest_alignment_key();
         let mode = self.output_mode();
 
         match (left_key, right_key) {
-            (Some(l_key), Some(r_key)) if l_key == r_key => {
+            (Some(l_key), Some(r_key)) if self.key_comparison.matches(l_key, r_key) => {
                 // Keys are aligned! Compare the states
                 if let (Some(left_state), Some(right_state)) = (left_buffer.latest(), right_buffer.latest()) {
+                    self.check_types(&left_state.data, &right_state.data)?;
+                    let lag = lag_millis(left_state.timestamp, right_state.timestamp);
+                    self.metrics.record_latency(l_key, lag);
+                    self.metrics.record_alignment(AlignmentStatus::Aligned);
+                    let result = if left_state.data == right_state.data {
+                        DiffResult::default()
+                    } else {
+                        self.differ_for(l_key).compute_diff(&left_state.data, &right_state.data)
+                    };
+                    if !result.identical {
+                        self.metrics.record_diff(result.ops_count);
+                        self.update_outcome(|outcome| outcome.differing += 1);
+                    }
+                    swap.observe(&left_state.data, &right_state.data);
                     match mode {
                         OutputMode::Logs => {
-                            info!("✓ aligned: {}", l_key);
+                            info!("✓ aligned: {} ({})", l_key, describe_lead(lag));
                         }
                         OutputMode::PrettyDiff => {
                             println!("\n✓ Aligned at: {}", l_key.bright_green().bold());
-                            self.differ.print_diff("left", "right", &left_state.data, &right_state.data);
+                            if self.should_diff(l_key) {
+                                self.print_diff(l_key, &left_state.data, &right_state.data, &result);
+                            }
                         }
                         OutputMode::Visual => {} // Handled by visualizer
                     }
                 }
+                return Ok(true);
             }
             (Some(l_key), Some(r_key)) => {
                 if mode == OutputMode::PrettyDiff {
@@ -321,25 +1078,108 @@ impl<L: StateSource, R: StateSource, D: Differ, E: AlignmentKeyExtractor> Aligne
             }
             (None, None) => {}
         }
+
+        Ok(false)
     }
 
-    fn check_round_completion(
+    /// Compare and report the buffered round once both sides are done. The round is counted and
+    /// its flags reset up front, so nothing below can see a half-finished round.
+    async fn check_round_completion(
         &self,
-        left_buffer: &mut StateBuffer,
-        right_buffer: &mut StateBuffer,
-        left_complete: bool,
-        right_complete: bool,
-        left_flag: &mut bool,
-        right_flag: &mut bool,
+        left_buffer: &mut SpillingStateBuffer,
+        right_buffer: &mut SpillingStateBuffer,
+        round: &mut RoundState,
         mut visualizer: Option<&mut TimelineVisualizer>,
-        rounds_completed: &mut usize
-    ) -> bool {
+        reports: &ReportPool
+    ) -> Result<bool, TrackerError> {
         let mode = self.output_mode();
-        if left_complete && right_complete {
-            *rounds_completed += 1;
-            // Compare all states in the buffers
-            let left_states = left_buffer.states();
-            let right_states = right_buffer.states();
+        if let Some(rounds_completed) = round.finish() {
+            // Keys and arrival times of everything the round buffered; payloads stay where they are
+            // (on disk when spilled) and are loaded one pair at a time
+            let (left_all, right_all) = (left_buffer.outline(), right_buffer.outline());
+
+            // Buffer positions of the states compared: all of them, or only each key's first one
+            let (left_positions, right_positions) = if self.first_per_key {
+                let (left, right) = (first_per_key(&left_all), first_per_key(&right_all));
+                let repeats = left_all.len() + right_all.len() - left.len() - right.len();
+                if repeats > 0 && mode == OutputMode::Logs {
+                    info!("🔁 Ignoring {} repeated state(s) this round", repeats);
+                }
+                (left, right)
+            } else {
+                ((0..left_all.len()).collect(), (0..right_all.len()).collect())
+            };
+            let left_states: Vec<State> = left_positions.iter().map(|&i| left_all[i].clone()).collect();
+            let right_states: Vec<State> = right_positions.iter().map(|&j| right_all[j].clone()).collect();
+            let (left_states, right_states) = (left_states.as_slice(), right_states.as_slice());
+            // The full state behind an entry of `left_states`/`right_states`
+            let load_left = |i: usize| left_buffer.get(left_positions[i]);
+            let load_right = |j: usize| right_buffer.get(right_positions[j]);
+
+            let pairs = {
+                // `OptimalByKey` scores every same-key pairing with the key's differ; each state is
+                // loaded at most once for that and dropped once the pairs are chosen
+                let left_loaded: Vec<OnceCell<Option<State>>> = left_states.iter().map(|_| OnceCell::new()).collect();
+                let right_loaded: Vec<OnceCell<Option<State>>> = right_states.iter().map(|_| OnceCell::new()).collect();
+                match_states(self.match_strategy, self.key_comparison, left_states, right_states, |i, j| {
+                    let left = left_loaded[i].get_or_init(|| load_left(i).ok().map(Cow::into_owned));
+                    let right = right_loaded[j].get_or_init(|| load_right(j).ok().map(Cow::into_owned));
+                    match (left, right) {
+                        (Some(l), Some(r)) => {
+                            let key = left_states[i].alignment_key.as_deref().unwrap_or("<no-key>");
+                            self.differ_for(key).count_changes(&l.data, &r.data)
+                        }
+                        _ => usize::MAX
+                    }
+                })
+            };
+
+            let mut rollup = PathRollup::new();
+            // Similarity of each left state to its partner, printed with the diffs
+            let mut similarities = vec![1.0; left_states.len()];
+            // Diff of each left state and its partner, printed below without diffing again
+            let mut results = vec![DiffResult::default(); left_states.len()];
+            let mut found = TrackerOutcome { rounds: 1, ..TrackerOutcome::default() };
+            for pair in &pairs {
+                let (i, j) = match (pair.left, pair.right) {
+                    (Some(i), Some(j)) if self.same_key(&left_states[i], &right_states[j]) => (i, j),
+                    (Some(_), Some(_)) => {
+                        self.metrics.record_alignment(AlignmentStatus::Mismatched);
+                        found.mismatched += 1;
+                        continue;
+                    }
+                    _ => {
+                        self.metrics.record_alignment(AlignmentStatus::Missing);
+                        found.missing += 1;
+                        continue;
+                    }
+                };
+                let (left_state, right_state) = (load_left(i)?, load_right(j)?);
+                let key = left_state.alignment_key.as_deref().unwrap_or("<no-key>");
+                self.check_types(&left_state.data, &right_state.data)?;
+                self.metrics.record_latency(key, lag_millis(left_state.timestamp, right_state.timestamp));
+                self.metrics.record_alignment(AlignmentStatus::Aligned);
+                let result = if left_state.data == right_state.data {
+                    DiffResult::default()
+                } else {
+                    let result = self.differ_for(key).compute_diff(&left_state.data, &right_state.data);
+                    self.metrics.record_diff(result.ops_count);
+                    found.differing += usize::from(!result.identical);
+                    result
+                };
+                similarities[i] = similarity(&result, &left_state.data, &right_state.data);
+                if self.should_diff(key) {
+                    rollup.record(&result);
+                }
+                results[i] = result;
+            }
+            self.update_outcome(|outcome| {
+                outcome.rounds += 1;
+                outcome.diverged_rounds += usize::from(found.diverged());
+                outcome.differing += found.differing;
+                outcome.mismatched += found.mismatched;
+                outcome.missing += found.missing;
+            });
 
             if self.visual {
                 // Use visual rendering
@@ -354,75 +1194,361 @@ impl<L: StateSource, R: StateSource, D: Differ, E: AlignmentKeyExtractor> Aligne
             }
 
             if !self.visual {
-                // Compare state by state based on alignment keys
-                for (i, left_state) in left_states.iter().enumerate() {
-                    if let Some(left_key) = &left_state.alignment_key {
-                        // Find matching state in right buffer
-                        if let Some(right_state) =
-                            right_states.iter().find(|r| r.alignment_key.as_ref() == Some(left_key))
-                        {
-                            info!("  Comparing state {}: {}", i + 1, left_key);
-                            self.differ.print_diff("left", "right", &left_state.data, &right_state.data);
-                        } else {
-                            info!("  ⚠️  State {} ({}) missing in right", i + 1, left_key);
+                // Compare state by state, as paired by the match strategy
+                for pair in &pairs {
+                    let key = |state: &State| state.alignment_key.clone().unwrap_or_else(|| "<no-key>".to_string());
+                    match (pair.left.map(|i| (i, &left_states[i])), pair.right.map(|j| (j, &right_states[j]))) {
+                        (Some((i, left_state)), Some((j, right_state))) if self.same_key(left_state, right_state) => {
+                            let left_key = key(left_state);
+                            if self.should_diff(&left_key) {
+                                let (left_state, right_state) = (load_left(i)?, load_right(j)?);
+                                info!(
+                                    "  Comparing state {}: {} (similarity {:.0}%)",
+                                    i + 1,
+                                    left_key,
+                                    similarities[i] * 100.0
+                                );
+                                self.print_diff(&left_key, &left_state.data, &right_state.data, &results[i]);
+                            }
+                        }
+                        (Some((i, left_state)), Some((_, right_state))) => {
+                            info!("  ✗ State {}: left={} ≠ right={}", i + 1, key(left_state), key(right_state));
+                        }
+                        (Some((i, left_state)), None) => {
+                            info!("  ⚠️  State {} ({}) missing in right", i + 1, key(left_state));
                         }
+                        (None, Some((_, right_state))) => {
+                            info!("  ⚠️  State ({}) only in right", key(right_state));
+                        }
+                        (None, None) => {}
                     }
                 }
 
-                // Check for states in right that aren't in left
-                for right_state in right_states.iter() {
-                    if let Some(right_key) = &right_state.alignment_key {
-                        if !left_states.iter().any(|l| l.alignment_key.as_ref() == Some(right_key)) {
-                            info!("  ⚠️  State ({}) only in right", right_key);
-                        }
+                if !rollup.is_empty() {
+                    info!("🧩 Changed paths this round ({} comparisons):", rollup.comparisons);
+                    for line in rollup.to_string().lines() {
+                        info!("  {}", line);
                     }
                 }
-
+                if let Some(latency) = self.metrics.latency_stats() {
+                    info!("⏱️  right lag: {}", latency);
+                }
                 info!("✅ Round comparison complete\n");
             }
 
-            if let Some(output_path) = &self.report_output {
-                let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
-                let report_path = output_path.replace(".html", &format!("_{}.html", timestamp));
+            let timestamp = reports.stamp();
+            for output_path in &self.report_outputs {
+                let report_path = timestamped_path(output_path, &timestamp);
 
-                let mut final_reporter = HtmlReporter::new();
-                for state in left_buffer.states() {
-                    final_reporter.add_left(state.clone());
+                // Streamed from the buffers, so the round is never held in memory twice
+                let mut final_reporter = self.reporter_for(output_path);
+                for state in left_buffer.iter()? {
+                    final_reporter.add_left(state?);
                 }
-                for state in right_buffer.states() {
-                    final_reporter.add_right(state.clone());
+                for state in right_buffer.iter()? {
+                    final_reporter.add_right(state?);
                 }
+                final_reporter.set_rollup(rollup.clone());
 
-                if let Err(e) = final_reporter.generate(&report_path) {
-                    if mode != OutputMode::Visual {
-                        eprintln!("⚠️  Failed to generate round report: {}", e);
-                    }
-                } else if mode != OutputMode::Visual {
-                    println!("📄 Round report: {}", report_path);
-                }
+                reports.submit(final_reporter, report_path, mode == OutputMode::Visual).await;
+            }
+            if let Some(dir) = &self.dump_dir {
+                dump_round(dir, &timestamp, self.dump_gzip, left_buffer, right_buffer);
             }
 
-            // Reset for next round
-            *left_flag = false;
-            *right_flag = false;
-            left_buffer.clear();
-            right_buffer.clear();
+            let has_key = |states: &[State], key: &str| {
+                states.iter().any(|s| s.alignment_key.as_deref().is_some_and(|k| self.key_comparison.matches(k, key)))
+            };
+            if let Some(missing) =
+                MissingKeys::check(&self.required_keys, |key| has_key(&left_all, key), |key| has_key(&right_all, key))
+            {
+                reports.drain().await;
+                return Err(TrackerError::MissingKeys(missing));
+            }
+
+            // Reset buffers for the next round
+            left_buffer.clear()?;
+            right_buffer.clear()?;
             if let Some(ref mut viz) = visualizer {
                 viz.clear_history();
             }
 
             // Check if we should stop
-            if let Some(max) = self.max_rounds {
-                if *rounds_completed >= max {
-                    return true; // Signal to exit
-                }
+            if let Some(max) = self.max_rounds
+                && rounds_completed >= max
+            {
+                return Ok(true); // Signal to exit
             }
-        } else if left_complete && mode == OutputMode::Logs {
+        } else if round.left_done() && mode == OutputMode::Logs {
             info!("⏳ left round complete, waiting for right...");
-        } else if right_complete && mode == OutputMode::Logs {
+        } else if round.right_done() && mode == OutputMode::Logs {
             info!("⏳ right round complete, waiting for left...");
         }
 
-        false // Continue tracking
+        Ok(false) // Continue tracking
+    }
+}
+
+/// The first state of each alignment key, in arrival order; states without a key are all kept
+fn first_per_key(states: &[State]) -> Vec<usize> {
+    let mut seen = HashSet::new();
+    states
+        .iter()
+        .enumerate()
+        .filter(|(_, state)| state.alignment_key.as_ref().is_none_or(|key| seen.insert(key.clone())))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Forward each line read from stdin as a resume signal
+fn spawn_enter_reader() -> mpsc::UnboundedReceiver<()> {
+    spawn_line_signals(|| std::io::stdin().lock())
+}
+
+/// Send a signal per line of the input `open` returns, read on a dedicated thread (a blocking read
+/// must not hold up the runtime's shutdown); the channel closes at the end of the input
+fn spawn_line_signals<R: BufRead>(open: impl FnOnce() -> R + Send + 'static) -> mpsc::UnboundedReceiver<()> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        for line in open().lines() {
+            if line.is_err() || tx.send(()).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// Hold the tracker at the first divergent row until Enter is pressed; later divergences don't
+/// pause. With stdin closed the view keeps going.
+async fn freeze_on_divergence(
+    viz: &TimelineVisualizer,
+    is_left: bool,
+    resume: &mut Option<mpsc::UnboundedReceiver<()>>
+) {
+    let Some(enter) = resume else {
+        return;
+    };
+    if !viz.latest_row_diverged(is_left) {
+        return;
+    }
+
+    viz.render_paused();
+    // Only presses made after the pause count
+    while enter.try_recv().is_ok() {}
+    enter.recv().await;
+    *resume = None;
+}
+
+/// Wait for a deadline (round grace window, alignment timeout) to pass, or forever when none is set
+async fn deadline_elapsed(until: Option<Instant>) {
+    match until {
+        Some(until) => sleep_until(until).await,
+        None => std::future::pending().await
+    }
+}
+
+/// Save a completed round's buffers side by side in `dir`; failures are reported but don't stop
+/// the session
+fn dump_round(dir: &Path, timestamp: &str, gzip: bool, left: &SpillingStateBuffer, right: &SpillingStateBuffer) {
+    if let Err(err) = fs::create_dir_all(dir) {
+        eprintln!("⚠️  Failed to create dump directory {}: {err}", dir.display());
+        return;
+    }
+    for (side, buffer) in [("left", left), ("right", right)] {
+        let path = dir.join(format!("round_{timestamp}.{side}.ndjson{}", if gzip { ".gz" } else { "" }));
+        let saved = buffer.iter().and_then(|states| {
+            // Stop at the first state that can't be read back and report it
+            let mut failed = None;
+            save_states(&path, states.map_while(|state| state.map_err(|err| failed = Some(err)).ok()))?;
+            failed.map_or(Ok(()), Err)
+        });
+        match saved {
+            Ok(()) => debug!("saved {} {side} state(s) to {}", buffer.len(), path.display()),
+            Err(err) => eprintln!("⚠️  Failed to dump {side} buffer to {}: {err}", path.display())
+        }
+    }
+}
+
+/// Insert a timestamp before the file extension ("report.html" -> "report_20251008_210006.html",
+/// "report.html.gz" -> "report_20251008_210006.html.gz")
+fn timestamped_path(path: &str, timestamp: &str) -> String {
+    let (path, gz) = split_gz(path);
+    let stamped = match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => format!("{}_{}.{}", &path[..path.len() - ext.len() - 1], timestamp, ext),
+        None => format!("{}_{}", path, timestamp)
+    };
+    if gz { stamped + ".gz" } else { stamped }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        adapter::{JsonPatchDiffer, KeyRules, RuleDiffer, VecSource},
+        port::JsonPathExtractor
+    };
+
+    fn tracker() -> AlignedTracker<VecSource, VecSource, JsonPatchDiffer, JsonPathExtractor> {
+        let (left, right) = (VecSource::new("left", vec![]), VecSource::new("right", vec![]));
+        AlignedTracker::new(left, right, JsonPatchDiffer::default(), JsonPathExtractor::new("type"))
+    }
+
+    #[test]
+    fn round_ends_are_read_from_the_round_end_field_when_set() {
+        let state = |kind: &str, status: &str| {
+            State::new(json!({"type": kind, "data": {"status": status}}), Some(kind.to_string()))
+        };
+
+        let by_key = tracker().with_round_end_signal("final".to_string());
+        assert!(by_key.ends_round(&state("final", "open")));
+        assert!(!by_key.ends_round(&state("bet", "final")));
+
+        let by_field = tracker()
+            .with_round_end_value("final".to_string())
+            .with_round_end_extractor(JsonPathExtractor::new("data.status"));
+        assert!(by_field.ends_round(&state("bet", "final")));
+        assert!(!by_field.ends_round(&state("final", "open")));
+        assert!(!tracker().ends_round(&state("final", "final")));
+    }
+
+    #[tokio::test]
+    async fn stopping_early_still_writes_the_session_reports() {
+        let dir = std::env::temp_dir().join(format!("pica-stop-early-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let report = dir.join("session.json");
+        // Both sides stay open well past the shutdown; left has finished its round, right hasn't
+        let left = VecSource::timed(
+            "left",
+            vec![
+                (Duration::ZERO, json!({"type": "bet", "v": 1})),
+                (Duration::ZERO, json!({"type": "end"})),
+                (Duration::from_secs(60), json!({"type": "bet"})),
+            ]
+        );
+        let right = VecSource::timed(
+            "right",
+            vec![(Duration::ZERO, json!({"type": "bet", "v": 2})), (Duration::from_secs(60), json!({"type": "end"}))]
+        );
+        let tracker = AlignedTracker::new(left, right, JsonPatchDiffer::default(), JsonPathExtractor::new("type"))
+            .with_round_end_signal("end".to_string())
+            .with_report_output(report.to_string_lossy().into_owned());
+
+        let stopped = tokio::time::timeout(
+            Duration::from_secs(10),
+            tracker.start_until(tokio::time::sleep(Duration::from_millis(100)))
+        )
+        .await;
+        assert!(stopped.expect("the session should stop on shutdown").is_ok());
+        let report: JsonValue = serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+        assert_eq!(report["stats"], json!({"left": 2, "right": 1, "matched": 1, "mismatched": 0}));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn sessions_that_fail_still_write_their_reports() {
+        let dir = std::env::temp_dir().join(format!("pica-failed-session-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let report = dir.join("session.json");
+        let round = || vec![json!({"type": "bet"}), json!({"type": "end"}), json!({"type": "bet"})];
+        let tracker = AlignedTracker::new(
+            VecSource::new("left", round()),
+            VecSource::new("right", round()),
+            JsonPatchDiffer::default(),
+            JsonPathExtractor::new("type")
+        )
+        .with_round_end_signal("end".to_string())
+        .with_required_keys(vec!["settle".to_string()])
+        .with_drain_on_close(true)
+        .with_report_output(report.to_string_lossy().into_owned());
+
+        assert!(matches!(tracker.start().await, Err(TrackerError::MissingKeys(_))));
+        let report: JsonValue = serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+        assert!(report["stats"]["left"].as_u64().is_some_and(|left| left >= 2));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn drain_on_close_reads_the_open_stream_to_its_end() {
+        for (enabled, expected) in [(true, 3), (false, 0)] {
+            let left = VecSource::new("left", vec![json!({"type": "a"})]);
+            let right = VecSource::new("right", (0..3).map(|n| json!({"type": "a", "n": n})).collect())
+                .with_delay(Duration::from_millis(20));
+            let tracker = AlignedTracker::new(left, right, JsonPatchDiffer::default(), JsonPathExtractor::new("type"))
+                .with_drain_on_close(enabled);
+            tracker.start().await.unwrap();
+            assert_eq!(tracker.metrics().right.messages(), expected, "drain_on_close = {enabled}");
+        }
+    }
+
+    #[tokio::test]
+    async fn round_ends_arriving_together_close_one_round() {
+        // Both sides end their round in the same tick
+        let side = |name: &str, settled: u64| {
+            VecSource::timed(
+                name,
+                vec![
+                    (Duration::ZERO, json!({"type": "bet", "v": 1})),
+                    (Duration::ZERO, json!({"type": "settle", "v": settled})),
+                    (Duration::from_millis(50), json!({"type": "end"})),
+                ]
+            )
+        };
+        let tracker = AlignedTracker::new(
+            side("left", 1),
+            side("right", 2),
+            JsonPatchDiffer::default(),
+            JsonPathExtractor::new("type")
+        )
+        .with_round_end_signal("end".to_string())
+        .with_drain_on_close(true);
+
+        let outcome = tracker.start().await.unwrap();
+        assert_eq!(outcome.rounds, 1);
+        // bet, settle and end paired by key; only settle differs
+        assert_eq!((outcome.differing, outcome.mismatched, outcome.missing), (1, 0, 0));
+    }
+
+    #[tokio::test]
+    async fn optimal_matching_scores_pairs_with_the_keys_differ() {
+        let noise = json!({"a": 1, "b": 2, "c": 3, "d": 4, "e": 5});
+        let left = VecSource::new(
+            "left",
+            vec![
+                json!({"type": "bet", "v": 1, "noise": noise}),
+                json!({"type": "bet", "v": 2, "noise": {}}),
+                json!({"type": "end"}),
+            ]
+        );
+        let right = VecSource::new(
+            "right",
+            vec![
+                json!({"type": "bet", "v": 1, "noise": {}}),
+                json!({"type": "bet", "v": 2, "noise": noise}),
+                json!({"type": "end"}),
+            ]
+        );
+        // Raw patches would pair v:1 with v:2 (one op each) over the noisy but otherwise equal states
+        let rules = KeyRules { ignore: vec!["/noise".to_string()], ..KeyRules::default() };
+        let tracker = AlignedTracker::new(left, right, JsonPatchDiffer::default(), JsonPathExtractor::new("type"))
+            .with_round_end_signal("end".to_string())
+            .with_match_strategy(MatchStrategy::OptimalByKey)
+            .with_key_differ("bet".to_string(), Box::new(RuleDiffer::new(rules, JsonPatchDiffer::default())))
+            .with_drain_on_close(true);
+
+        let outcome = tracker.start().await.unwrap();
+        assert_eq!((outcome.rounds, outcome.differing), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn each_line_is_a_resume_signal_until_the_input_ends() {
+        let mut signals = spawn_line_signals(|| Cursor::new("\n\n"));
+        assert_eq!(signals.recv().await, Some(()));
+        assert_eq!(signals.recv().await, Some(()));
+        assert_eq!(signals.recv().await, None);
     }
 }
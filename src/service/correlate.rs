@@ -0,0 +1,218 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use super::{
+    health::SourceHealth,
+    tracker::{next_tick, start_ticker}
+};
+use crate::{
+    domain::{State, TrackerError, find_type_change},
+    metric::{Metrics, lag_millis},
+    port::{AlignmentKeyExtractor, Differ, StateSource}
+};
+
+/// Default cap on unmatched items kept per side
+const DEFAULT_MAX_PENDING: usize = 10_000;
+
+/// Correlates the two streams as request/response pairs: each left item is paired with the right
+/// item carrying the same id, however far apart they arrive. Unmatched items wait on their side
+/// until the other half shows up; each completed pair is diffed and its round-trip time recorded.
+pub struct PairTracker<L: StateSource, R: StateSource, D: Differ, E: AlignmentKeyExtractor> {
+    left:                L,
+    right:               R,
+    differ:              D,
+    /// Extracts the correlation id (e.g., `request_id`) from both sides
+    id_extractor:        E,
+    /// Oldest unmatched items are dropped beyond this many per side
+    max_pending:         usize,
+    /// Shared session counters (readable from outside while the tracker runs)
+    metrics:             Arc<Metrics>,
+    /// Print a throughput line at this interval
+    stats_interval:      Option<Duration>,
+    /// Stop with an error the first time a pair has a path that changed JSON type
+    fail_on_type_change: bool,
+    /// Stop with an error once a source reports this many errors in a row
+    max_source_errors:   Option<usize>,
+    /// When one stream closes, keep reading the other until it closes too
+    drain_on_close:      bool
+}
+
+/// Items of one side still waiting for their other half, by id
+#[derive(Default)]
+struct Pending {
+    items: HashMap<String, State>
+}
+
+impl Pending {
+    /// Park an item; beyond `max` the oldest one is dropped and returned
+    fn park(&mut self, id: String, state: State, max: usize) -> Option<(String, State)> {
+        if let Some(previous) = self.items.insert(id.clone(), state) {
+            warn!("duplicate id {id}; replacing the unmatched item from {}", previous.timestamp.format("%H:%M:%S%.3f"));
+        }
+        if self.items.len() <= max {
+            return None;
+        }
+        let oldest = self.items.iter().min_by_key(|(_, state)| state.timestamp).map(|(id, _)| id.clone())?;
+        self.items.remove_entry(&oldest)
+    }
+}
+
+impl<L: StateSource, R: StateSource, D: Differ, E: AlignmentKeyExtractor> PairTracker<L, R, D, E> {
+    pub fn new(left: L, right: R, differ: D, id_extractor: E) -> Self {
+        Self {
+            left,
+            right,
+            differ,
+            id_extractor,
+            max_pending: DEFAULT_MAX_PENDING,
+            metrics: Arc::new(Metrics::new()),
+            stats_interval: None,
+            fail_on_type_change: false,
+            max_source_errors: None,
+            drain_on_close: false
+        }
+    }
+
+    /// Keep at most `max` unmatched items per side (default 10 000)
+    pub fn with_max_pending(mut self, max: usize) -> Self {
+        self.max_pending = max;
+        self
+    }
+
+    pub fn with_stats_interval(mut self, interval: Duration) -> Self {
+        self.stats_interval = Some(interval);
+        self
+    }
+
+    pub fn with_fail_on_type_change(mut self, enabled: bool) -> Self {
+        self.fail_on_type_change = enabled;
+        self
+    }
+
+    /// Share session counters created elsewhere (e.g., wired to a StatsD sink or to the sources)
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Give up with `TrackerError::SourceFailed` after `max` consecutive errors from one source
+    pub fn with_max_source_errors(mut self, max: usize) -> Self {
+        self.max_source_errors = Some(max);
+        self
+    }
+
+    /// Keep consuming the still-open stream after the other one closes, so its late halves can
+    /// still pair with items already waiting
+    pub fn with_drain_on_close(mut self, enabled: bool) -> Self {
+        self.drain_on_close = enabled;
+        self
+    }
+
+    pub fn metrics(&self) -> Arc<Metrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    pub async fn start(&self) -> Result<(), TrackerError> {
+        let (left_errors_tx, mut left_errors) = mpsc::unbounded_channel();
+        let (right_errors_tx, mut right_errors) = mpsc::unbounded_channel();
+        let mut left_rx = self.left.spawn_with_errors(left_errors_tx);
+        let mut right_rx = self.right.spawn_with_errors(right_errors_tx);
+        let mut health = SourceHealth::new(self.max_source_errors);
+
+        let mut left_pending = Pending::default();
+        let mut right_pending = Pending::default();
+        let mut ticker = start_ticker(self.stats_interval);
+        let (mut left_open, mut right_open) = (true, true);
+
+        loop {
+            tokio::select! {
+                Some(error) = left_errors.recv() => health.failed(true, error)?,
+                Some(error) = right_errors.recv() => health.failed(false, error)?,
+                _ = next_tick(&mut ticker) => {
+                    info!(
+                        "📈 {} | waiting: {} left, {} right",
+                        self.metrics.summary(),
+                        left_pending.items.len(),
+                        right_pending.items.len()
+                    );
+                }
+                msg = left_rx.recv(), if left_open => {
+                    let Some(data) = msg else {
+                        left_open = false;
+                        if !self.drain_on_close || !right_open {
+                            break;
+                        }
+                        info!("left stream closed; draining right until it closes");
+                        continue;
+                    };
+                    self.metrics.left.record_value(&data);
+                    health.ok(true);
+                    self.receive(State::with_data(data), true, &mut left_pending, &mut right_pending)?;
+                }
+                msg = right_rx.recv(), if right_open => {
+                    let Some(data) = msg else {
+                        right_open = false;
+                        if !self.drain_on_close || !left_open {
+                            break;
+                        }
+                        info!("right stream closed; draining left until it closes");
+                        continue;
+                    };
+                    self.metrics.right.record_value(&data);
+                    health.ok(false);
+                    self.receive(State::with_data(data), false, &mut right_pending, &mut left_pending)?;
+                }
+            }
+        }
+
+        if !left_pending.items.is_empty() || !right_pending.items.is_empty() {
+            warn!("⚠️  never paired: {} left, {} right item(s)", left_pending.items.len(), right_pending.items.len());
+        }
+        Ok(())
+    }
+
+    /// Pair an item with its waiting other half, or park it until that arrives
+    fn receive(&self, state: State, is_left: bool, own: &mut Pending, other: &mut Pending) -> Result<(), TrackerError> {
+        let side = if is_left { "left" } else { "right" };
+        let Some(id) = self.id_extractor.extract_key(&state.data) else {
+            warn!("{side} item has no correlation id; skipped");
+            return Ok(());
+        };
+
+        match other.items.remove(&id) {
+            Some(waiting) if is_left => self.compare(&id, &state, &waiting),
+            Some(waiting) => self.compare(&id, &waiting, &state),
+            None => {
+                if let Some((dropped, _)) = own.park(id, state, self.max_pending) {
+                    warn!("{side} item {dropped} dropped unmatched (more than {} waiting)", self.max_pending);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn compare(&self, id: &str, left: &State, right: &State) -> Result<(), TrackerError> {
+        let round_trip = lag_millis(left.timestamp, right.timestamp);
+        self.metrics.record_latency(id, round_trip);
+        if round_trip < 0.0 {
+            info!("🔗 {id}: response arrived {:.1}ms before the request", -round_trip);
+        } else {
+            info!("🔗 {id}: round trip {round_trip:.1}ms");
+        }
+
+        let result = self.differ.compute_diff(&left.data, &right.data);
+        if !result.identical {
+            self.metrics.record_diff(result.ops_count);
+        }
+        self.differ.print_result("left", "right", &left.data, &right.data, &result);
+
+        if self.fail_on_type_change
+            && let Some(change) = find_type_change(&left.data, &right.data)
+        {
+            return Err(TrackerError::TypeChange(change));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use tracing::debug;
+
+use crate::domain::{SourceError, TrackerError};
+
+/// Consecutive errors reported by each source; a message from a side resets its count. With a
+/// limit, the tracker gives up once either side reaches it.
+#[derive(Debug, Default)]
+pub(crate) struct SourceHealth {
+    max:   Option<usize>,
+    left:  usize,
+    right: usize
+}
+
+impl SourceHealth {
+    pub(crate) fn new(max: Option<usize>) -> Self {
+        Self { max, ..Self::default() }
+    }
+
+    /// The side delivered a message, so it is healthy again
+    pub(crate) fn ok(&mut self, is_left: bool) {
+        *self.count(is_left) = 0;
+    }
+
+    pub(crate) fn failed(&mut self, is_left: bool, error: SourceError) -> Result<(), TrackerError> {
+        let max = self.max;
+        let count = self.count(is_left);
+        *count += 1;
+        debug!("{error} ({count} consecutive)");
+
+        match max {
+            Some(max) if *count >= max => Err(TrackerError::SourceFailed(error)),
+            _ => Ok(())
+        }
+    }
+
+    fn count(&mut self, is_left: bool) -> &mut usize {
+        if is_left { &mut self.left } else { &mut self.right }
+    }
+}
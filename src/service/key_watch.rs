@@ -0,0 +1,65 @@
+use std::collections::BTreeSet;
+
+use serde_json::Value as JsonValue;
+use tracing::warn;
+
+/// Messages per side sampled before deciding the alignment path never resolves
+const SAMPLE: usize = 10;
+/// Top-level fields listed in the warning
+const MAX_FIELDS_SHOWN: usize = 12;
+
+/// Notices an alignment path that yields no key for any of a side's first messages (e.g., a typo
+/// in `--align-by`), which would otherwise look like a stuck tracker, and warns once per side
+#[derive(Debug, Default)]
+pub(crate) struct MissingKeyWatch {
+    left:  SideSample,
+    right: SideSample
+}
+
+/// The first messages of one side
+#[derive(Debug, Default)]
+struct SideSample {
+    seen:   usize,
+    keyed:  usize,
+    /// Top-level fields of the sampled messages
+    fields: BTreeSet<String>
+}
+
+impl MissingKeyWatch {
+    /// Count one message; `describe` names where keys are read from and is only called to warn
+    pub(crate) fn observe(
+        &mut self,
+        is_left: bool,
+        state: &JsonValue,
+        has_key: bool,
+        describe: impl FnOnce() -> String
+    ) {
+        let sample = if is_left { &mut self.left } else { &mut self.right };
+        if sample.seen >= SAMPLE {
+            return;
+        }
+        sample.seen += 1;
+        sample.keyed += usize::from(has_key);
+        if let Some(map) = state.as_object() {
+            sample.fields.extend(map.keys().cloned());
+        }
+        if sample.seen < SAMPLE || sample.keyed > 0 {
+            return;
+        }
+
+        let side = if is_left { "left" } else { "right" };
+        let fields = match sample.fields.len() {
+            0 => "none (the messages aren't objects)".to_string(),
+            n if n > MAX_FIELDS_SHOWN => {
+                let shown: Vec<&str> = sample.fields.iter().take(MAX_FIELDS_SHOWN).map(String::as_str).collect();
+                format!("{}, ... ({n} in all)", shown.join(", "))
+            }
+            _ => sample.fields.iter().map(String::as_str).collect::<Vec<_>>().join(", ")
+        };
+        warn!(
+            "⚠️  none of the first {SAMPLE} {side} messages has an alignment key at {}; the path may be wrong. \
+             Top-level fields seen: {fields}",
+            describe()
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use tracing::info;
+
+/// When a message limit ends the session
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LimitMode {
+    /// Once both sides have delivered the limit (or closed)
+    #[default]
+    Both,
+    /// As soon as either side has delivered the limit
+    Either
+}
+
+/// States received per side against an optional cap; a side that reaches it stops being read
+#[derive(Debug, Default)]
+pub(crate) struct MessageLimit {
+    max:   Option<usize>,
+    mode:  LimitMode,
+    left:  usize,
+    right: usize
+}
+
+impl MessageLimit {
+    pub(crate) fn new(max: Option<usize>, mode: LimitMode) -> Self {
+        Self { max, mode, ..Self::default() }
+    }
+
+    pub(crate) fn received(&mut self, is_left: bool) {
+        if is_left { self.left += 1 } else { self.right += 1 }
+    }
+
+    /// Whether the side has delivered its share
+    pub(crate) fn reached(&self, is_left: bool) -> bool {
+        let count = if is_left { self.left } else { self.right };
+        self.max.is_some_and(|max| count >= max)
+    }
+
+    /// Whether either side has hit the limit, i.e., the session was cut short by it
+    pub(crate) fn hit(&self) -> bool {
+        self.reached(true) || self.reached(false)
+    }
+
+    /// Stop reading each side that has reached the limit; true once the session should end
+    pub(crate) fn close_reached(&self, left_open: &mut bool, right_open: &mut bool) -> bool {
+        let Some(max) = self.max else {
+            return false;
+        };
+        for (is_left, open) in [(true, &mut *left_open), (false, &mut *right_open)] {
+            if *open && self.reached(is_left) {
+                *open = false;
+                info!("{} reached the {max}-message limit", if is_left { "left" } else { "right" });
+            }
+        }
+        match self.mode {
+            LimitMode::Both => self.hit() && !*left_open && !*right_open,
+            LimitMode::Either => self.hit()
+        }
+    }
+}
@@ -1,5 +1,20 @@
 mod aligned;
+mod correlate;
+mod health;
+mod key_watch;
+mod limit;
+mod report_pool;
+mod round;
+mod spill;
+mod swap;
+mod tail;
+mod throttle;
 mod tracker;
+mod triage;
 
 pub use aligned::*;
+pub use correlate::*;
+pub use limit::LimitMode;
+pub use tail::*;
 pub use tracker::*;
+pub use triage::*;
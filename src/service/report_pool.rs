@@ -0,0 +1,70 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+
+use crate::port::Reporter;
+
+/// Writes round reports on blocking threads, at most `size` at a time, so building and writing a
+/// large report never stalls the tracker's select loop. Once every writer is busy, `submit` waits
+/// for one to free up: reports can't pile up in memory faster than they are written.
+pub(crate) struct ReportPool {
+    slots:      Arc<Semaphore>,
+    size:       usize,
+    /// Last stamp handed out and how often it has been reused
+    last_stamp: Mutex<(String, usize)>
+}
+
+impl ReportPool {
+    pub(crate) fn new(size: usize) -> Self {
+        // `drain` acquires every permit at once, which takes a u32
+        let size = size.clamp(1, Semaphore::MAX_PERMITS.min(u32::MAX as usize));
+        Self { slots: Arc::new(Semaphore::new(size)), size, last_stamp: Mutex::default() }
+    }
+
+    /// Timestamp for a round's report paths, suffixed ("_1", "_2", ...) when several rounds end
+    /// within the same second so that no two writers ever share a file
+    pub(crate) fn stamp(&self) -> String {
+        let now = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
+        let mut last = self.last_stamp.lock().unwrap_or_else(|e| e.into_inner());
+        if last.0 == now {
+            last.1 += 1;
+            format!("{now}_{}", last.1)
+        } else {
+            *last = (now.clone(), 0);
+            now
+        }
+    }
+
+    /// Write `reporter` to `path` in the background; `quiet` suppresses the console lines
+    pub(crate) async fn submit(&self, reporter: Box<dyn Reporter>, path: String, quiet: bool) {
+        let Ok(permit) = Arc::clone(&self.slots).acquire_owned().await else { return };
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            match reporter.generate(&path) {
+                // Logged rather than printed, so stdout holds only diffs when they are machine-readable
+                Ok(()) if !quiet => info!("📄 Round report: {}", path),
+                Ok(()) => {}
+                // Quiet only hides progress; a report that wasn't written is always reported
+                Err(e) => warn!("⚠️  Failed to generate round report {}: {}", path, e)
+            }
+        });
+    }
+
+    /// Wait until every submitted report has been written
+    pub(crate) async fn drain(&self) {
+        let _all = self.slots.acquire_many(self.size as u32).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn oversized_pools_still_drain() {
+        let pool = ReportPool::new(usize::MAX);
+        assert_eq!(pool.size, u32::MAX as usize);
+        tokio::time::timeout(std::time::Duration::from_secs(1), pool.drain()).await.unwrap();
+    }
+}
@@ -0,0 +1,70 @@
+/// Round-end progress of both sides. The flags are only changed through `&mut self`, and
+/// `finish` counts the round and resets them in one step, so a round can't be counted twice or
+/// leave a stale flag behind when both sides signal in quick succession.
+#[derive(Debug, Default)]
+pub(crate) struct RoundState {
+    left_done:  bool,
+    right_done: bool,
+    completed:  usize
+}
+
+impl RoundState {
+    /// Record that one side saw the round-end signal
+    pub(crate) fn mark(&mut self, is_left: bool) {
+        if is_left {
+            self.left_done = true;
+        } else {
+            self.right_done = true;
+        }
+    }
+
+    pub(crate) fn left_done(&self) -> bool {
+        self.left_done
+    }
+
+    pub(crate) fn right_done(&self) -> bool {
+        self.right_done
+    }
+
+    pub(crate) fn both_done(&self) -> bool {
+        self.left_done && self.right_done
+    }
+
+    /// If both sides are done, count the round, reset for the next one and return its number
+    pub(crate) fn finish(&mut self) -> Option<usize> {
+        if !self.both_done() {
+            return None;
+        }
+        self.left_done = false;
+        self.right_done = false;
+        self.completed += 1;
+        Some(self.completed)
+    }
+
+    pub(crate) fn completed(&self) -> usize {
+        self.completed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_round_finishes_once_both_sides_are_done() {
+        let mut round = RoundState::default();
+        round.mark(true);
+        round.mark(true);
+        assert_eq!(round.finish(), None);
+        round.mark(false);
+        assert!(round.both_done());
+        assert_eq!(round.finish(), Some(1));
+        assert_eq!(round.finish(), None);
+        assert!(!round.left_done() && !round.right_done());
+
+        round.mark(false);
+        round.mark(true);
+        assert_eq!(round.finish(), Some(2));
+        assert_eq!(round.completed(), 2);
+    }
+}
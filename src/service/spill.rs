@@ -0,0 +1,265 @@
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Mutex
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use tracing::info;
+
+use crate::domain::State;
+
+/// Round buffer that keeps the newest `window` states in memory. Without a spill directory older
+/// states are dropped; with one they're appended to an NDJSON file, so a round too large for memory
+/// is still compared in full: only keys and arrival times stay in memory (`outline()`), and
+/// payloads are read back one at a time (`get()`, `iter()`).
+#[derive(Debug)]
+pub(crate) struct SpillingStateBuffer {
+    recent: VecDeque<State>,
+    window: usize,
+    /// Where older states go once the window is full: (directory, side name)
+    target: Option<(PathBuf, &'static str)>,
+    spill:  Option<Spill>
+}
+
+/// The on-disk part of a buffer; the file is removed when the buffer is dropped
+#[derive(Debug)]
+struct Spill {
+    path:    PathBuf,
+    file:    File,
+    /// Bytes written so far, i.e., where the next line starts
+    len:     u64,
+    /// Every spilled state without its payload, in order
+    entries: Vec<SpillEntry>,
+    /// Read handle for `get`, opened on first use
+    reader:  Mutex<Option<BufReader<File>>>
+}
+
+/// What stays in memory of a spilled state
+#[derive(Debug)]
+struct SpillEntry {
+    offset:    u64,
+    key:       Option<String>,
+    timestamp: DateTime<Utc>
+}
+
+/// One spilled state as an NDJSON line
+#[derive(Serialize, Deserialize)]
+struct SpilledState {
+    data:   JsonValue,
+    key:    Option<String>,
+    /// Receive time in microseconds since the epoch
+    micros: i64
+}
+
+impl SpillingStateBuffer {
+    pub(crate) fn new(window: usize) -> Self {
+        Self { recent: VecDeque::new(), window, target: None, spill: None }
+    }
+
+    /// Spill states that fall out of the in-memory window to a file in `dir` instead of dropping
+    /// them
+    pub(crate) fn with_spill_dir(mut self, dir: &Path, side: &'static str) -> Self {
+        self.target = Some((dir.to_path_buf(), side));
+        self
+    }
+
+    pub(crate) fn push(&mut self, state: State) -> io::Result<()> {
+        self.recent.push_back(state);
+        if self.recent.len() > self.window
+            && let Some(oldest) = self.recent.pop_front()
+            && self.target.is_some()
+        {
+            self.spill(oldest)?;
+        }
+        Ok(())
+    }
+
+    fn spill(&mut self, state: State) -> io::Result<()> {
+        if self.spill.is_none()
+            && let Some((dir, side)) = &self.target
+        {
+            fs::create_dir_all(dir)?;
+            let path = dir.join(format!("pica-spill-{side}-{}.ndjson", uuid::Uuid::new_v4()));
+            let file = OpenOptions::new().create(true).append(true).open(&path)?;
+            info!("💾 {} round exceeds {} states; spilling older ones to {}", side, self.window, path.display());
+            self.spill = Some(Spill { path, file, len: 0, entries: Vec::new(), reader: Mutex::new(None) });
+        }
+        let Some(spill) = &mut self.spill else {
+            return Ok(());
+        };
+
+        let record = SpilledState {
+            data:   state.data,
+            key:    state.alignment_key,
+            micros: state.timestamp.timestamp_micros()
+        };
+        // One write per line, so a reader opening the file sees only whole records
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+        spill.file.write_all(&line)?;
+        spill.entries.push(SpillEntry { offset: spill.len, key: record.key, timestamp: state.timestamp });
+        spill.len += line.len() as u64;
+        Ok(())
+    }
+
+    pub(crate) fn latest(&self) -> Option<&State> {
+        self.recent.back()
+    }
+
+    pub(crate) fn latest_alignment_key(&self) -> Option<&str> {
+        self.latest().and_then(|s| s.alignment_key.as_deref())
+    }
+
+    /// Every buffered state in arrival order with its key and arrival time but without its payload
+    /// (`null`), e.g. to pair states up before loading them; positions are those of `get`
+    pub(crate) fn outline(&self) -> Vec<State> {
+        let spilled = self.spilled().iter().map(|entry| State {
+            data:          JsonValue::Null,
+            alignment_key: entry.key.clone(),
+            timestamp:     entry.timestamp
+        });
+        let recent = self.recent.iter().map(|state| State {
+            data:          JsonValue::Null,
+            alignment_key: state.alignment_key.clone(),
+            timestamp:     state.timestamp
+        });
+        spilled.chain(recent).collect()
+    }
+
+    /// The state at `index` in arrival order, read back from disk when it was spilled
+    pub(crate) fn get(&self, index: usize) -> io::Result<Cow<'_, State>> {
+        let spilled = self.spilled().len();
+        if index >= spilled {
+            return self.recent.get(index - spilled).map(Cow::Borrowed).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, format!("no buffered state at position {index}"))
+            });
+        }
+
+        let Some(spill) = &self.spill else { unreachable!("spilled states imply a spill file") };
+        let mut reader = spill.reader.lock().unwrap_or_else(|e| e.into_inner());
+        if reader.is_none() {
+            *reader = Some(BufReader::new(File::open(&spill.path)?));
+        }
+        let Some(reader) = reader.as_mut() else { unreachable!("the reader was just opened") };
+        reader.seek(SeekFrom::Start(spill.entries[index].offset))?;
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        Ok(Cow::Owned(read_record(&line)?))
+    }
+
+    /// Every buffered state in arrival order, the spilled ones streamed back from disk one by one
+    pub(crate) fn iter(&self) -> io::Result<impl Iterator<Item = io::Result<State>> + '_> {
+        let spilled = match self.spill.as_ref().filter(|spill| !spill.entries.is_empty()) {
+            Some(spill) => Some(BufReader::new(File::open(&spill.path)?).lines().take(spill.entries.len())),
+            None => None
+        };
+        let spilled = spilled.into_iter().flatten().map(|line| read_record(&line?));
+        Ok(spilled.chain(self.recent.iter().cloned().map(Ok)))
+    }
+
+    fn spilled(&self) -> &[SpillEntry] {
+        self.spill.as_ref().map_or(&[], |spill| &spill.entries)
+    }
+
+    pub(crate) fn clear(&mut self) -> io::Result<()> {
+        self.recent.clear();
+        if let Some(spill) = &mut self.spill {
+            spill.file.set_len(0)?;
+            spill.len = 0;
+            spill.entries.clear();
+        }
+        Ok(())
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.recent.len() + self.spilled().len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn read_record(line: &str) -> io::Result<State> {
+    let record: SpilledState = serde_json::from_str(line)?;
+    let timestamp = DateTime::from_timestamp_micros(record.micros).unwrap_or_default();
+    Ok(State { data: record.data, alignment_key: record.key, timestamp })
+}
+
+impl Drop for Spill {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn spilling(name: &str) -> SpillingStateBuffer {
+        let dir = std::env::temp_dir().join(format!("pica-spill-test-{name}-{}", std::process::id()));
+        SpillingStateBuffer::new(2).with_spill_dir(&dir, "left")
+    }
+
+    fn push_all(buffer: &mut SpillingStateBuffer, count: usize) {
+        for i in 0..count {
+            buffer.push(State::new(json!({"i": i}), Some(format!("k{i}")))).unwrap();
+        }
+    }
+
+    #[test]
+    fn outlines_keep_keys_but_not_payloads() {
+        let mut buffer = spilling("outline");
+        push_all(&mut buffer, 5);
+        let outline = buffer.outline();
+        assert_eq!(outline.len(), 5);
+        assert!(outline.iter().all(|state| state.data.is_null()));
+        let keys: Vec<_> = outline.iter().map(|state| state.alignment_key.as_deref().unwrap()).collect();
+        assert_eq!(keys, ["k0", "k1", "k2", "k3", "k4"]);
+    }
+
+    #[test]
+    fn get_reads_spilled_states_back_in_any_order() {
+        let mut buffer = spilling("get");
+        push_all(&mut buffer, 5);
+        for i in [4, 0, 2, 1, 3] {
+            assert_eq!(buffer.get(i).unwrap().data, json!({"i": i}));
+        }
+        assert!(buffer.get(5).is_err());
+    }
+
+    #[test]
+    fn iter_streams_every_state_in_order() {
+        let mut buffer = spilling("iter");
+        push_all(&mut buffer, 5);
+        let data: Vec<_> = buffer.iter().unwrap().map(|state| state.unwrap().data["i"].clone()).collect();
+        assert_eq!(data, [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn clear_starts_a_fresh_round() {
+        let mut buffer = spilling("clear");
+        push_all(&mut buffer, 5);
+        buffer.clear().unwrap();
+        assert!(buffer.is_empty());
+        push_all(&mut buffer, 3);
+        assert_eq!(buffer.iter().unwrap().count(), 3);
+        assert_eq!(buffer.get(0).unwrap().data, json!({"i": 0}));
+    }
+
+    #[test]
+    fn without_a_spill_dir_old_states_are_dropped() {
+        let mut buffer = SpillingStateBuffer::new(2);
+        push_all(&mut buffer, 5);
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(buffer.get(0).unwrap().data, json!({"i": 3}));
+    }
+}
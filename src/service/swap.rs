@@ -0,0 +1,109 @@
+use std::collections::BTreeSet;
+
+use json_patch::PatchOperation;
+use serde_json::Value as JsonValue;
+use tracing::warn;
+
+/// Comparisons to skip before sampling, so start-up noise doesn't count
+const WARMUP: usize = 10;
+/// Comparisons sampled in both directions
+const SAMPLES: usize = 20;
+
+/// Heuristic that notices when the left/right URLs were probably connected the wrong way round.
+/// Op counts alone can't tell (a diff and its reverse are the same size), so it looks at direction:
+/// the right side is expected to add to what the left has, not drop from it. After a warm-up it
+/// collects the paths each direction removes and hints once if reversing the sides removes far
+/// fewer of them.
+#[derive(Debug, Default)]
+pub(crate) struct SwapDetector {
+    seen:            usize,
+    /// Paths the left has that the right lacks
+    forward_removed: BTreeSet<String>,
+    /// Paths the right has that the left lacks
+    reverse_removed: BTreeSet<String>,
+    done:            bool,
+    suspected:       bool
+}
+
+impl SwapDetector {
+    pub(crate) fn observe(&mut self, left: &JsonValue, right: &JsonValue) {
+        if self.done {
+            return;
+        }
+
+        self.seen += 1;
+        if self.seen <= WARMUP {
+            return;
+        }
+
+        self.forward_removed.extend(removed_paths(left, right));
+        self.reverse_removed.extend(removed_paths(right, left));
+
+        if self.seen == WARMUP + SAMPLES {
+            self.done = true;
+            let (forward, reverse) = (self.forward_removed.len(), self.reverse_removed.len());
+            // "Dramatically smaller": at most half the removals with the sides reversed
+            if forward > 0 && reverse * 2 <= forward {
+                self.suspected = true;
+                warn!(
+                    "🔀 streams may be swapped; fewer diffs with sides reversed (right lacks {forward} path(s) left \
+                     has, left lacks {reverse} over {SAMPLES} comparisons)"
+                );
+            }
+        }
+    }
+}
+
+/// Paths the patch from `from` to `to` removes
+fn removed_paths(from: &JsonValue, to: &JsonValue) -> impl Iterator<Item = String> {
+    json_patch::diff(from, to).0.into_iter().filter_map(|op| match op {
+        PatchOperation::Remove(op) => Some(op.path.to_string()),
+        _ => None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn run(left: &JsonValue, right: &JsonValue) -> SwapDetector {
+        let mut detector = SwapDetector::default();
+        for _ in 0..WARMUP + SAMPLES {
+            detector.observe(left, right);
+        }
+        detector
+    }
+
+    #[test]
+    fn hints_when_the_left_side_has_the_newer_fields() {
+        let old = json!({"hp": 10});
+        let new = json!({"hp": 10, "shield": 5, "buffs": []});
+        assert!(run(&new, &old).suspected);
+    }
+
+    #[test]
+    fn stays_quiet_for_the_expected_direction() {
+        let old = json!({"hp": 10});
+        let new = json!({"hp": 10, "shield": 5, "buffs": []});
+        assert!(!run(&old, &new).suspected);
+    }
+
+    #[test]
+    fn stays_quiet_for_value_changes() {
+        assert!(!run(&json!({"hp": 10}), &json!({"hp": 12})).suspected);
+    }
+
+    #[test]
+    fn ignores_the_warm_up() {
+        let mut detector = SwapDetector::default();
+        for _ in 0..WARMUP {
+            detector.observe(&json!({"a": 1}), &json!({}));
+        }
+        for _ in 0..SAMPLES {
+            detector.observe(&json!({}), &json!({}));
+        }
+        assert!(!detector.suspected);
+    }
+}
@@ -0,0 +1,65 @@
+use anstream::println;
+use owo_colors::OwoColorize;
+use serde_json::Value as JsonValue;
+use tracing::info;
+
+use crate::{adapter::Theme, domain::TrackerError, port::StateSource};
+
+/// Prints every message from both sources as it arrives, pretty-printed and labeled with its side
+/// and arrival time. Nothing is aligned or diffed, which makes it handy for looking at a stream
+/// before choosing an `--align-by` path.
+pub struct RawTail<L: StateSource, R: StateSource> {
+    left:  L,
+    right: R,
+    theme: Theme
+}
+
+impl<L: StateSource, R: StateSource> RawTail<L, R> {
+    pub fn new(left: L, right: R) -> Self {
+        Self { left, right, theme: Theme::default() }
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Print messages until both sources close
+    pub async fn start(&self) -> Result<(), TrackerError> {
+        let mut left_rx = self.left.spawn();
+        let mut right_rx = self.right.spawn();
+        let (mut left_open, mut right_open) = (true, true);
+
+        while left_open || right_open {
+            tokio::select! {
+                msg = left_rx.recv(), if left_open => match msg {
+                    Some(value) => self.print("left", &value, true)?,
+                    None => {
+                        info!("left stream closed");
+                        left_open = false;
+                    }
+                },
+                msg = right_rx.recv(), if right_open => match msg {
+                    Some(value) => self.print("right", &value, false)?,
+                    None => {
+                        info!("right stream closed");
+                        right_open = false;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn print(&self, side: &str, value: &JsonValue, is_left: bool) -> Result<(), TrackerError> {
+        let time = chrono::Utc::now().format("%H:%M:%S%.3f");
+        let label = if is_left {
+            self.theme.left(&side).bold().to_string()
+        } else {
+            self.theme.right(&side).bold().to_string()
+        };
+        println!("{} {label}\n{}", time.dimmed(), serde_json::to_string_pretty(value)?);
+        Ok(())
+    }
+}
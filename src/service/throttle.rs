@@ -0,0 +1,35 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher}
+};
+
+use crate::domain::DiffResult;
+
+/// Remembers the last diff shown per alignment key, so a steady-state divergence that repeats on
+/// every message is printed once and then only counted
+#[derive(Debug, Default)]
+pub(crate) struct DiffThrottle {
+    /// Key -> (hash of the last diff, times it occurred in a row)
+    last: HashMap<String, (u64, usize)>
+}
+
+impl DiffThrottle {
+    /// Record a diff for `key`; returns how many times in a row it has now occurred when it's the
+    /// same as the previous one (so it can be collapsed), `None` when it's new
+    pub(crate) fn repeat(&mut self, key: &str, result: &DiffResult) -> Option<usize> {
+        let mut hasher = DefaultHasher::new();
+        serde_json::to_string(&result.patch).unwrap_or_default().hash(&mut hasher);
+        let hash = hasher.finish();
+
+        match self.last.get_mut(key) {
+            Some((last, count)) if *last == hash => {
+                *count += 1;
+                Some(*count)
+            }
+            _ => {
+                self.last.insert(key.to_string(), (hash, 1));
+                None
+            }
+        }
+    }
+}
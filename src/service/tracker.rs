@@ -1,60 +1,420 @@
+use std::{collections::VecDeque, sync::Arc, time::Duration};
+
 use serde_json::Value as JsonValue;
-use tracing::info;
+use tokio::{
+    sync::mpsc::{self, Receiver},
+    time::{Instant, Interval, interval_at}
+};
+use tracing::{debug, info, warn};
 
+use super::{LimitMode, health::SourceHealth, limit::MessageLimit, swap::SwapDetector};
 use crate::{
-    domain::TrackerError,
+    domain::{TrackerError, find_type_change},
+    metric::{AlignmentStatus, Metrics, Throughput},
     port::{Differ, StateSource}
 };
 
+/// Drain any messages already queued behind `first` and keep only the newest one, still counting
+/// the drained messages in `throughput`. Returns the newest message and how many were skipped.
+pub(crate) fn coalesce_latest(
+    first: JsonValue,
+    rx: &mut Receiver<JsonValue>,
+    throughput: &Throughput
+) -> (JsonValue, usize) {
+    let mut latest = first;
+    let mut skipped = 0;
+    while let Ok(next) = rx.try_recv() {
+        throughput.record_value(&next);
+        latest = next;
+        skipped += 1;
+    }
+    (latest, skipped)
+}
+
+/// Build a ticker for a periodic log line (first tick after one full period); a zero period,
+/// which `interval_at` rejects, means no ticker
+pub(crate) fn start_ticker(period: Option<Duration>) -> Option<Interval> {
+    period.filter(|period| !period.is_zero()).map(|period| interval_at(Instant::now() + period, period))
+}
+
+/// Wait for the next tick, or forever when no ticker is configured
+pub(crate) async fn next_tick(ticker: &mut Option<Interval>) {
+    match ticker {
+        Some(ticker) => {
+            ticker.tick().await;
+        }
+        None => std::future::pending().await
+    }
+}
+
+/// Comparisons since the last heartbeat
+#[derive(Debug, Default)]
+struct SyncCounter {
+    comparisons: usize,
+    differed:    usize
+}
+
+/// States queued in lockstep mode until the other side has one to pair with
+#[derive(Debug, Default)]
+struct Lockstep {
+    left:   VecDeque<JsonValue>,
+    right:  VecDeque<JsonValue>,
+    /// Pairs compared so far
+    paired: usize
+}
+
 pub struct Tracker<L: StateSource, R: StateSource, D: Differ> {
-    left:   L,
-    right:  R,
-    differ: D
+    left:                L,
+    right:               R,
+    differ:              D,
+    /// Skip to the newest queued message per side so a slow differ never falls behind
+    realtime:            bool,
+    /// Shared session counters (readable from outside while the tracker runs)
+    metrics:             Arc<Metrics>,
+    /// Print a throughput line at this interval
+    stats_interval:      Option<Duration>,
+    /// Stop with an error the first time a path changes JSON type between the two sides
+    fail_on_type_change: bool,
+    /// Don't log identical comparisons; print one "in sync" line per interval instead
+    heartbeat:           Option<Duration>,
+    /// Stop with an error once a source reports this many errors in a row
+    max_source_errors:   Option<usize>,
+    /// When one stream closes, keep reading the other until it closes too
+    drain_on_close:      bool,
+    /// Pair the i-th left state with the i-th right state instead of diffing the latest of each
+    lockstep:            bool,
+    /// Stop reading a side after this many states
+    message_limit:       Option<usize>,
+    /// Whether the limit ends the session once both sides reach it or either does
+    limit_mode:          LimitMode
 }
 
 impl<L: StateSource, R: StateSource, D: Differ> Tracker<L, R, D> {
     pub fn new(left: L, right: R, differ: D) -> Self {
-        Self { left, right, differ }
+        Self {
+            left,
+            right,
+            differ,
+            realtime: false,
+            metrics: Arc::new(Metrics::new()),
+            stats_interval: None,
+            fail_on_type_change: false,
+            heartbeat: None,
+            max_source_errors: None,
+            drain_on_close: false,
+            lockstep: false,
+            message_limit: None,
+            limit_mode: LimitMode::default()
+        }
+    }
+
+    pub fn with_realtime(mut self, enabled: bool) -> Self {
+        self.realtime = enabled;
+        self
+    }
+
+    pub fn with_stats_interval(mut self, interval: Duration) -> Self {
+        self.stats_interval = Some(interval);
+        self
+    }
+
+    pub fn with_fail_on_type_change(mut self, enabled: bool) -> Self {
+        self.fail_on_type_change = enabled;
+        self
+    }
+
+    pub fn with_heartbeat(mut self, interval: Duration) -> Self {
+        self.heartbeat = Some(interval);
+        self
+    }
+
+    /// Keep consuming the still-open stream after the other one closes, instead of stopping
+    pub fn with_drain_on_close(mut self, enabled: bool) -> Self {
+        self.drain_on_close = enabled;
+        self
+    }
+
+    /// Compare states record by record: whichever side runs ahead is queued until the other one
+    /// catches up, so replayed captures pair up however fast each is read. `--realtime` coalescing
+    /// doesn't apply.
+    pub fn with_lockstep(mut self, enabled: bool) -> Self {
+        self.lockstep = enabled;
+        self
+    }
+
+    /// Stop reading each side after `limit` states and end the session once both have delivered
+    /// them (or, with `LimitMode::Either`, once one has)
+    pub fn with_message_limit(mut self, limit: usize) -> Self {
+        self.message_limit = Some(limit);
+        self
+    }
+
+    pub fn with_limit_mode(mut self, mode: LimitMode) -> Self {
+        self.limit_mode = mode;
+        self
+    }
+
+    /// Share session counters created elsewhere (e.g., wired to a StatsD sink or to the sources)
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Give up with `TrackerError::SourceFailed` after `max` consecutive errors from one source
+    pub fn with_max_source_errors(mut self, max: usize) -> Self {
+        self.max_source_errors = Some(max);
+        self
+    }
+
+    pub fn metrics(&self) -> Arc<Metrics> {
+        Arc::clone(&self.metrics)
     }
 
     pub async fn start(&self) -> Result<(), TrackerError> {
-        let mut left_rx = self.left.spawn();
-        let mut right_rx = self.right.spawn();
+        let (left_errors_tx, mut left_errors) = mpsc::unbounded_channel();
+        let (right_errors_tx, mut right_errors) = mpsc::unbounded_channel();
+        let mut left_rx = self.left.spawn_with_errors(left_errors_tx);
+        let mut right_rx = self.right.spawn_with_errors(right_errors_tx);
+        let mut health = SourceHealth::new(self.max_source_errors);
 
         let mut left_state: Option<JsonValue> = None;
         let mut right_state: Option<JsonValue> = None;
+        let mut ticker = start_ticker(self.stats_interval);
+        let mut heartbeat = start_ticker(self.heartbeat);
+        let mut sync = SyncCounter::default();
+        let mut swap = SwapDetector::default();
+        let mut queued = Lockstep::default();
+        let coalesce = self.realtime && !self.lockstep;
+        let (mut left_open, mut right_open) = (true, true);
+        let mut limit = MessageLimit::new(self.message_limit, self.limit_mode);
 
-        loop {
-            tokio::select! {
-                msg = left_rx.recv() => {
-                    match msg {
-                        Some(state) => {
-                            left_state = Some(state);
-                            if let (Some(l), Some(r)) = (left_state.as_ref(), right_state.as_ref()) {
-                                self.differ.print_diff("left", "right", l, r);
-                            } else {
-                                info!("left updated; waiting for right before diffing");
+        // Errors end the session too, but only after the unpaired summary below
+        let session: Result<(), TrackerError> = async {
+            loop {
+                if limit.close_reached(&mut left_open, &mut right_open) {
+                    break;
+                }
+                tokio::select! {
+                    Some(error) = left_errors.recv() => health.failed(true, error)?,
+                    Some(error) = right_errors.recv() => health.failed(false, error)?,
+                    _ = next_tick(&mut ticker) => {
+                        info!("📈 {}", self.metrics.summary());
+                    }
+                    _ = next_tick(&mut heartbeat) => {
+                        if sync.differed == 0 {
+                            info!("💓 streams in sync ({} comparisons)", sync.comparisons);
+                        } else {
+                            info!("💓 {} comparisons, {} differed", sync.comparisons, sync.differed);
+                        }
+                        sync = SyncCounter::default();
+                    }
+                    msg = left_rx.recv(), if left_open => {
+                        match msg {
+                            Some(mut state) => {
+                                self.metrics.left.record_value(&state);
+                                health.ok(true);
+                                limit.received(true);
+                                if coalesce {
+                                    let skipped;
+                                    (state, skipped) = coalesce_latest(state, &mut left_rx, &self.metrics.left);
+                                    if skipped > 0 {
+                                        debug!("left coalesced {skipped} queued message(s)");
+                                    }
+                                }
+                                if self.lockstep {
+                                    queued.left.push_back(state);
+                                    self.compare_queued(&mut queued, &mut sync, &mut swap)?;
+                                    continue;
+                                }
+                                left_state = Some(state);
+                                if let (Some(l), Some(r)) = (left_state.as_ref(), right_state.as_ref()) {
+                                    swap.observe(l, r);
+                                    self.compare(l, r, &mut sync);
+                                    if self.fail_on_type_change && let Some(change) = find_type_change(l, r) {
+                                        return Err(TrackerError::TypeChange(change));
+                                    }
+                                } else {
+                                    info!("left updated; waiting for right before diffing");
+                                }
+                            }
+                            None => {
+                                left_open = false;
+                                if !self.drain_on_close || !right_open {
+                                    break;
+                                }
+                                info!("left stream closed; draining right until it closes");
                             }
                         }
-                        None => break,
                     }
-                }
-                msg = right_rx.recv() => {
-                    match msg {
-                        Some(state) => {
-                            right_state = Some(state);
-                            if let (Some(l), Some(r)) = (left_state.as_ref(), right_state.as_ref()) {
-                                self.differ.print_diff("left", "right", l, r);
-                            } else {
-                                info!("right updated; waiting for left before diffing");
+                    msg = right_rx.recv(), if right_open => {
+                        match msg {
+                            Some(mut state) => {
+                                self.metrics.right.record_value(&state);
+                                health.ok(false);
+                                limit.received(false);
+                                if coalesce {
+                                    let skipped;
+                                    (state, skipped) = coalesce_latest(state, &mut right_rx, &self.metrics.right);
+                                    if skipped > 0 {
+                                        debug!("right coalesced {skipped} queued message(s)");
+                                    }
+                                }
+                                if self.lockstep {
+                                    queued.right.push_back(state);
+                                    self.compare_queued(&mut queued, &mut sync, &mut swap)?;
+                                    continue;
+                                }
+                                right_state = Some(state);
+                                if let (Some(l), Some(r)) = (left_state.as_ref(), right_state.as_ref()) {
+                                    swap.observe(l, r);
+                                    self.compare(l, r, &mut sync);
+                                    if self.fail_on_type_change && let Some(change) = find_type_change(l, r) {
+                                        return Err(TrackerError::TypeChange(change));
+                                    }
+                                } else {
+                                    info!("right updated; waiting for left before diffing");
+                                }
+                            }
+                            None => {
+                                right_open = false;
+                                if !self.drain_on_close || !left_open {
+                                    break;
+                                }
+                                info!("right stream closed; draining left until it closes");
                             }
                         }
-                        None => break,
                     }
                 }
             }
+            Ok(())
         }
+        .await;
+
+        let unpaired = queued.left.len() + queued.right.len();
+        if unpaired > 0 {
+            for _ in 0..unpaired {
+                self.metrics.record_alignment(AlignmentStatus::Missing);
+            }
+            warn!(
+                "⚠️  {} left and {} right state(s) had no counterpart after {} pair(s)",
+                queued.left.len(),
+                queued.right.len(),
+                queued.paired
+            );
+        }
+        session
+    }
 
+    /// Lockstep mode: diff queued states pairwise, oldest first, while both sides have one
+    fn compare_queued(
+        &self,
+        queued: &mut Lockstep,
+        sync: &mut SyncCounter,
+        swap: &mut SwapDetector
+    ) -> Result<(), TrackerError> {
+        while let (Some(l), Some(r)) = (queued.left.front(), queued.right.front()) {
+            queued.paired += 1;
+            if self.heartbeat.is_none() {
+                info!("record {}:", queued.paired);
+            }
+            swap.observe(l, r);
+            self.metrics.record_alignment(AlignmentStatus::Aligned);
+            self.compare(l, r, sync);
+            if self.fail_on_type_change
+                && let Some(change) = find_type_change(l, r)
+            {
+                return Err(TrackerError::TypeChange(change));
+            }
+            queued.left.pop_front();
+            queued.right.pop_front();
+        }
         Ok(())
     }
+
+    /// Diff the latest pair; with a heartbeat, identical pairs are only counted
+    fn compare(&self, left: &JsonValue, right: &JsonValue, sync: &mut SyncCounter) {
+        sync.comparisons += 1;
+        let result = self.differ.compute_diff(left, right);
+        if !result.identical {
+            sync.differed += 1;
+            self.metrics.record_diff(result.ops_count);
+        } else if self.heartbeat.is_some() {
+            return;
+        }
+
+        self.differ.print_result("left", "right", left, right, &result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        adapter::{JsonPatchDiffer, VecSource},
+        domain::DiffResult
+    };
+
+    fn draining(enabled: bool) -> Tracker<VecSource, VecSource, JsonPatchDiffer> {
+        let left = VecSource::new("left", vec![json!({"n": 0})]);
+        let right =
+            VecSource::new("right", (0..3).map(|n| json!({"n": n})).collect()).with_delay(Duration::from_millis(20));
+        Tracker::new(left, right, JsonPatchDiffer::default()).with_drain_on_close(enabled)
+    }
+
+    #[tokio::test]
+    async fn sessions_that_fail_still_count_unpaired_states() {
+        let left = VecSource::new("left", (0..3).map(|n| json!({"n": n})).collect());
+        let right = VecSource::new("right", vec![json!({"n": "0"})]).with_delay(Duration::from_millis(20));
+        let tracker = Tracker::new(left, right, JsonPatchDiffer::default())
+            .with_lockstep(true)
+            .with_drain_on_close(true)
+            .with_fail_on_type_change(true);
+
+        assert!(matches!(tracker.start().await, Err(TrackerError::TypeChange(_))));
+        assert!(tracker.metrics().alignment(AlignmentStatus::Missing) > 0);
+    }
+
+    #[tokio::test]
+    async fn drain_on_close_reads_the_open_stream_to_its_end() {
+        let tracker = draining(true);
+        tracker.start().await.unwrap();
+        assert_eq!(tracker.metrics().right.messages(), 3);
+
+        let tracker = draining(false);
+        tracker.start().await.unwrap();
+        assert!(tracker.metrics().right.messages() < 3);
+    }
+
+    #[test]
+    fn each_pair_is_diffed_once_and_counted_by_the_differs_verdict() {
+        struct Counting(JsonPatchDiffer, AtomicUsize);
+
+        impl Differ for Counting {
+            fn compute_diff(&self, left: &JsonValue, right: &JsonValue) -> DiffResult {
+                self.1.fetch_add(1, Ordering::Relaxed);
+                self.0.compute_diff(left, right)
+            }
+        }
+
+        let differ =
+            Counting(JsonPatchDiffer::default().with_ignore_paths(vec!["/ts".to_string()]), AtomicUsize::new(0));
+        let tracker = Tracker::new(VecSource::new("left", vec![]), VecSource::new("right", vec![]), differ);
+        let mut sync = SyncCounter::default();
+        tracker.compare(&json!({"n": 1, "ts": 1}), &json!({"n": 1, "ts": 2}), &mut sync);
+        tracker.compare(&json!({"n": 1, "ts": 1}), &json!({"n": 2, "ts": 1}), &mut sync);
+
+        assert_eq!((sync.comparisons, sync.differed), (2, 1));
+        assert_eq!(tracker.differ.1.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn zero_periods_have_no_ticker() {
+        assert!(start_ticker(Some(Duration::ZERO)).is_none());
+        assert!(start_ticker(None).is_none());
+        assert!(start_ticker(Some(Duration::from_secs(1))).is_some());
+    }
 }
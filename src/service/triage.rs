@@ -0,0 +1,227 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::Arc,
+    time::Duration
+};
+
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use super::{
+    health::SourceHealth,
+    tracker::{next_tick, start_ticker}
+};
+use crate::{
+    domain::{State, TrackerError, TriageVerdict},
+    metric::Metrics,
+    port::{AlignmentKeyExtractor, Differ, StateSource}
+};
+
+/// Slots of the three streams in a `Waiting` entry
+const BASELINE: usize = 0;
+const LEFT: usize = 1;
+const RIGHT: usize = 2;
+const NAMES: [&str; 3] = ["baseline", "left", "right"];
+
+/// Compares two candidate streams against a known-good baseline: states are aligned by key across
+/// all three, and once a key has arrived on every stream it is diffed baseline↔left,
+/// baseline↔right and left↔right, and given a verdict saying which candidate regressed.
+pub struct TriageTracker<B: StateSource, L: StateSource, R: StateSource, D: Differ, E: AlignmentKeyExtractor> {
+    baseline:          B,
+    left:              L,
+    right:             R,
+    differ:            D,
+    key_extractor:     E,
+    /// Shared session counters; `left`/`right` count the candidates
+    metrics:           Arc<Metrics>,
+    /// Print a progress line at this interval
+    stats_interval:    Option<Duration>,
+    /// Stop with an error once a source reports this many errors in a row
+    max_source_errors: Option<usize>
+}
+
+/// States per key still waiting for the other streams, oldest first in each slot, so a key that
+/// repeats is compared occurrence by occurrence
+#[derive(Default)]
+struct Waiting {
+    keys: HashMap<String, [VecDeque<State>; 3]>
+}
+
+impl Waiting {
+    /// Add a state; once every stream has one for the key, the oldest of each is returned
+    fn add(&mut self, key: String, slot: usize, state: State) -> Option<[State; 3]> {
+        let slots = self.keys.entry(key.clone()).or_default();
+        slots[slot].push_back(state);
+        if slots.iter().any(VecDeque::is_empty) {
+            return None;
+        }
+
+        let complete = slots.each_mut().map(|queue| queue.pop_front().expect("checked non-empty"));
+        if slots.iter().all(VecDeque::is_empty) {
+            self.keys.remove(&key);
+        }
+        Some(complete)
+    }
+
+    /// States that never got a counterpart, per stream
+    fn counts(&self) -> [usize; 3] {
+        let mut counts = [0; 3];
+        for slots in self.keys.values() {
+            for (count, queue) in counts.iter_mut().zip(slots) {
+                *count += queue.len();
+            }
+        }
+        counts
+    }
+}
+
+impl<B: StateSource, L: StateSource, R: StateSource, D: Differ, E: AlignmentKeyExtractor> TriageTracker<B, L, R, D, E> {
+    pub fn new(baseline: B, left: L, right: R, differ: D, key_extractor: E) -> Self {
+        Self {
+            baseline,
+            left,
+            right,
+            differ,
+            key_extractor,
+            metrics: Arc::new(Metrics::new()),
+            stats_interval: None,
+            max_source_errors: None
+        }
+    }
+
+    pub fn with_stats_interval(mut self, interval: Duration) -> Self {
+        self.stats_interval = Some(interval);
+        self
+    }
+
+    /// Share session counters created elsewhere (e.g., wired to a StatsD sink or to the sources)
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Give up with `TrackerError::SourceFailed` after `max` consecutive errors from one source
+    pub fn with_max_source_errors(mut self, max: usize) -> Self {
+        self.max_source_errors = Some(max);
+        self
+    }
+
+    pub fn metrics(&self) -> Arc<Metrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// Run until all three streams close, then print how many keys got each verdict
+    pub async fn start(&self) -> Result<(), TrackerError> {
+        let (baseline_errors_tx, mut baseline_errors) = mpsc::unbounded_channel();
+        let (left_errors_tx, mut left_errors) = mpsc::unbounded_channel();
+        let (right_errors_tx, mut right_errors) = mpsc::unbounded_channel();
+        let mut baseline_rx = self.baseline.spawn_with_errors(baseline_errors_tx);
+        let mut left_rx = self.left.spawn_with_errors(left_errors_tx);
+        let mut right_rx = self.right.spawn_with_errors(right_errors_tx);
+        // The baseline gets its own counter, kept in the left slot
+        let mut baseline_health = SourceHealth::new(self.max_source_errors);
+        let mut health = SourceHealth::new(self.max_source_errors);
+
+        let mut waiting = Waiting::default();
+        let mut verdicts: BTreeMap<TriageVerdict, usize> = BTreeMap::new();
+        let mut ticker = start_ticker(self.stats_interval);
+        let mut open = [true; 3];
+
+        while open.iter().any(|&open| open) {
+            let (slot, msg) = tokio::select! {
+                Some(error) = baseline_errors.recv() => {
+                    baseline_health.failed(true, error)?;
+                    continue;
+                }
+                Some(error) = left_errors.recv() => {
+                    health.failed(true, error)?;
+                    continue;
+                }
+                Some(error) = right_errors.recv() => {
+                    health.failed(false, error)?;
+                    continue;
+                }
+                _ = next_tick(&mut ticker) => {
+                    let [baseline, left, right] = waiting.counts();
+                    info!(
+                        "📈 {} | waiting: {baseline} baseline, {left} left, {right} right",
+                        self.metrics.summary()
+                    );
+                    continue;
+                }
+                msg = baseline_rx.recv(), if open[BASELINE] => (BASELINE, msg),
+                msg = left_rx.recv(), if open[LEFT] => (LEFT, msg),
+                msg = right_rx.recv(), if open[RIGHT] => (RIGHT, msg)
+            };
+
+            let Some(data) = msg else {
+                open[slot] = false;
+                info!("{} stream closed", NAMES[slot]);
+                continue;
+            };
+            match slot {
+                BASELINE => baseline_health.ok(true),
+                LEFT => {
+                    self.metrics.left.record_value(&data);
+                    health.ok(true);
+                }
+                _ => {
+                    self.metrics.right.record_value(&data);
+                    health.ok(false);
+                }
+            }
+
+            let Some(key) = self.key_extractor.extract_key(&data) else {
+                warn!("{} state has no alignment key; skipped", NAMES[slot]);
+                continue;
+            };
+            if let Some(states) = waiting.add(key.clone(), slot, State::with_data(data)) {
+                *verdicts.entry(self.compare(&key, &states)).or_default() += 1;
+            }
+        }
+
+        let compared: usize = verdicts.values().sum();
+        let breakdown: Vec<String> = verdicts.iter().map(|(verdict, count)| format!("{count} {verdict}")).collect();
+        info!(
+            "🧪 triaged {compared} key(s): {}",
+            if breakdown.is_empty() { "none".into() } else { breakdown.join(", ") }
+        );
+
+        let [baseline, left, right] = waiting.counts();
+        if baseline + left + right > 0 {
+            warn!("⚠️  never seen on all three streams: {baseline} baseline, {left} left, {right} right state(s)");
+        }
+        Ok(())
+    }
+
+    /// Diff one key three ways, log its verdict and print the diffs that explain it
+    fn compare(&self, key: &str, [baseline, left, right]: &[State; 3]) -> TriageVerdict {
+        let baseline_left = self.differ.compute_diff(&baseline.data, &left.data);
+        let baseline_right = self.differ.compute_diff(&baseline.data, &right.data);
+        let left_right = self.differ.compute_diff(&left.data, &right.data);
+        let verdict = TriageVerdict::classify(&baseline_left, &baseline_right, &left_right);
+
+        info!(
+            "🧪 {key}: {verdict} (baseline→left {}, baseline→right {}, left→right {} change(s))",
+            baseline_left.ops_count, baseline_right.ops_count, left_right.ops_count
+        );
+        if verdict.is_match() {
+            return verdict;
+        }
+
+        self.metrics.record_diff(baseline_left.ops_count + baseline_right.ops_count);
+        match verdict {
+            TriageVerdict::LeftRegressed | TriageVerdict::BothChanged => {
+                self.differ.print_result("baseline", "left", &baseline.data, &left.data, &baseline_left);
+            }
+            TriageVerdict::RightRegressed => {
+                self.differ.print_result("baseline", "right", &baseline.data, &right.data, &baseline_right)
+            }
+            _ => {
+                self.differ.print_result("baseline", "left", &baseline.data, &left.data, &baseline_left);
+                self.differ.print_result("baseline", "right", &baseline.data, &right.data, &baseline_right);
+            }
+        }
+        verdict
+    }
+}
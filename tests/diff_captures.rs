@@ -0,0 +1,42 @@
+//! `diff-captures` on files: unreadable captures fail the run, and record-by-record comparisons
+//! honor the report and exit-code flags like keyed ones.
+
+use std::{fs, path::PathBuf, process::Command};
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pica-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn pica(args: &[&str]) -> Option<i32> {
+    Command::new(env!("CARGO_BIN_EXE_pica")).args(args).output().unwrap().status.code()
+}
+
+#[test]
+fn a_missing_capture_fails_the_run() {
+    let dir = scratch("missing-capture");
+    let right = dir.join("right.ndjson");
+    fs::write(&right, "{\"v\": 1}\n").unwrap();
+    let missing = dir.join("missing.ndjson");
+    let (missing, right) = (missing.to_str().unwrap(), right.to_str().unwrap());
+    assert_eq!(pica(&["diff-captures", missing, right]), Some(1));
+    assert_eq!(pica(&["diff-captures", missing, right, "--fail-on-diff"]), Some(2));
+}
+
+#[test]
+fn positional_comparisons_write_reports_and_fail_on_diff() {
+    let dir = scratch("positional-capture");
+    let (left, right) = (dir.join("left.ndjson"), dir.join("right.ndjson"));
+    fs::write(&left, "{\"v\": 1}\n{\"v\": 2}\n").unwrap();
+    fs::write(&right, "{\"v\": 1}\n{\"v\": 3}\n").unwrap();
+    let report = dir.join("report.json");
+    let (left, right, report_arg) = (left.to_str().unwrap(), right.to_str().unwrap(), report.to_str().unwrap());
+
+    assert_eq!(pica(&["diff-captures", left, right, "--report-json", report_arg, "--fail-on-diff"]), Some(1));
+    let report: serde_json::Value = serde_json::from_str(&fs::read_to_string(&report).unwrap()).unwrap();
+    assert_eq!(report["stats"]["matched"], 2);
+
+    assert_eq!(pica(&["diff-captures", left, left, "--fail-on-diff"]), Some(0));
+}
@@ -0,0 +1,37 @@
+//! Exit codes: 0 when nothing differed, 1 for divergence (with `--fail-on-diff` or `diff-files`)
+//! and 2 for flag and config errors. Other errors exit 1, or 2 with `--fail-on-diff`.
+
+use std::process::Command;
+
+fn exit_code(args: &[&str]) -> Option<i32> {
+    Command::new(env!("CARGO_BIN_EXE_pica")).args(args).output().unwrap().status.code()
+}
+
+#[test]
+fn flag_errors_exit_2() {
+    assert_eq!(exit_code(&["track", "a.ndjson", "b.ndjson", "--align-by", "t", "--report", "r.html"]), Some(2));
+    assert_eq!(exit_code(&["track", "a.ndjson", "b.ndjson", "--stats-interval", "0"]), Some(2));
+}
+
+#[test]
+fn config_errors_exit_2() {
+    assert_eq!(exit_code(&["track", "a.ndjson", "b.ndjson", "--config", "/nonexistent/pica.toml"]), Some(2));
+}
+
+#[test]
+fn session_errors_exit_2_only_with_fail_on_diff() {
+    let capture = std::env::temp_dir().join(format!("pica-exit-codes-{}.ndjson", std::process::id()));
+    std::fs::write(&capture, "{\"t\": \"bet\"}\n").unwrap();
+    let capture = capture.to_str().unwrap();
+    let require = ["track", capture, capture, "--align-by", "t", "--require-key", "end"];
+    assert_eq!(exit_code(&require), Some(1));
+    assert_eq!(exit_code(&[&require[..], &["--fail-on-diff"]].concat()), Some(2));
+
+}
+
+#[test]
+fn check_exits_1_when_a_source_delivers_nothing() {
+    let missing = std::env::temp_dir().join(format!("pica-check-{}.ndjson", std::process::id()));
+    let missing = missing.to_str().unwrap();
+    assert_eq!(exit_code(&["check", missing, missing, "--timeout-secs", "1"]), Some(1));
+}
@@ -0,0 +1,55 @@
+//! With `--engine json`, stdout must stay valid JSONL: status lines, entity headings and report
+//! paths go to stderr.
+
+use std::{fs, path::PathBuf, process::Command};
+
+/// A fresh scratch directory for one test
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pica-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn assert_json_lines(args: &[&str]) {
+    let output = Command::new(env!("CARGO_BIN_EXE_pica")).args(args).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.trim().is_empty(), "no output for {args:?}");
+    for line in stdout.lines() {
+        assert!(serde_json::from_str::<serde_json::Value>(line).is_ok(), "not JSON: {line:?}");
+    }
+}
+
+#[test]
+fn entity_diffs_are_json_lines() {
+    let dir = scratch("entities");
+    let (left, right) = (dir.join("left.json"), dir.join("right.json"));
+    fs::write(&left, r#"{"entities": [{"id": 1, "hp": 5}, {"id": 2, "hp": 7}, {"id": 3}]}"#).unwrap();
+    fs::write(&right, r#"{"entities": [{"id": 2, "hp": 6}, {"id": 1, "hp": 5}, {"id": 4}]}"#).unwrap();
+    let (left, right) = (left.to_str().unwrap(), right.to_str().unwrap());
+    assert_json_lines(&["diff-files", left, right, "--entities", "/entities", "--engine", "json"]);
+}
+
+#[test]
+fn round_reports_stay_off_stdout() {
+    let dir = scratch("rounds");
+    let (left, right) = (dir.join("left.ndjson"), dir.join("right.ndjson"));
+    fs::write(&left, "{\"t\": \"a\", \"v\": 1}\n{\"t\": \"end\"}\n{\"t\": \"a\", \"v\": 2}\n{\"t\": \"end\"}\n")
+        .unwrap();
+    fs::write(&right, "{\"t\": \"a\", \"v\": 1}\n{\"t\": \"end\"}\n{\"t\": \"a\", \"v\": 3}\n{\"t\": \"end\"}\n")
+        .unwrap();
+    let report = dir.join("report.html");
+    assert_json_lines(&[
+        "diff-captures",
+        left.to_str().unwrap(),
+        right.to_str().unwrap(),
+        "--align-by",
+        "t",
+        "--round-end",
+        "end",
+        "--report",
+        report.to_str().unwrap(),
+        "--engine",
+        "json"
+    ]);
+}
@@ -0,0 +1,46 @@
+//! `--output-dir`: relative artifact paths land inside it, absolute ones are used as given, and the
+//! default HTML report only stands in when no report of any kind is requested.
+
+use std::{fs, path::PathBuf, process::Command};
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pica-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn pica(args: &[&str]) -> Option<i32> {
+    Command::new(env!("CARGO_BIN_EXE_pica")).args(args).output().unwrap().status.code()
+}
+
+fn files(dir: &PathBuf) -> Vec<String> {
+    let mut names: Vec<_> =
+        fs::read_dir(dir).unwrap().map(|entry| entry.unwrap().file_name().into_string().unwrap()).collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn relative_artifacts_go_to_the_output_dir() {
+    let dir = scratch("output-dir");
+    let (left, right, out) = (dir.join("left.ndjson"), dir.join("right.ndjson"), dir.join("out"));
+    fs::write(&left, "{\"type\": \"bet\", \"v\": 1}\n{\"type\": \"end\"}\n").unwrap();
+    fs::write(&right, "{\"type\": \"bet\", \"v\": 2}\n{\"type\": \"end\"}\n").unwrap();
+    let (left, right, out) = (left.to_str().unwrap(), right.to_str().unwrap(), out.to_str().unwrap());
+    let track =
+        ["track", left, right, "--align-by", "type", "--round-end", "end", "--drain-on-close", "--output-dir", out];
+
+    let args = ["--report-json", "round.json", "--record", "capture", "--dump-buffers", "dumps"];
+    assert_eq!(pica(&[&track[..], &args].concat()), Some(0));
+    let written = files(&dir.join("out"));
+    assert!(written.contains(&"round.json".to_string()), "{written:?}");
+    assert!(written.contains(&"capture.left.ndjson".to_string()), "{written:?}");
+    assert!(written.contains(&"dumps".to_string()), "{written:?}");
+    assert!(!written.iter().any(|name| name.ends_with(".html")), "{written:?}");
+    assert!(!dir.join("round.json").exists() && !PathBuf::from("round.json").exists());
+
+    let absolute = dir.join("absolute.json");
+    assert_eq!(pica(&[&track[..], &["--report-json", absolute.to_str().unwrap()]].concat()), Some(0));
+    assert!(absolute.exists());
+}
@@ -0,0 +1,41 @@
+//! Round reports from `track` on capture files: `--report-json` writes the JSON session report and
+//! only takes ".json" paths, and ".gz" report paths need the `compression` feature.
+
+use std::{fs, path::PathBuf, process::Command};
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pica-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn pica(args: &[&str]) -> Option<i32> {
+    Command::new(env!("CARGO_BIN_EXE_pica")).args(args).output().unwrap().status.code()
+}
+
+#[test]
+fn report_json_writes_the_json_report_for_each_round() {
+    let dir = scratch("report-json");
+    let (left, right) = (dir.join("left.ndjson"), dir.join("right.ndjson"));
+    fs::write(&left, "{\"type\": \"bet\", \"v\": 1}\n{\"type\": \"end\"}\n").unwrap();
+    fs::write(&right, "{\"type\": \"bet\", \"v\": 2}\n{\"type\": \"end\"}\n").unwrap();
+    let (report, html) = (dir.join("round.json"), dir.join("round.html"));
+    let (left, right) = (left.to_str().unwrap(), right.to_str().unwrap());
+    let track = ["track", left, right, "--align-by", "type", "--round-end", "end", "--report-json"];
+
+    assert_eq!(pica(&[&track[..], &[report.to_str().unwrap()]].concat()), Some(0));
+    let report: serde_json::Value = serde_json::from_str(&fs::read_to_string(&report).unwrap()).unwrap();
+    assert_eq!(report["left"].as_array().map(Vec::len), Some(2));
+
+    assert_eq!(pica(&[&track[..], &[html.to_str().unwrap()]].concat()), Some(2));
+    assert!(!html.exists());
+}
+
+#[cfg(not(feature = "compression"))]
+#[test]
+fn gzip_reports_need_the_compression_feature() {
+    let track = ["track", "left.ndjson", "right.ndjson", "--align-by", "type", "--round-end", "end"];
+    assert_eq!(pica(&[&track[..], &["--report", "round.html.gz"]].concat()), Some(2));
+    assert_eq!(pica(&[&track[..], &["--report", "round.html", "--compact-report"]].concat()), Some(2));
+}